@@ -0,0 +1,453 @@
+// SPDX-FileCopyrightText: 2024 Jason Pena <jasonpena@awkless.com>
+// SPDX-License-Identifier: MIT
+
+//! Derive macros for configuration settings of `dotfile-ocd`.
+//!
+//! Generated code refers to items in `crate::config`, so these macros are only
+//! meant to be used inside of the `dotfile-ocd` crate itself.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Error, Expr, ExprLit, Fields, Ident, Lit,
+    LitStr, Meta, Result, Type,
+};
+
+/// Derive serialization and deserialization of configuration settings.
+///
+/// Always implements `SettingsValue`, such that the type can be nested in
+/// other settings. If a field is marked with `#[settings(key)]`, then
+/// `Settings` is also implemented, where the key field names the entry.
+///
+/// Doc comments of fields are used as descriptions in the generated JSON
+/// Schema. Fields that are neither [`Option`] nor have a default are required,
+/// and fail to deserialize when missing.
+///
+/// # Container Attributes
+///
+/// - `#[settings(inline)]`: Serialize as inline table instead of a table.
+///
+/// # Field Attributes
+///
+/// - `#[settings(key)]`: Field holds the key of the entry.
+/// - `#[settings(value)]`: Field holds the value of the entry as a whole,
+///   rather than being one of its keys.
+/// - `#[settings(rename = "name")]`: Use different key name for field.
+/// - `#[settings(default = expr)]`: Use `expr` when field is missing.
+/// - `#[settings(multiline)]`: Place each element of array on its own line.
+/// - `#[settings(comment = "text")]`: Place comment on its own line before
+///   field.
+/// - `#[settings(trailing = "text")]`: Place comment after value of field on
+///   the same line.
+///
+/// Comments cannot be placed in inline tables, because TOML does not allow
+/// them there.
+#[proc_macro_derive(Settings, attributes(settings))]
+pub fn derive_settings(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_settings(input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+struct Container {
+    inline: bool,
+}
+
+struct Field {
+    ident: Ident,
+    ty: Type,
+    name: String,
+    doc: String,
+    key: bool,
+    value: bool,
+    default: Option<Expr>,
+    multiline: bool,
+    comment: Option<String>,
+    trailing: Option<String>,
+}
+
+fn expand_settings(input: DeriveInput) -> Result<TokenStream2> {
+    let ident = &input.ident;
+    let container = parse_container(&input)?;
+    let fields = parse_fields(&input, &container)?;
+
+    let keys: Vec<&Field> = fields.iter().filter(|field| field.key).collect();
+    let values: Vec<&Field> = fields.iter().filter(|field| field.value).collect();
+    if keys.len() > 1 {
+        return Err(Error::new_spanned(ident, "only one field can be marked as key"));
+    }
+    if values.len() > 1 {
+        return Err(Error::new_spanned(ident, "only one field can be marked as value"));
+    }
+
+    let to_item = match values.first() {
+        Some(field) => expand_value_to_item(field),
+        None => expand_table_to_item(&container, &fields),
+    };
+    let from_item = match values.first() {
+        Some(field) => expand_value_from_item(field),
+        None => expand_table_from_item(&fields),
+    };
+
+    let schema = match values.first() {
+        Some(field) => expand_field_schema(field),
+        None => expand_table_schema(&fields),
+    };
+
+    let settings = match keys.first() {
+        Some(field) => expand_keyed_settings(ident, field),
+        None => quote! {},
+    };
+
+    Ok(quote! {
+        impl crate::config::SettingsValue for #ident {
+            fn to_item(&self) -> ::core::option::Option<::toml_edit::Item> {
+                #to_item
+            }
+
+            fn from_item(
+                item: &::toml_edit::Item,
+                path: &str,
+            ) -> ::core::result::Result<Self, crate::config::SettingsError> {
+                #from_item
+            }
+
+            fn schema() -> ::serde_json::Value {
+                #schema
+            }
+        }
+
+        #settings
+    })
+}
+
+fn parse_container(input: &DeriveInput) -> Result<Container> {
+    let mut container = Container { inline: false };
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("settings")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("inline") {
+                container.inline = true;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported settings container attribute"))
+            }
+        })?;
+    }
+
+    Ok(container)
+}
+
+fn parse_fields(input: &DeriveInput, container: &Container) -> Result<Vec<Field>> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(&input.ident, "settings can only be derived for structs"));
+    };
+
+    if let Fields::Unit = data.fields {
+        return Err(Error::new_spanned(&input.ident, "settings require named fields"));
+    }
+
+    let mut fields = Vec::new();
+    for field in &data.fields {
+        let Some(ident) = field.ident.clone() else {
+            return Err(Error::new_spanned(field, "settings require named fields"));
+        };
+        let mut parsed = Field {
+            name: ident.to_string(),
+            ident,
+            ty: field.ty.clone(),
+            doc: parse_doc(&field.attrs),
+            key: false,
+            value: false,
+            default: None,
+            multiline: false,
+            comment: None,
+            trailing: None,
+        };
+
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("settings")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("key") {
+                    parsed.key = true;
+                } else if meta.path.is_ident("value") {
+                    parsed.value = true;
+                } else if meta.path.is_ident("multiline") {
+                    parsed.multiline = true;
+                } else if meta.path.is_ident("rename") {
+                    parsed.name = meta.value()?.parse::<LitStr>()?.value();
+                } else if meta.path.is_ident("default") {
+                    parsed.default = Some(meta.value()?.parse::<Expr>()?);
+                } else if meta.path.is_ident("comment") || meta.path.is_ident("trailing") {
+                    if container.inline {
+                        return Err(meta.error("comments are not supported in inline tables"));
+                    }
+                    let comment = Some(meta.value()?.parse::<LitStr>()?.value());
+                    if meta.path.is_ident("comment") {
+                        parsed.comment = comment;
+                    } else {
+                        parsed.trailing = comment;
+                    }
+                } else {
+                    return Err(meta.error("unsupported settings field attribute"));
+                }
+
+                Ok(())
+            })?;
+        }
+
+        if parsed.comment.is_some() && (parsed.key || parsed.value) {
+            return Err(Error::new_spanned(field, "comment requires field with its own key"));
+        }
+
+        fields.push(parsed);
+    }
+
+    Ok(fields)
+}
+
+fn parse_doc(attrs: &[Attribute]) -> String {
+    let mut lines = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("doc")) {
+        if let Meta::NameValue(meta) = &attr.meta {
+            if let Expr::Lit(ExprLit { lit: Lit::Str(doc), .. }) = &meta.value {
+                let line = doc.value().trim().to_string();
+                if !line.is_empty() {
+                    lines.push(line);
+                }
+            }
+        }
+    }
+
+    lines.join(" ")
+}
+
+fn expand_format(field: &Field) -> TokenStream2 {
+    let multiline = if field.multiline {
+        quote! { crate::config::format_multiline(&mut item); }
+    } else {
+        quote! {}
+    };
+    let trailing = match &field.trailing {
+        Some(comment) => quote! { crate::config::format_trailing_comment(&mut item, #comment); },
+        None => quote! {},
+    };
+
+    quote! {
+        #multiline
+        #trailing
+    }
+}
+
+fn expand_value_to_item(field: &Field) -> TokenStream2 {
+    let ident = &field.ident;
+    let format = expand_format(field);
+    quote! {
+        let mut item = crate::config::SettingsValue::to_item(&self.#ident)?;
+        #format
+        ::core::option::Option::Some(item)
+    }
+}
+
+fn expand_table_to_item(container: &Container, fields: &[Field]) -> TokenStream2 {
+    let inserts = fields.iter().filter(|field| !field.key).map(|field| {
+        let ident = &field.ident;
+        let name = &field.name;
+        let format = expand_format(field);
+        let insert = if container.inline {
+            quote! {
+                if let ::core::result::Result::Ok(value) = item.into_value() {
+                    table.insert(#name, value);
+                }
+            }
+        } else {
+            let comment = match &field.comment {
+                Some(comment) => {
+                    quote! { crate::config::format_comment(&mut table, #name, #comment); }
+                }
+                None => quote! {},
+            };
+            quote! {
+                table.insert(#name, item);
+                #comment
+            }
+        };
+
+        quote! {
+            if let ::core::option::Option::Some(mut item) =
+                crate::config::SettingsValue::to_item(&self.#ident)
+            {
+                #format
+                #insert
+            }
+        }
+    });
+
+    if container.inline {
+        quote! {
+            let mut table = ::toml_edit::InlineTable::new();
+            #(#inserts)*
+            ::core::option::Option::Some(::toml_edit::Item::Value(
+                ::toml_edit::Value::InlineTable(table),
+            ))
+        }
+    } else {
+        quote! {
+            let mut table = ::toml_edit::Table::new();
+            #(#inserts)*
+            ::core::option::Option::Some(::toml_edit::Item::Table(table))
+        }
+    }
+}
+
+fn expand_assign(field: &Field) -> TokenStream2 {
+    let ident = &field.ident;
+    let ty = &field.ty;
+    let name = &field.name;
+    let fallback = match &field.default {
+        Some(default) => quote! {
+            settings.#ident = ::core::convert::Into::into(#default);
+        },
+        None => quote! {
+            if !<#ty as crate::config::SettingsValue>::is_optional() {
+                return ::core::result::Result::Err(crate::config::SettingsError::missing(
+                    ::std::format!("{}.{}", path, #name),
+                ));
+            }
+        },
+    };
+
+    quote! {
+        match table.get(#name) {
+            ::core::option::Option::Some(item) => {
+                let path = ::std::format!("{}.{}", path, #name);
+                settings.#ident = crate::config::SettingsValue::from_item(item, &path)?;
+            }
+            ::core::option::Option::None => { #fallback }
+        }
+    }
+}
+
+fn expand_value_from_item(field: &Field) -> TokenStream2 {
+    let ident = &field.ident;
+    quote! {
+        let mut settings = <Self as ::core::default::Default>::default();
+        settings.#ident = crate::config::SettingsValue::from_item(item, path)?;
+        ::core::result::Result::Ok(settings)
+    }
+}
+
+fn expand_table_from_item(fields: &[Field]) -> TokenStream2 {
+    let assigns = fields.iter().filter(|field| !field.key).map(expand_assign);
+
+    quote! {
+        let table = item.as_table_like().ok_or_else(|| {
+            crate::config::SettingsError::bad_type(path, "table", item)
+        })?;
+        let mut settings = <Self as ::core::default::Default>::default();
+        #(#assigns)*
+        ::core::result::Result::Ok(settings)
+    }
+}
+
+fn expand_field_schema(field: &Field) -> TokenStream2 {
+    let ty = &field.ty;
+    let doc = &field.doc;
+    let description = if doc.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            schema.insert("description".into(), ::serde_json::Value::from(#doc));
+        }
+    };
+    let default = match &field.default {
+        Some(default) => quote! {
+            schema.insert("default".into(), ::serde_json::Value::from(#default));
+        },
+        None => quote! {},
+    };
+
+    quote! {{
+        let mut schema = <#ty as crate::config::SettingsValue>::schema();
+        if let ::core::option::Option::Some(schema) = schema.as_object_mut() {
+            #description
+            #default
+        }
+        schema
+    }}
+}
+
+fn expand_table_schema(fields: &[Field]) -> TokenStream2 {
+    let properties = fields.iter().filter(|field| !field.key).map(|field| {
+        let ty = &field.ty;
+        let name = &field.name;
+        let schema = expand_field_schema(field);
+        let required = if field.default.is_none() {
+            quote! {
+                if !<#ty as crate::config::SettingsValue>::is_optional() {
+                    required.push(::serde_json::Value::from(#name));
+                }
+            }
+        } else {
+            quote! {}
+        };
+
+        quote! {
+            properties.insert(#name.into(), #schema);
+            #required
+        }
+    });
+
+    quote! {
+        let mut properties = ::serde_json::Map::new();
+        let mut required = ::std::vec::Vec::new();
+        #(#properties)*
+
+        let mut schema = ::serde_json::Map::new();
+        schema.insert("type".into(), ::serde_json::Value::from("object"));
+        schema.insert("properties".into(), ::serde_json::Value::Object(properties));
+        if !required.is_empty() {
+            schema.insert("required".into(), ::serde_json::Value::Array(required));
+        }
+        ::serde_json::Value::Object(schema)
+    }
+}
+
+fn expand_keyed_settings(ident: &Ident, key: &Field) -> TokenStream2 {
+    let key = &key.ident;
+    quote! {
+        impl crate::config::Settings for #ident {
+            fn to_toml(&self) -> (::toml_edit::Key, ::toml_edit::Item) {
+                let key = ::toml_edit::Key::new(&self.#key);
+                let value = crate::config::SettingsValue::to_item(self)
+                    .unwrap_or(::toml_edit::Item::None);
+                (key, value)
+            }
+        }
+
+        impl ::core::convert::TryFrom<(::toml_edit::Key, ::toml_edit::Item)> for #ident {
+            type Error = crate::config::SettingsError;
+
+            fn try_from(
+                entry: (::toml_edit::Key, ::toml_edit::Item),
+            ) -> ::core::result::Result<Self, Self::Error> {
+                let (key, value) = entry;
+                Self::try_from((&key, &value))
+            }
+        }
+
+        impl<'toml> ::core::convert::TryFrom<(&'toml ::toml_edit::Key, &'toml ::toml_edit::Item)>
+            for #ident
+        {
+            type Error = crate::config::SettingsError;
+
+            fn try_from(
+                entry: (&'toml ::toml_edit::Key, &'toml ::toml_edit::Item),
+            ) -> ::core::result::Result<Self, Self::Error> {
+                let (key, value) = entry;
+                let mut settings =
+                    <Self as crate::config::SettingsValue>::from_item(value, key.get())?;
+                settings.#key = ::core::convert::Into::into(key.get());
+                ::core::result::Result::Ok(settings)
+            }
+        }
+    }
+}
@@ -0,0 +1,16 @@
+// SPDX-FileCopyrightText: 2024 Jason Pena <jasonpena@awkless.com>
+// SPDX-License-Identifier: MIT
+
+#[path = "../support/config.rs"]
+mod config;
+
+use dotfile_ocd_derive::Settings;
+
+#[derive(Debug, Default, PartialEq, Settings)]
+#[settings(inline)]
+struct Remote {
+    #[settings(comment = "Where to clone from.")]
+    url: String,
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+// SPDX-FileCopyrightText: 2024 Jason Pena <jasonpena@awkless.com>
+// SPDX-License-Identifier: MIT
+
+#[path = "../support/config.rs"]
+mod config;
+
+use dotfile_ocd_derive::Settings;
+
+#[derive(Debug, Default, PartialEq, Settings)]
+struct RepoSettings {
+    #[settings(key)]
+    name: String,
+
+    #[settings(flatten)]
+    branch: String,
+}
+
+fn main() {}
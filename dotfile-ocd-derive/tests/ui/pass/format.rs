@@ -0,0 +1,54 @@
+// SPDX-FileCopyrightText: 2024 Jason Pena <jasonpena@awkless.com>
+// SPDX-License-Identifier: MIT
+
+#[path = "../support/config.rs"]
+mod config;
+
+use config::SettingsValue;
+use dotfile_ocd_derive::Settings;
+
+#[derive(Debug, Default, PartialEq, Settings)]
+#[settings(inline)]
+struct Remote {
+    url: String,
+    branch: Option<String>,
+}
+
+#[derive(Debug, Default, PartialEq, Settings)]
+struct RepoSettings {
+    #[settings(key)]
+    name: String,
+
+    #[settings(comment = "Branch to deploy.\nDefaults to master.")]
+    branch: String,
+
+    #[settings(trailing = "from origin")]
+    remote: Remote,
+
+    #[settings(multiline)]
+    depends: Vec<String>,
+}
+
+fn main() {
+    let repo = RepoSettings {
+        name: "vim".into(),
+        branch: "main".into(),
+        remote: Remote { url: "https://example.com/vim.git".into(), branch: None },
+        depends: vec!["sh".into(), "tmux".into()],
+    };
+    let expect = concat!(
+        "[repos.vim]\n",
+        "# Branch to deploy.\n",
+        "# Defaults to master.\n",
+        "branch = \"main\"\n",
+        "remote = { url = \"https://example.com/vim.git\" } # from origin\n",
+        "depends = [\n",
+        "    \"sh\",\n",
+        "    \"tmux\"\n",
+        "]\n",
+    );
+    assert_eq!(config::render(&repo), expect);
+
+    let schema = Remote::schema();
+    assert_eq!(schema["required"], serde_json::json!(["url"]));
+}
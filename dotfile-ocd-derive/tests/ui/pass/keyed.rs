@@ -0,0 +1,50 @@
+// SPDX-FileCopyrightText: 2024 Jason Pena <jasonpena@awkless.com>
+// SPDX-License-Identifier: MIT
+
+#[path = "../support/config.rs"]
+mod config;
+
+use config::{Settings, SettingsValue};
+use dotfile_ocd_derive::Settings;
+use toml_edit::{Item, Key};
+
+#[derive(Debug, Default, PartialEq, Settings)]
+struct RepoSettings {
+    #[settings(key)]
+    name: String,
+
+    /// Branch to deploy.
+    #[settings(default = "master")]
+    branch: String,
+
+    #[settings(rename = "bare-alias")]
+    bare_alias: Option<String>,
+}
+
+fn main() {
+    let (key, item) = "[vim]\nbare-alias = \"$HOME\"\n"
+        .parse::<toml_edit::DocumentMut>()
+        .unwrap()
+        .as_table()
+        .get_key_value("vim")
+        .map(|(key, item)| (key.clone(), item.clone()))
+        .unwrap();
+    let repo = RepoSettings::try_from((key, item)).unwrap();
+    let expect = RepoSettings {
+        name: "vim".into(),
+        branch: "master".into(),
+        bare_alias: Some("$HOME".into()),
+    };
+    assert_eq!(repo, expect);
+
+    let (key, item): (Key, Item) = repo.to_toml();
+    assert_eq!(key.get(), "vim");
+    assert_eq!(item["branch"].as_str(), Some("master"));
+    assert_eq!(item["bare-alias"].as_str(), Some("$HOME"));
+    assert!(item.get("name").is_none());
+
+    let schema = RepoSettings::schema();
+    assert_eq!(schema["properties"]["branch"]["description"], "Branch to deploy.");
+    assert_eq!(schema["properties"]["branch"]["default"], "master");
+    assert!(schema.get("required").is_none());
+}
@@ -0,0 +1,34 @@
+// SPDX-FileCopyrightText: 2024 Jason Pena <jasonpena@awkless.com>
+// SPDX-License-Identifier: MIT
+
+#[path = "../support/config.rs"]
+mod config;
+
+use config::SettingsValue;
+use dotfile_ocd_derive::Settings;
+use toml_edit::DocumentMut;
+
+#[derive(Debug, Default, PartialEq, Settings)]
+struct BootstrapSettings {
+    clone: String,
+
+    #[settings(default = "master")]
+    branch: String,
+
+    os: Option<String>,
+}
+
+fn main() {
+    let doc = "branch = \"main\"\n".parse::<DocumentMut>().unwrap();
+    let result = BootstrapSettings::from_item(doc.as_item(), "vim.bootstrap");
+    assert_eq!(result.unwrap_err().path, "vim.bootstrap.clone");
+
+    let doc = "clone = \"https://some/url\"\n".parse::<DocumentMut>().unwrap();
+    let result = BootstrapSettings::from_item(doc.as_item(), "vim.bootstrap").unwrap();
+    let expect =
+        BootstrapSettings { clone: "https://some/url".into(), branch: "master".into(), os: None };
+    assert_eq!(result, expect);
+
+    let schema = BootstrapSettings::schema();
+    assert_eq!(schema["required"], serde_json::json!(["clone"]));
+}
@@ -0,0 +1,27 @@
+// SPDX-FileCopyrightText: 2024 Jason Pena <jasonpena@awkless.com>
+// SPDX-License-Identifier: MIT
+
+#[path = "../support/config.rs"]
+mod config;
+
+use config::SettingsValue;
+use dotfile_ocd_derive::Settings;
+use toml_edit::{Item, Value};
+
+#[derive(Debug, Default, PartialEq, Settings)]
+struct Targets {
+    #[settings(value)]
+    targets: Vec<String>,
+}
+
+fn main() {
+    let item = Item::Value(Value::from_iter(["vim", "tmux"]));
+    let targets = Targets::from_item(&item, "targets").unwrap();
+    assert_eq!(targets, Targets { targets: vec!["vim".into(), "tmux".into()] });
+
+    let item = targets.to_item().unwrap();
+    assert_eq!(item.to_string(), r#"["vim", "tmux"]"#);
+
+    let error = Targets::from_item(&Item::Value(Value::from("vim")), "targets").unwrap_err();
+    assert_eq!(error.path, "targets");
+}
@@ -0,0 +1,129 @@
+// SPDX-FileCopyrightText: 2024 Jason Pena <jasonpena@awkless.com>
+// SPDX-License-Identifier: MIT
+
+// Minimal stand-in for `crate::config` of `dotfile-ocd`, which generated code
+// refers to.
+
+#![allow(dead_code)]
+
+use serde_json::{json, Value as JsonValue};
+use toml_edit::{Array, Item, Key, Table, Value};
+
+pub trait Settings: SettingsValue + TryFrom<(Key, Item), Error = SettingsError> {
+    fn to_toml(&self) -> (Key, Item);
+}
+
+pub trait SettingsValue: Sized {
+    fn to_item(&self) -> Option<Item>;
+
+    fn from_item(item: &Item, path: &str) -> Result<Self, SettingsError>;
+
+    fn schema() -> JsonValue;
+
+    fn is_optional() -> bool {
+        false
+    }
+}
+
+impl SettingsValue for String {
+    fn to_item(&self) -> Option<Item> {
+        Some(Item::Value(Value::from(self)))
+    }
+
+    fn from_item(item: &Item, path: &str) -> Result<Self, SettingsError> {
+        item.as_str().map(Into::into).ok_or_else(|| SettingsError::bad_type(path, "string", item))
+    }
+
+    fn schema() -> JsonValue {
+        json!({ "type": "string" })
+    }
+}
+
+impl<T: SettingsValue> SettingsValue for Option<T> {
+    fn to_item(&self) -> Option<Item> {
+        self.as_ref().and_then(T::to_item)
+    }
+
+    fn from_item(item: &Item, path: &str) -> Result<Self, SettingsError> {
+        T::from_item(item, path).map(Some)
+    }
+
+    fn schema() -> JsonValue {
+        T::schema()
+    }
+
+    fn is_optional() -> bool {
+        true
+    }
+}
+
+impl<T: SettingsValue> SettingsValue for Vec<T> {
+    fn to_item(&self) -> Option<Item> {
+        let array = self
+            .iter()
+            .filter_map(|value| value.to_item().and_then(|item| item.into_value().ok()))
+            .collect::<Array>();
+        Some(Item::Value(Value::Array(array)))
+    }
+
+    fn from_item(item: &Item, path: &str) -> Result<Self, SettingsError> {
+        let array = item.as_array().ok_or_else(|| SettingsError::bad_type(path, "array", item))?;
+        array.iter().map(|value| T::from_item(&Item::Value(value.clone()), path)).collect()
+    }
+
+    fn schema() -> JsonValue {
+        json!({ "type": "array", "items": T::schema() })
+    }
+}
+
+#[derive(Debug)]
+pub struct SettingsError {
+    pub path: String,
+}
+
+impl SettingsError {
+    pub fn bad_type(path: impl Into<String>, _expect: &'static str, _item: &Item) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn missing(path: impl Into<String>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+pub fn format_multiline(item: &mut Item) {
+    if let Some(array) = item.as_array_mut() {
+        let len = array.len();
+        for (idx, value) in array.iter_mut().enumerate() {
+            let decor = value.decor_mut();
+            decor.set_prefix("\n    ");
+            if idx + 1 == len {
+                decor.set_suffix("\n");
+            }
+        }
+    }
+}
+
+pub fn format_comment(table: &mut Table, key: &str, comment: &str) {
+    let prefix = comment.lines().map(|line| format!("# {line}\n")).collect::<String>();
+    if let Some(mut key) = table.key_mut(key) {
+        key.leaf_decor_mut().set_prefix(prefix);
+    }
+}
+
+pub fn format_trailing_comment(item: &mut Item, comment: &str) {
+    if let Some(value) = item.as_value_mut() {
+        value.decor_mut().set_suffix(format!(" # {comment}"));
+    }
+}
+
+/// Render entry as it would appear in a configuration file.
+pub fn render(settings: &impl Settings) -> String {
+    let (key, item) = settings.to_toml();
+    let mut table = Table::new();
+    table.set_implicit(true);
+    table.insert(key.get(), item);
+    let mut doc = toml_edit::DocumentMut::new();
+    doc.insert("repos", Item::Table(table));
+    doc.to_string()
+}
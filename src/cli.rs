@@ -88,6 +88,15 @@ pub enum CommandSet {
     /// Commit changes to all repositories.
     Commit(CommitOptions),
 
+    /// Manage configuration files.
+    Config(ConfigOptions),
+
+    /// Show dependency graph of repositories.
+    Graph(GraphOptions),
+
+    /// Record exact commits of all repositories into lockfile.
+    Lock,
+
     /// Run user's Git binary on target repository.
     #[command(external_subcommand)]
     Git(Vec<OsString>),
@@ -123,6 +132,10 @@ pub struct RemoveOptions {
 pub struct DeployOptions {
     #[arg(num_args = 1.., value_delimiter = ',')]
     pub repos: Vec<String>,
+
+    /// Check out exact commits recorded in lockfile.
+    #[arg(long)]
+    pub locked: bool,
 }
 
 #[derive(Args, Debug)]
@@ -142,6 +155,10 @@ pub struct StatusOptions {
     /// Give a short status report.
     #[arg(long, short)]
     pub terse: bool,
+
+    /// Show untracked files, even in bare-alias repositories.
+    #[arg(long, short)]
+    pub untracked: bool,
 }
 
 #[derive(Args, Debug)]
@@ -173,11 +190,72 @@ pub struct CommitOptions {
     pub message: Option<String>,
 }
 
+#[derive(Args, Debug)]
+pub struct ConfigOptions {
+    #[command(subcommand)]
+    pub cmd: ConfigCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Migrate configuration files to current format version.
+    Migrate(MigrateOptions),
+
+    /// Show JSON Schema of configuration file.
+    Schema(SchemaOptions),
+}
+
+#[derive(Args, Debug)]
+pub struct MigrateOptions {
+    /// Show migrated configuration files without writing them.
+    #[arg(long, short = 'n')]
+    pub dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct SchemaOptions {
+    /// Configuration file to describe.
+    #[arg(value_enum, value_name = "FILE")]
+    pub file: SchemaFile,
+}
+
+#[derive(Args, Debug)]
+pub struct GraphOptions {
+    /// Only show REPO and its dependencies.
+    #[arg(value_name = "REPO")]
+    pub repo: Option<String>,
+
+    #[arg(default_value_t = GraphFormat::default(), long, short, value_enum, value_name = "FORMAT")]
+    pub format: GraphFormat,
+}
+
 #[derive(Debug, Args)]
 #[command(next_help_heading = "Command Options")]
 pub struct SharedOptions {
     #[arg(default_value_t = HookAction::default(), long, short, value_enum, value_name = "ACTION")]
     pub run_hook: HookAction,
+
+    /// Use DIR as home directory.
+    #[arg(long, env = "OCD_HOME", value_name = "DIR")]
+    pub home_dir: Option<PathBuf>,
+
+    /// Use DIR to store configuration files and hooks.
+    #[arg(long, env = "OCD_CONFIG_DIR", value_name = "DIR")]
+    pub config_dir: Option<PathBuf>,
+
+    /// Use DIR to store repositories.
+    #[arg(long, env = "OCD_DATA_DIR", value_name = "DIR")]
+    pub data_dir: Option<PathBuf>,
+
+    /// Use BACKEND to operate on repositories.
+    #[arg(
+        default_value_t = VcsBackend::default(),
+        long,
+        env = "OCD_VCS_BACKEND",
+        value_enum,
+        value_name = "BACKEND"
+    )]
+    pub vcs_backend: VcsBackend,
 }
 
 #[derive(Debug, Snafu)]
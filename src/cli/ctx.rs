@@ -18,6 +18,9 @@ pub enum Ctx {
     Pull(PullCtx),
     Push(PushCtx),
     Commit(CommitCtx),
+    Config(ConfigCtx),
+    Graph(GraphCtx),
+    Lock(LockCtx),
     Git(GitCtx),
 }
 
@@ -34,6 +37,9 @@ fn from(opts: Cli) -> Self {
             CommandSet::Pull(_) => Self::Pull(PullCtx::from(opts)),
             CommandSet::Push(_) => Self::Push(PushCtx::from(opts)),
             CommandSet::Commit(_) => Self::Commit(CommitCtx::from(opts)),
+            CommandSet::Config(_) => Self::Config(ConfigCtx::from(opts)),
+            CommandSet::Graph(_) => Self::Graph(GraphCtx::from(opts)),
+            CommandSet::Lock => Self::Lock(LockCtx::from(opts)),
             CommandSet::Git(_) => Self::Git(GitCtx::from(opts)),
         }
     }
@@ -104,6 +110,7 @@ fn from(opts: Cli) -> Self {
 #[derive(Debug, Eq, PartialEq)]
 pub struct DeployCtx {
     pub repos: Vec<String>,
+    pub locked: bool,
     pub shared: SharedCtx,
 }
 
@@ -115,7 +122,7 @@ fn from(opts: Cli) -> Self {
             _ => unreachable!("This should not happen. The command is not 'deploy'"),
         };
 
-        Self { repos: cmd_set.repos, shared: shared_opts.into() }
+        Self { repos: cmd_set.repos, locked: cmd_set.locked, shared: shared_opts.into() }
     }
 }
 
@@ -158,6 +165,7 @@ fn from(opts: Cli) -> Self {
 #[derive(Debug, Eq, PartialEq)]
 pub struct StatusCtx {
     pub terse: bool,
+    pub untracked: bool,
     pub shared: SharedCtx,
 }
 
@@ -169,7 +177,7 @@ fn from(opts: Cli) -> Self {
             _ => unreachable!("This should not happen. The command is not 'status'"),
         };
 
-        Self { terse: cmd_set.terse, shared: shared_opts.into() }
+        Self { terse: cmd_set.terse, untracked: cmd_set.untracked, shared: shared_opts.into() }
     }
 }
 
@@ -230,6 +238,74 @@ fn from(opts: Cli) -> Self {
     }
 }
 
+#[derive(Debug, Eq, PartialEq)]
+pub struct ConfigCtx {
+    pub action: ConfigAction,
+    pub shared: SharedCtx,
+}
+
+impl From<Cli> for ConfigCtx {
+    fn from(opts: Cli) -> Self {
+        let Cli { shared_opts, cmd_set, .. } = opts;
+        let cmd_set = match cmd_set {
+            CommandSet::Config(opts) => opts,
+            _ => unreachable!("This should not happen. The command is not 'config'"),
+        };
+
+        let action = match cmd_set.cmd {
+            ConfigCommand::Migrate(opts) => ConfigAction::Migrate { dry_run: opts.dry_run },
+            ConfigCommand::Schema(opts) => ConfigAction::Schema { file: opts.file },
+        };
+
+        Self { action, shared: shared_opts.into() }
+    }
+}
+
+/// Actions for config command.
+#[derive(Debug, Eq, PartialEq)]
+pub enum ConfigAction {
+    /// Migrate configuration files to current format version.
+    Migrate { dry_run: bool },
+
+    /// Show JSON Schema of configuration file.
+    Schema { file: SchemaFile },
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct GraphCtx {
+    pub repo: Option<String>,
+    pub format: GraphFormat,
+    pub shared: SharedCtx,
+}
+
+impl From<Cli> for GraphCtx {
+    fn from(opts: Cli) -> Self {
+        let Cli { shared_opts, cmd_set, .. } = opts;
+        let cmd_set = match cmd_set {
+            CommandSet::Graph(opts) => opts,
+            _ => unreachable!("This should not happen. The command is not 'graph'"),
+        };
+
+        Self { repo: cmd_set.repo, format: cmd_set.format, shared: shared_opts.into() }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct LockCtx {
+    pub shared: SharedCtx,
+}
+
+impl From<Cli> for LockCtx {
+    fn from(opts: Cli) -> Self {
+        let Cli { shared_opts, cmd_set, .. } = opts;
+        if !matches!(cmd_set, CommandSet::Lock) {
+            unreachable!("This should not happen. The command is not 'lock'");
+        }
+
+        Self { shared: shared_opts.into() }
+    }
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub struct GitCtx {
     pub repo: OsString,
@@ -287,6 +363,30 @@ pub enum ListAction {
     Undeployed,
 }
 
+/// Configuration files for schema of config command.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum SchemaFile {
+    /// Repository configuration file.
+    Repos,
+
+    /// Command hook configuration file.
+    Hooks,
+}
+
+/// Output formats for graph command.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum GraphFormat {
+    /// Indented tree for the terminal.
+    #[default]
+    Tree,
+
+    /// Graphviz DOT.
+    Dot,
+
+    /// Mermaid flowchart.
+    Mermaid,
+}
+
 /// Fixup actions for `--fixup` flag in commit command.
 #[derive(Default, Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
 pub enum FixupAction {
@@ -297,3 +397,15 @@ pub enum FixupAction {
     /// Fix text of latest commit to all repositories.
     Reword,
 }
+
+/// Version control backends of shareable `--vcs-backend` flag.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum VcsBackend {
+    /// User's Git binary.
+    #[default]
+    Git,
+
+    /// In-process gitoxide.
+    #[cfg(feature = "gix")]
+    Gix,
+}
@@ -2,34 +2,62 @@
 // SPDX-License-Identifier: MIT
 
 mod locate;
+mod migrate;
+mod os;
+mod rev;
 mod settings;
 mod toml;
+mod when;
 
 #[doc(inline)]
 pub use locate::*;
+pub use migrate::*;
+pub use os::*;
+pub use rev::*;
 pub use settings::*;
 pub use toml::*;
+pub use when::*;
 
-use log::debug;
+use glob::{glob, PatternError};
+use log::{debug, info};
 use mkdirp::mkdirp;
+use serde_json::{json, Value as JsonValue};
 use snafu::prelude::*;
 use std::{
     fmt::{Debug, Display, Formatter, Result as FmtResult},
-    fs::OpenOptions,
+    fs::{read_to_string, OpenOptions},
     io::{Error as IoError, Read, Write},
     path::{Path, PathBuf},
     vec::IntoIter as VecIntoIter,
 };
-use toml_edit::{Item, Key};
+use toml_edit::{ImDocument, Item, Key, Value};
 
 /// Format preserving configuration file handler.
+///
+/// Configuration data is loaded from a set of layered configuration files. In
+/// order of lowest to highest precedence, these layers are the system-wide
+/// configuration file, the user's configuration file, and an optional per-host
+/// configuration file. Layers are merged together such that a setting in a
+/// higher layer overrides the same setting in a lower layer, while leaving the
+/// rest of the lower layer intact. Only the user layer is ever written to.
+///
+/// Any configuration file can split its data across multiple files through a
+/// top-level `include` array of glob patterns, e.g., `include =
+/// ["repos.d/*.toml"]`. Relative patterns are resolved from the directory of
+/// the file that includes them. Included files belong to the same layer as the
+/// file that included them, and are merged on top of it in the order they were
+/// matched.
+///
+/// Each file is migrated to the current format version when loaded. Files of
+/// a newer format version than what is supported are refused.
 #[derive(Clone, Debug)]
 pub struct ConfigFile<'cfg, C, L>
 where
     C: Config,
     L: Locator,
 {
-    doc: Toml,
+    layers: Vec<ConfigLayer>,
+    merged: Toml,
     config: C,
     locator: &'cfg L,
 }
@@ -41,71 +69,107 @@ impl<'cfg, C, L> ConfigFile<'cfg, C, L>
 {
     /// Load new configuration file.
     ///
-    /// If path to configuration file does not exist, then it will created at
-    /// target location. Otherwise, configuration file will be read and parsed
-    /// like normal.
+    /// If path to user configuration file does not exist, then it will created
+    /// at target location. Otherwise, configuration file will be read and
+    /// parsed like normal. System and host layers are only loaded if they
+    /// exist.
     ///
     /// # Errors
     ///
     /// Will fail if parent directory cannot be created when needed, or
     /// configuration file cannot be opened, read, and/or parsed at all.
     pub fn load(config: C, locator: &'cfg L) -> Result<Self, ConfigError> {
-        let path = config.location(locator);
-        debug!("Load new configuration file from '{}'", path.display());
-        let root = path.parent().unwrap();
-        mkdirp(root).context(MakeDirPSnafu { path: root.to_path_buf() })?;
+        Self::load_layers(config, locator, true)
+    }
+
+    /// Load new configuration file without touching the file system.
+    ///
+    /// Same as [`ConfigFile::load`], except that a missing user configuration
+    /// file is taken as empty instead of being created.
+    ///
+    /// # Errors
+    ///
+    /// Will fail if configuration file cannot be opened, read, and/or parsed
+    /// at all.
+    pub fn load_existing(config: C, locator: &'cfg L) -> Result<Self, ConfigError> {
+        Self::load_layers(config, locator, false)
+    }
+
+    fn load_layers(config: C, locator: &'cfg L, create: bool) -> Result<Self, ConfigError> {
+        let mut layers = Vec::new();
+        for (layer, path) in config.layers(locator) {
+            if layer != Layer::User && !path.exists() {
+                debug!("Skip missing {layer} configuration layer '{}'", path.display());
+                continue;
+            }
 
-        let mut file = OpenOptions::new()
-            .write(true)
-            .truncate(false)
-            .read(true)
-            .create(true)
-            .open(path)
-            .context(FileOpenSnafu { path: path.to_path_buf() })?;
-        let mut buffer = String::new();
-        file.read_to_string(&mut buffer).context(FileReadSnafu { path: path.to_path_buf() })?;
-        let doc = buffer.parse().context(TomlSnafu { path: path.to_path_buf() })?;
+            load_layer(layer, path, create, &mut Vec::new(), &mut layers)?;
+        }
 
-        Ok(Self { doc, config, locator })
+        let mut config_file = Self { layers, merged: Toml::new(), config, locator };
+        config_file.merge_layers();
+
+        Ok(config_file)
     }
 
     /// Save current data to configuration file.
     ///
     /// If path to configuration file does not exist, then it will created at
     /// target location. Otherwise, configuration file will be written to like
-    /// normal.
+    /// normal. Any included file of the user layer that was modified will also
+    /// be written to. Included files that were only migrated are left as they
+    /// are, see [`ConfigFile::save_migrated`].
     ///
     /// # Errors
     ///
     /// Will fail if parent directory cannot be created when needed, or
     /// configuration file cannot be opened, or written to for whatever reason.
     pub fn save(&mut self) -> Result<(), ConfigError> {
-        let path = self.as_path();
-        debug!("Save configuration manager data to '{}'", self.as_path().display());
-        let root = path.parent().unwrap();
-        mkdirp(root).context(MakeDirPSnafu { path: root.to_path_buf() })?;
+        let root = self.user_layer_index();
+        for (idx, layer) in self.layers.iter_mut().enumerate() {
+            if idx == root || (layer.layer == Layer::User && layer.modified) {
+                if let Some(version) = layer.migrated_from.take() {
+                    info!(
+                        "Migrate '{}' from version {version} to {CONFIG_VERSION}",
+                        layer.path.display()
+                    );
+                }
+                save_toml(&layer.path, &layer.doc)?;
+                layer.modified = false;
+            }
+        }
 
-        let mut file = OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .read(true)
-            .create(true)
-            .open(self.as_path())
-            .context(FileOpenSnafu { path: path.to_path_buf() })?;
-        let buffer = self.doc.to_string();
-        file.write_all(buffer.as_bytes()).context(FileWriteSnafu { path: path.to_path_buf() })?;
+        Ok(())
+    }
+
+    /// Save all configuration files that were migrated to current format
+    /// version.
+    ///
+    /// Unlike [`ConfigFile::save`], this writes to files of any layer.
+    ///
+    /// # Errors
+    ///
+    /// Will fail if a migrated configuration file cannot be written to.
+    pub fn save_migrated(&mut self) -> Result<(), ConfigError> {
+        for layer in self.layers.iter_mut().filter(|layer| layer.migrated_from.is_some()) {
+            save_toml(&layer.path, &layer.doc)?;
+            layer.migrated_from = None;
+            layer.modified = false;
+        }
 
         Ok(())
     }
 
     /// Get configuration setting.
     ///
+    /// Setting is taken from merged view of all configuration layers.
+    ///
     /// # Errors
     ///
-    /// Will fail if configuration setting does not exist, or target table
-    /// setting does not exist or was not defined as a table.
+    /// Will fail if configuration setting does not exist or is malformed, or
+    /// target table setting does not exist or was not defined as a table.
     pub fn get(&self, key: impl AsRef<str>) -> Result<C::Entry, ConfigError> {
-        self.config.get(self.locator, &self.doc, key.as_ref())
+        self.config.get(self.locator, &self.merged, key.as_ref())
     }
 
     /// Add configuration setting.
@@ -115,31 +179,59 @@ pub fn get(&self, key: impl AsRef<str>) -> Result<C::Entry, ConfigError> {
     /// being returned to indicate that no replacement took place.
     ///
     /// Will create table data if needed in case it does not exist for whatever
-    /// reason.
+    /// reason. Entry is always added into the user layer. If the entry was
+    /// originally defined in a file included by the user layer, then it will
+    /// be written back to that file.
     ///
     /// # Errors
     ///
-    /// Will fail if table setting is defined but not defined as a table.
+    /// Will fail if table setting is defined but not defined as a table, or
+    /// replaced entry is malformed.
     pub fn add(&mut self, entry: C::Entry) -> Result<Option<C::Entry>, ConfigError> {
-        self.config.add(self.locator, &mut self.doc, entry)
+        let (key, _) = entry.to_toml();
+        let idx = self.writable_layer_index(key.get());
+        let layer = &mut self.layers[idx];
+        let result = self.config.add(self.locator, &mut layer.doc, entry);
+        layer.modified = true;
+        self.merge_layers();
+        result
     }
 
     /// Remove configuration setting.
     ///
+    /// Entry is only removed from the user layer, or from the file included by
+    /// the user layer that defines it.
+    ///
     /// # Errors
     ///
-    /// Will fail if configuration setting does not exist, or target table
-    /// setting does not exist or was not defined as a table.
+    /// Will fail if configuration setting does not exist, is only defined in
+    /// the system or host layer, or target table setting does not exist or was
+    /// not defined as a table.
     pub fn remove(&mut self, key: impl AsRef<str>) -> Result<C::Entry, ConfigError> {
-        self.config.remove(self.locator, &mut self.doc, key.as_ref())
+        let idx = self.writable_layer_index(key.as_ref());
+        if !self.layers[idx].doc.contains(self.config.target_table(), &[key.as_ref()]) {
+            if let Some(layer) = self.layer_of(&[key.as_ref()]) {
+                return Err(ConfigError(InnerConfigError::ReadOnlyLayer {
+                    key: key.as_ref().into(),
+                    layer: layer.layer,
+                    path: layer.path.clone(),
+                }));
+            }
+        }
+
+        let layer = &mut self.layers[idx];
+        let result = self.config.remove(self.locator, &mut layer.doc, key.as_ref());
+        layer.modified = true;
+        self.merge_layers();
+        result
     }
 
     /// Return iterator over deserialized settings in configuration file.
     ///
     /// Yields all configuration settings in deserialized form from start to
-    /// end.
+    /// end. Malformed settings are yielded as errors.
     pub fn iter(&self) -> ConfigFileIterator<'_, C> {
-        let entries = if let Ok(table) = self.doc.get_table(self.config.target_table()) {
+        let entries = if let Ok(table) = self.merged.get_table(self.config.target_table()) {
             table.iter().map(|(key, value)| (Key::new(key), value.clone())).collect()
         } else {
             Vec::new()
@@ -148,10 +240,296 @@ pub fn iter(&self) -> ConfigFileIterator<'_, C> {
         ConfigFileIterator { config: &self.config, entries: entries.into_iter() }
     }
 
+    /// Determine which configuration layer defined a given setting.
+    ///
+    /// The `path` is a set of nested keys relative to the target table of the
+    /// configuration file, e.g., `["vim", "bootstrap", "clone"]`. Return the
+    /// layer of highest precedence that defines the setting, or [`None`] if no
+    /// layer defines it.
+    pub fn layer_of(&self, path: &[&str]) -> Option<&ConfigLayer> {
+        self.layers.iter().rev().find(|layer| layer.doc.contains(self.config.target_table(), path))
+    }
+
+    /// Locate line of file that defines a given setting.
+    ///
+    /// See [`Sources::locate`] for details.
+    pub fn locate(&self, path: &[&str], element: Option<&str>) -> Option<Location> {
+        self.sources().locate(path, element)
+    }
+
+    /// Files of all loaded configuration layers, to locate settings in later.
+    pub fn sources(&self) -> Sources {
+        Sources {
+            table: self.config.target_table().to_string(),
+            paths: self.layers.iter().map(|layer| layer.as_path().to_path_buf()).collect(),
+        }
+    }
+
+    /// Return all loaded configuration layers from lowest to highest
+    /// precedence.
+    pub fn layers(&self) -> &[ConfigLayer] {
+        &self.layers
+    }
+
     /// Coerces to a [`Path`] slice.
     pub fn as_path(&self) -> &Path {
         self.config.location(self.locator)
     }
+
+    fn user_layer_index(&self) -> usize {
+        self.layers.iter().position(|layer| layer.layer == Layer::User).unwrap()
+    }
+
+    fn user_layer(&self) -> &ConfigLayer {
+        &self.layers[self.user_layer_index()]
+    }
+
+    fn writable_layer_index(&self, key: &str) -> usize {
+        self.layers
+            .iter()
+            .rposition(|layer| {
+                layer.layer == Layer::User && layer.doc.contains(self.config.target_table(), &[key])
+            })
+            .unwrap_or_else(|| self.user_layer_index())
+    }
+
+    fn merge_layers(&mut self) {
+        let mut merged = Toml::new();
+        for layer in &self.layers {
+            merged.merge(&layer.doc);
+        }
+        self.merged = merged;
+    }
+}
+
+fn load_layer(
+    layer: Layer,
+    path: PathBuf,
+    create: bool,
+    stack: &mut Vec<PathBuf>,
+    layers: &mut Vec<ConfigLayer>,
+) -> Result<(), ConfigError> {
+    // Files created on load start at current version, they are not migrated.
+    let existed = path.exists();
+    let user = stack.is_empty() && layer == Layer::User;
+    let mut doc = if user && !existed && !create { Toml::new() } else { load_toml(&path, user)? };
+    let migrated_from =
+        migrate(&mut doc).context(MigrateSnafu { path: path.clone() })?.filter(|_| existed);
+    let includes = include_paths(&path, &doc)?;
+    stack.push(path.canonicalize().unwrap_or_else(|_| path.clone()));
+    layers.push(ConfigLayer { layer, path, doc, modified: false, migrated_from });
+
+    for include in includes {
+        let canonical = include.canonicalize().unwrap_or_else(|_| include.clone());
+        if stack.contains(&canonical) {
+            let cycle = stack
+                .iter()
+                .chain([&canonical])
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            return Err(ConfigError(InnerConfigError::IncludeCycle { cycle }));
+        }
+
+        if layers.iter().any(|loaded| loaded.layer == layer && loaded.path == include) {
+            continue;
+        }
+
+        load_layer(layer, include, create, stack, layers)?;
+    }
+    stack.pop();
+
+    Ok(())
+}
+
+fn include_paths(path: &Path, doc: &Toml) -> Result<Vec<PathBuf>, ConfigError> {
+    let Some(item) = doc.get_item("include") else {
+        return Ok(Vec::new());
+    };
+
+    let patterns = item
+        .as_array()
+        .map(|array| array.iter().map(|value| value.as_str()).collect::<Option<Vec<_>>>())
+        .context(BadIncludeSnafu { path: path.to_path_buf() })?
+        .context(BadIncludeSnafu { path: path.to_path_buf() })?;
+
+    let mut includes = Vec::new();
+    for pattern in patterns {
+        let root = path.parent().unwrap_or(Path::new(""));
+        let pattern = root.join(pattern).to_string_lossy().into_owned();
+        debug!("Include configuration files matching '{pattern}'");
+        let paths = glob(&pattern).context(IncludePatternSnafu { pattern: pattern.clone() })?;
+        includes.extend(paths.filter_map(std::result::Result::ok));
+    }
+
+    Ok(includes)
+}
+
+fn load_toml(path: &Path, create: bool) -> Result<Toml, ConfigError> {
+    debug!("Load new configuration file from '{}'", path.display());
+    if create {
+        let root = path.parent().unwrap();
+        mkdirp(root).context(MakeDirPSnafu { path: root.to_path_buf() })?;
+    }
+
+    let mut file = OpenOptions::new()
+        .write(create)
+        .truncate(false)
+        .read(true)
+        .create(create)
+        .open(path)
+        .context(FileOpenSnafu { path: path.to_path_buf() })?;
+    let mut buffer = String::new();
+    file.read_to_string(&mut buffer).context(FileReadSnafu { path: path.to_path_buf() })?;
+    let doc = buffer.parse().context(TomlSnafu { path: path.to_path_buf() })?;
+
+    Ok(doc)
+}
+
+fn save_toml(path: &Path, doc: &Toml) -> Result<(), ConfigError> {
+    debug!("Save configuration manager data to '{}'", path.display());
+    let root = path.parent().unwrap();
+    mkdirp(root).context(MakeDirPSnafu { path: root.to_path_buf() })?;
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .read(true)
+        .create(true)
+        .open(path)
+        .context(FileOpenSnafu { path: path.to_path_buf() })?;
+    let buffer = doc.to_string();
+    file.write_all(buffer.as_bytes()).context(FileWriteSnafu { path: path.to_path_buf() })?;
+
+    Ok(())
+}
+
+/// Configuration layer kinds.
+///
+/// Variants are ordered from lowest to highest precedence.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Layer {
+    /// System-wide configuration shared by all users.
+    System,
+
+    /// User's own configuration.
+    User,
+
+    /// Configuration specific to current host.
+    Host,
+}
+
+impl Display for Layer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Layer::System => write!(f, "system"),
+            Layer::User => write!(f, "user"),
+            Layer::Host => write!(f, "host"),
+        }
+    }
+}
+
+/// Single file of configuration data in a layer.
+#[derive(Clone, Debug)]
+pub struct ConfigLayer {
+    layer: Layer,
+    path: PathBuf,
+    doc: Toml,
+    modified: bool,
+    migrated_from: Option<i64>,
+}
+
+impl ConfigLayer {
+    /// Kind of layer configuration data was loaded from.
+    pub fn layer(&self) -> Layer {
+        self.layer
+    }
+
+    /// Coerces to a [`Path`] slice.
+    pub fn as_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Original format version of file, if it was migrated when loaded.
+    pub fn migrated_from(&self) -> Option<i64> {
+        self.migrated_from
+    }
+}
+
+/// Files that configuration settings were loaded from.
+///
+/// Settings are only located on demand, because it takes reading and parsing
+/// files again to do so. Reserve it for reporting errors.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct Sources {
+    table: String,
+    paths: Vec<PathBuf>,
+}
+
+impl Sources {
+    /// Locate line of file that defines a given setting.
+    ///
+    /// The `path` is a set of nested keys relative to the target table of the
+    /// configuration file, e.g., `["vim", "bootstrap", "clone"]`. If `element`
+    /// is given, then the setting must be an array, and the line of its first
+    /// element equal to `element` is located instead. Inline table elements
+    /// are compared through their `repo` entry. Only the file of highest
+    /// precedence that defines the setting is searched. Return [`None`] if
+    /// setting cannot be located.
+    pub fn locate(&self, path: &[&str], element: Option<&str>) -> Option<Location> {
+        for file in self.paths.iter().rev() {
+            let Ok(data) = read_to_string(file) else {
+                continue;
+            };
+            let Ok(doc) = ImDocument::parse(data.as_str()) else {
+                continue;
+            };
+            let mut item = doc.get(&self.table);
+            for key in path {
+                item = item.and_then(|item| item.as_table_like()?.get(key));
+            }
+            let Some(item) = item else {
+                continue;
+            };
+
+            let span = match element {
+                Some(element) => {
+                    fn name(value: &Value) -> Option<&str> {
+                        match value.as_inline_table() {
+                            Some(table) => table.get("repo").and_then(Value::as_str),
+                            None => value.as_str(),
+                        }
+                    }
+                    item.as_array()?.iter().find(|value| name(value) == Some(element))?.span()?
+                }
+                None => item.span()?,
+            };
+            let line = data[..span.start].matches('\n').count() + 1;
+
+            return Some(Location { path: file.clone(), line });
+        }
+
+        None
+    }
+}
+
+/// Line of configuration file that defines a setting.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Location {
+    pub path: PathBuf,
+    pub line: usize,
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}:{}", self.path.display(), self.line)
+    }
+}
+
+impl Display for ConfigLayer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.doc)
+    }
 }
 
 impl<C, L> Display for ConfigFile<'_, C, L>
@@ -160,7 +538,7 @@ impl<C, L> Display for ConfigFile<'_, C, L>
     L: Locator,
 {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        write!(f, "{}", self.doc)
+        write!(f, "{}", self.user_layer().doc)
     }
 }
 
@@ -176,10 +554,14 @@ impl<C> Iterator for ConfigFileIterator<'_, C>
 where
     C: Config,
 {
-    type Item = C::Entry;
+    type Item = Result<C::Entry, ConfigError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.entries.next().map(|(key, value)| C::Entry::from((key, value)))
+        self.entries.next().map(|(key, value)| {
+            let table = self.config.target_table();
+            let entry = C::Entry::try_from((key, value)).map_err(|err| err.with_parent(table));
+            Ok(entry.context(SettingsSnafu)?)
+        })
     }
 }
 
@@ -212,7 +594,36 @@ fn remove(
 
     fn location<'cfg>(&self, locator: &'cfg impl Locator) -> &'cfg Path;
 
+    /// Configuration layers from lowest to highest precedence.
+    fn layers(&self, locator: &impl Locator) -> Vec<(Layer, PathBuf)>;
+
     fn target_table(&self) -> &str;
+
+    /// JSON Schema of configuration file.
+    fn schema(&self) -> JsonValue {
+        json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "title": format!("dotfile-ocd {} configuration", self.target_table()),
+            "type": "object",
+            "properties": {
+                "version": {
+                    "description": "Format version of configuration file.",
+                    "type": "integer",
+                    "minimum": 0,
+                    "maximum": CONFIG_VERSION,
+                },
+                "include": {
+                    "description": "Glob patterns of configuration files to include.",
+                    "type": "array",
+                    "items": { "type": "string" },
+                },
+                self.target_table(): {
+                    "type": "object",
+                    "additionalProperties": Self::Entry::schema(),
+                },
+            },
+        })
+    }
 }
 
 #[derive(Clone, Default, Debug, PartialEq, Eq)]
@@ -231,7 +642,9 @@ fn get(
             .get(self.target_table(), key)
             .context(TomlSnafu { path: self.location(locator).to_path_buf() })?;
 
-        Ok(RepoSettings::from(entry))
+        Ok(RepoSettings::try_from(entry)
+            .map_err(|err| err.with_parent(self.target_table()))
+            .context(SettingsSnafu)?)
     }
 
     fn add(
@@ -243,7 +656,10 @@ fn add(
         let entry = doc
             .add(self.target_table(), entry.to_toml())
             .context(TomlSnafu { path: self.location(locator).to_path_buf() })?
-            .map(RepoSettings::from);
+            .map(RepoSettings::try_from)
+            .transpose()
+            .map_err(|err| err.with_parent(self.target_table()))
+            .context(SettingsSnafu)?;
 
         Ok(entry)
     }
@@ -258,13 +674,28 @@ fn remove(
             .remove(self.target_table(), key)
             .context(TomlSnafu { path: self.location(locator).to_path_buf() })?;
 
-        Ok(RepoSettings::from(entry))
+        Ok(RepoSettings::try_from(entry)
+            .map_err(|err| err.with_parent(self.target_table()))
+            .context(SettingsSnafu)?)
     }
 
     fn location<'cfg>(&self, locator: &'cfg impl Locator) -> &'cfg Path {
         locator.repo_config_file()
     }
 
+    fn layers(&self, locator: &impl Locator) -> Vec<(Layer, PathBuf)> {
+        let mut layers = Vec::new();
+        if let Some(path) = locator.system_repo_config_file() {
+            layers.push((Layer::System, path));
+        }
+        layers.push((Layer::User, self.location(locator).to_path_buf()));
+        if let Some(path) = locator.host_repo_config_file() {
+            layers.push((Layer::Host, path));
+        }
+
+        layers
+    }
+
     fn target_table(&self) -> &str {
         "repos"
     }
@@ -286,7 +717,9 @@ fn get(
             .get(self.target_table(), key)
             .context(TomlSnafu { path: self.location(locator).to_path_buf() })?;
 
-        Ok(CmdHookSettings::from(entry))
+        Ok(CmdHookSettings::try_from(entry)
+            .map_err(|err| err.with_parent(self.target_table()))
+            .context(SettingsSnafu)?)
     }
 
     fn add(
@@ -298,7 +731,10 @@ fn add(
         let entry = doc
             .add(self.target_table(), entry.to_toml())
             .context(TomlSnafu { path: self.location(locator).to_path_buf() })?
-            .map(CmdHookSettings::from);
+            .map(CmdHookSettings::try_from)
+            .transpose()
+            .map_err(|err| err.with_parent(self.target_table()))
+            .context(SettingsSnafu)?;
 
         Ok(entry)
     }
@@ -313,13 +749,25 @@ fn remove(
             .remove(self.target_table(), key)
             .context(TomlSnafu { path: self.location(locator).to_path_buf() })?;
 
-        Ok(CmdHookSettings::from(entry))
+        Ok(CmdHookSettings::try_from(entry)
+            .map_err(|err| err.with_parent(self.target_table()))
+            .context(SettingsSnafu)?)
     }
 
     fn location<'cfg>(&self, locator: &'cfg impl Locator) -> &'cfg Path {
         locator.hook_config_file()
     }
 
+    fn layers(&self, locator: &impl Locator) -> Vec<(Layer, PathBuf)> {
+        let mut layers = Vec::new();
+        if let Some(path) = locator.system_hook_config_file() {
+            layers.push((Layer::System, path));
+        }
+        layers.push((Layer::User, self.location(locator).to_path_buf()));
+
+        layers
+    }
+
     fn target_table(&self) -> &str {
         "hooks"
     }
@@ -348,6 +796,24 @@ enum InnerConfigError {
 
     #[snafu(display("Failed to parse '{}'", path.display()))]
     Toml { path: PathBuf, source: TomlError },
+
+    #[snafu(display("Setting 'include' in '{}' must be an array of strings", path.display()))]
+    BadInclude { path: PathBuf },
+
+    #[snafu(display("Invalid include pattern '{pattern}'"))]
+    IncludePattern { pattern: String, source: PatternError },
+
+    #[snafu(display("Configuration files include each other: '{cycle}'"))]
+    IncludeCycle { cycle: String },
+
+    #[snafu(display("Setting '{key}' is defined in read-only {layer} layer '{}'", path.display()))]
+    ReadOnlyLayer { key: String, layer: Layer, path: PathBuf },
+
+    #[snafu(display("Failed to migrate '{}'", path.display()))]
+    Migrate { path: PathBuf, source: MigrateError },
+
+    #[snafu(display("Invalid configuration setting"))]
+    Settings { source: SettingsError },
 }
 
 #[cfg(test)]
@@ -366,6 +832,7 @@ fn config_dir() -> Result<FixtureHarness, Whatever> {
             .with_file("config.toml", |fixture| {
                 fixture
                     .data(indoc! {r#"
+                        version = 1
                         # Formatting should remain the same!
 
                         [repos.vim]
@@ -391,8 +858,130 @@ fn config_dir() -> Result<FixtureHarness, Whatever> {
                     .kind(FileKind::Normal)
                     .write()
             })?
+            .with_file("malformed.toml", |fixture| {
+                fixture
+                    .data(indoc! {r#"
+                        version = 1
+
+                        [repos.vim]
+                        branch = 5
+
+                        [hooks]
+                        commit = [{ pre = ["hook.sh"] }]
+                    "#})
+                    .kind(FileKind::Normal)
+                    .write()
+            })?
             .with_file("bad_format.toml", |fixture| {
                 fixture.data("this 'will fail!").kind(FileKind::Normal).write()
+            })?
+            .with_file("system.toml", |fixture| {
+                fixture
+                    .data(indoc! {r#"
+                        [repos.vim]
+                        branch = "main"
+                        remote = "upstream"
+
+                        [repos.dwm]
+                        branch = "master"
+                        remote = "origin"
+                    "#})
+                    .kind(FileKind::Normal)
+                    .write()
+            })?
+            .with_file("host.toml", |fixture| {
+                fixture
+                    .data(indoc! {r#"
+                        [repos.dwm]
+                        branch = "patch"
+                    "#})
+                    .kind(FileKind::Normal)
+                    .write()
+            })?
+            .with_file("include.toml", |fixture| {
+                fixture
+                    .data(indoc! {r#"
+                        version = 1
+                        include = ["repos.d/*.toml"]
+
+                        [repos.vim]
+                        branch = "master"
+                        remote = "origin"
+                    "#})
+                    .kind(FileKind::Normal)
+                    .write()
+            })?
+            .with_file("repos.d/dwm.toml", |fixture| {
+                fixture
+                    .data(indoc! {r#"
+                        version = 1
+                        [repos.dwm]
+                        branch = "master"
+                        remote = "origin"
+                    "#})
+                    .kind(FileKind::Normal)
+                    .write()
+            })?
+            .with_file("repos.d/st.toml", |fixture| {
+                fixture
+                    .data(indoc! {r#"
+                        version = 1
+                        [repos.st]
+                        branch = "main"
+                        remote = "origin"
+                    "#})
+                    .kind(FileKind::Normal)
+                    .write()
+            })?
+            .with_file("cycle.toml", |fixture| {
+                fixture.data("include = [\"cycle.d/*.toml\"]\n").kind(FileKind::Normal).write()
+            })?
+            .with_file("cycle.d/cycle.toml", |fixture| {
+                fixture.data("include = [\"../cycle.toml\"]\n").kind(FileKind::Normal).write()
+            })?
+            .with_file("newer.toml", |fixture| {
+                fixture.data("version = 9999\n").kind(FileKind::Normal).write()
+            })?
+            .with_file("etc/system.toml", |fixture| {
+                fixture
+                    .data(indoc! {r#"
+                        include = ["repos.d/*.toml", "../repos.d/*.toml"]
+
+                        [repos.dwm]
+                        branch = "main"
+                        remote = "upstream"
+                    "#})
+                    .kind(FileKind::Normal)
+                    .write()
+            })?
+            .with_file("etc/repos.d/tmux.toml", |fixture| {
+                fixture
+                    .data(indoc! {r#"
+                        [repos.tmux]
+                        branch = "main"
+                        remote = "origin"
+                    "#})
+                    .kind(FileKind::Normal)
+                    .write()
+            })?
+            .with_file("old_include.toml", |fixture| {
+                fixture
+                    .data("version = 1\ninclude = [\"old.d/*.toml\"]\n")
+                    .kind(FileKind::Normal)
+                    .write()
+            })?
+            .with_file("old.d/vim.toml", |fixture| {
+                fixture
+                    .data(indoc! {r#"
+                        [repos.vim]
+                        branch = "master"
+                        remote = "origin"
+                    "#})
+                    .kind(FileKind::Normal)
+                    .write()
+            })?
+            .with_file("bad_include.toml", |fixture| {
+                fixture.data("include = \"not an array\"\n").kind(FileKind::Normal).write()
             })?;
 
         Ok(harness)
@@ -411,6 +1000,9 @@ fn config_file_load_parse_file(
         let mut locator = MockLocator::new();
         locator.expect_repo_config_file().return_const(fixture.as_path().into());
         locator.expect_hook_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_system_hook_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
 
         let config = ConfigFile::load(config_kind, &locator)
             .with_whatever_context(|_| "Failed to load configuration file")?;
@@ -431,10 +1023,15 @@ fn config_file_load_create_new_file(
         let mut locator = MockLocator::new();
         locator.expect_repo_config_file().return_const(config_dir.as_path().join("repos.toml"));
         locator.expect_hook_config_file().return_const(config_dir.as_path().join("hooks.toml"));
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_system_hook_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
 
         let config = ConfigFile::load(config_kind, &locator)
             .with_whatever_context(|_| "Failed to load configuration file")?;
         assert!(config.as_path().exists());
+        let result = config.layers().iter().map(ConfigLayer::migrated_from).collect::<Vec<_>>();
+        assert_eq!(result, vec![None]);
 
         Ok(())
     }
@@ -452,6 +1049,9 @@ fn config_file_load_return_err_toml(
         let mut locator = MockLocator::new();
         locator.expect_repo_config_file().return_const(fixture.as_path().into());
         locator.expect_hook_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_system_hook_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
 
         let result = ConfigFile::load(config_kind, &locator);
         assert!(matches!(result.unwrap_err().0, InnerConfigError::Toml { .. }));
@@ -483,6 +1083,9 @@ fn config_file_save_preserves_formatting<E, T>(
         let mut locator = MockLocator::new();
         locator.expect_repo_config_file().return_const(fixture.as_path().into());
         locator.expect_hook_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_system_hook_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
 
         let mut config = ConfigFile::load(config_kind, &locator)
             .with_whatever_context(|_| "Failed to load configuration file")?;
@@ -506,6 +1109,9 @@ fn config_file_save_create_new_file(
         let mut locator = MockLocator::new();
         locator.expect_repo_config_file().return_const(config_dir.as_path().join("repos.toml"));
         locator.expect_hook_config_file().return_const(config_dir.as_path().join("hooks.toml"));
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_system_hook_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
 
         let mut config = ConfigFile::load(config_kind, &locator)
             .with_whatever_context(|_| "Failed to load configuration file")?;
@@ -549,6 +1155,9 @@ fn config_file_get_return_setting<E, T>(
         let mut locator = MockLocator::new();
         locator.expect_repo_config_file().return_const(fixture.as_path().into());
         locator.expect_hook_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_system_hook_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
 
         let config = ConfigFile::load(config_kind, &locator)
             .with_whatever_context(|_| "Failed to load configuration file")?;
@@ -571,6 +1180,9 @@ fn config_file_get_return_err_toml(
         let mut locator = MockLocator::new();
         locator.expect_repo_config_file().return_const(fixture.as_path().into());
         locator.expect_hook_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_system_hook_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
 
         let config = ConfigFile::load(config_kind, &locator)
             .with_whatever_context(|_| "Failed to load configuration file")?;
@@ -580,6 +1192,49 @@ fn config_file_get_return_err_toml(
         Ok(())
     }
 
+    #[rstest]
+    #[case::repo_config(RepoConfig, include_str!("../schemas/repos.schema.json"))]
+    #[case::cmd_hook_config(CmdHookConfig, include_str!("../schemas/hooks.schema.json"))]
+    fn config_schema_match_snapshot(#[case] config_kind: impl Config, #[case] expect: &str) {
+        let result = format!("{:#}\n", config_kind.schema());
+        assert_eq!(result, expect);
+    }
+
+    #[rstest]
+    #[case::repo_config(RepoConfig, "vim", "repos.vim.branch")]
+    #[case::cmd_hook_config(CmdHookConfig, "commit", "hooks.commit[0].pre")]
+    #[report]
+    fn config_file_get_return_err_settings(
+        config_dir: Result<FixtureHarness, Whatever>,
+        #[case] config_kind: impl Config,
+        #[case] key: &str,
+        #[case] expect: &str,
+    ) -> Result<(), Whatever> {
+        let config_dir = config_dir?;
+        let fixture = config_dir.get("malformed.toml")?;
+        let mut locator = MockLocator::new();
+        locator.expect_repo_config_file().return_const(fixture.as_path().into());
+        locator.expect_hook_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_system_hook_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
+
+        let config = ConfigFile::load(config_kind, &locator)
+            .with_whatever_context(|_| "Failed to load configuration file")?;
+        let result = config.get(key);
+        assert!(matches!(
+            result.unwrap_err().0,
+            InnerConfigError::Settings { source, .. } if source.path() == expect
+        ));
+        let result = config.iter().next().unwrap();
+        assert!(matches!(
+            result.unwrap_err().0,
+            InnerConfigError::Settings { source, .. } if source.path() == expect
+        ));
+
+        Ok(())
+    }
+
     #[rstest]
     #[case::repo_config(
         RepoConfig,
@@ -604,6 +1259,9 @@ fn config_file_new_return_none<E, T>(
         let mut locator = MockLocator::new();
         locator.expect_repo_config_file().return_const(fixture.as_path().into());
         locator.expect_hook_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_system_hook_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
 
         let mut config = ConfigFile::load(config_kind, &locator)
             .with_whatever_context(|_| "Failed to load configuration file")?;
@@ -652,6 +1310,9 @@ fn config_file_new_return_some<E, T>(
         let mut locator = MockLocator::new();
         locator.expect_repo_config_file().return_const(fixture.as_path().into());
         locator.expect_hook_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_system_hook_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
 
         let mut config = ConfigFile::load(config_kind, &locator)
             .with_whatever_context(|_| "Failed to load configuration file")?;
@@ -677,6 +1338,9 @@ fn config_file_add_return_err_toml(
         let mut locator = MockLocator::new();
         locator.expect_repo_config_file().return_const(fixture.as_path().into());
         locator.expect_hook_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_system_hook_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
 
         let mut config = ConfigFile::load(config_kind, &locator)
             .with_whatever_context(|_| "Failed to load configuration file")?;
@@ -719,6 +1383,9 @@ fn config_file_remove_return_deleted_setting<E, T>(
         let mut locator = MockLocator::new();
         locator.expect_repo_config_file().return_const(fixture.as_path().into());
         locator.expect_hook_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_system_hook_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
 
         let mut config = ConfigFile::load(config_kind, &locator)
             .with_whatever_context(|_| "Failed to load configuration file")?;
@@ -744,6 +1411,9 @@ fn config_file_remove_return_err_toml(
         let mut locator = MockLocator::new();
         locator.expect_repo_config_file().return_const(fixture.as_path().into());
         locator.expect_hook_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_system_hook_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
 
         let mut config = ConfigFile::load(config_kind, &locator)
             .with_whatever_context(|_| "Failed to load configuration file")?;
@@ -752,4 +1422,331 @@ fn config_file_remove_return_err_toml(
 
         Ok(())
     }
+
+    #[rstest]
+    #[case::user_overrides_system(
+        "vim",
+        RepoSettings::new("vim", "master", "origin").with_bare_alias("$HOME"),
+    )]
+    #[case::host_overrides_system("dwm", RepoSettings::new("dwm", "patch", "origin"))]
+    #[report]
+    fn config_file_get_merge_layers(
+        config_dir: Result<FixtureHarness, Whatever>,
+        #[case] key: &str,
+        #[case] expect: RepoSettings,
+    ) -> Result<(), Whatever> {
+        let config_dir = config_dir?;
+        let user = config_dir.get("config.toml")?;
+        let system = config_dir.get("system.toml")?;
+        let host = config_dir.get("host.toml")?;
+        let mut locator = MockLocator::new();
+        locator.expect_repo_config_file().return_const(user.as_path().into());
+        locator.expect_system_repo_config_file().return_const(Some(system.as_path().into()));
+        locator.expect_host_repo_config_file().return_const(Some(host.as_path().into()));
+
+        let config = ConfigFile::load(RepoConfig, &locator)
+            .with_whatever_context(|_| "Failed to load configuration file")?;
+        let result = config.get(key).with_whatever_context(|_| "Failed to get setting")?;
+        assert_eq!(result, expect);
+        assert_eq!(config.iter().count(), 2);
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::system(&["dwm", "remote"], Some(Layer::System))]
+    #[case::user(&["vim", "branch"], Some(Layer::User))]
+    #[case::host(&["dwm", "branch"], Some(Layer::Host))]
+    #[case::undefined(&["vim", "bootstrap"], None)]
+    #[report]
+    fn config_file_layer_of_return_layer(
+        config_dir: Result<FixtureHarness, Whatever>,
+        #[case] path: &[&str],
+        #[case] expect: Option<Layer>,
+    ) -> Result<(), Whatever> {
+        let config_dir = config_dir?;
+        let user = config_dir.get("config.toml")?;
+        let system = config_dir.get("system.toml")?;
+        let host = config_dir.get("host.toml")?;
+        let mut locator = MockLocator::new();
+        locator.expect_repo_config_file().return_const(user.as_path().into());
+        locator.expect_system_repo_config_file().return_const(Some(system.as_path().into()));
+        locator.expect_host_repo_config_file().return_const(Some(host.as_path().into()));
+
+        let config = ConfigFile::load(RepoConfig, &locator)
+            .with_whatever_context(|_| "Failed to load configuration file")?;
+        let result = config.layer_of(path).map(|layer| layer.layer());
+        assert_eq!(result, expect);
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[report]
+    fn config_file_load_skip_missing_layers(
+        config_dir: Result<FixtureHarness, Whatever>,
+    ) -> Result<(), Whatever> {
+        let config_dir = config_dir?;
+        let user = config_dir.get("config.toml")?;
+        let mut locator = MockLocator::new();
+        locator.expect_repo_config_file().return_const(user.as_path().into());
+        locator
+            .expect_system_repo_config_file()
+            .return_const(Some(config_dir.as_path().join("no_system.toml")));
+        locator
+            .expect_host_repo_config_file()
+            .return_const(Some(config_dir.as_path().join("no_host.toml")));
+
+        let config = ConfigFile::load(RepoConfig, &locator)
+            .with_whatever_context(|_| "Failed to load configuration file")?;
+        assert_eq!(config.layers().len(), 1);
+        assert!(!config_dir.as_path().join("no_system.toml").exists());
+        assert!(!config_dir.as_path().join("no_host.toml").exists());
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[report]
+    fn config_file_save_only_write_user_layer(
+        config_dir: Result<FixtureHarness, Whatever>,
+    ) -> Result<(), Whatever> {
+        let mut config_dir = config_dir?;
+        let system = config_dir.get("system.toml")?.as_path().to_path_buf();
+        let host = config_dir.get("host.toml")?.as_path().to_path_buf();
+        let user = config_dir.get_mut("config.toml")?;
+        let mut locator = MockLocator::new();
+        locator.expect_repo_config_file().return_const(user.as_path().into());
+        locator.expect_system_repo_config_file().return_const(Some(system));
+        locator.expect_host_repo_config_file().return_const(Some(host));
+
+        let mut config = ConfigFile::load(RepoConfig, &locator)
+            .with_whatever_context(|_| "Failed to load configuration file")?;
+        config
+            .add(RepoSettings::new("st", "master", "origin"))
+            .with_whatever_context(|_| "Failed to add setting")?;
+        config.save().with_whatever_context(|_| "Failed to save configuration file")?;
+        user.sync()?;
+        assert_eq!(config.to_string(), user.as_str());
+        assert!(!user.as_str().contains("dwm"));
+        assert_eq!(config.iter().count(), 3);
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[report]
+    fn config_file_load_merge_includes(
+        config_dir: Result<FixtureHarness, Whatever>,
+    ) -> Result<(), Whatever> {
+        let config_dir = config_dir?;
+        let fixture = config_dir.get("include.toml")?;
+        let mut locator = MockLocator::new();
+        locator.expect_repo_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
+
+        let config = ConfigFile::load(RepoConfig, &locator)
+            .with_whatever_context(|_| "Failed to load configuration file")?;
+        let result = config
+            .iter()
+            .map(|repo| repo.map(|repo| repo.name))
+            .collect::<Result<Vec<_>, _>>()
+            .with_whatever_context(|_| "Failed to iterate settings")?;
+        assert_eq!(result, vec!["vim", "dwm", "st"]);
+        let result = config.layer_of(&["st"]).map(|layer| layer.as_path().to_path_buf());
+        assert_eq!(result, Some(config_dir.as_path().join("repos.d/st.toml")));
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[report]
+    fn config_file_add_write_back_to_included_file(
+        config_dir: Result<FixtureHarness, Whatever>,
+    ) -> Result<(), Whatever> {
+        let mut config_dir = config_dir?;
+        let fixture = config_dir.get("include.toml")?.clone();
+        let mut locator = MockLocator::new();
+        locator.expect_repo_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
+
+        let mut config = ConfigFile::load(RepoConfig, &locator)
+            .with_whatever_context(|_| "Failed to load configuration file")?;
+        let result = config
+            .add(RepoSettings::new("dwm", "patch", "upstream"))
+            .with_whatever_context(|_| "Failed to add setting")?;
+        assert_eq!(result, Some(RepoSettings::new("dwm", "master", "origin")));
+        config.remove("st").with_whatever_context(|_| "Failed to remove setting")?;
+        config.save().with_whatever_context(|_| "Failed to save configuration file")?;
+        config_dir.sync_tracked()?;
+
+        assert_eq!(config_dir.get("include.toml")?.as_str(), fixture.as_str());
+        assert_eq!(
+            config_dir.get("repos.d/dwm.toml")?.as_str(),
+            indoc! {r#"
+                version = 1
+
+                [repos.dwm]
+                branch = "patch"
+                remote = "upstream"
+            "#}
+        );
+        assert_eq!(config_dir.get("repos.d/st.toml")?.as_str(), "version = 1\n");
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[report]
+    fn config_file_load_resolve_includes_per_layer(
+        config_dir: Result<FixtureHarness, Whatever>,
+    ) -> Result<(), Whatever> {
+        let mut config_dir = config_dir?;
+        let system = config_dir.get("etc/system.toml")?.as_path().to_path_buf();
+        let fixture = config_dir.get("include.toml")?.clone();
+        let mut locator = MockLocator::new();
+        locator.expect_repo_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(Some(system));
+        locator.expect_host_repo_config_file().return_const(None);
+
+        let mut config = ConfigFile::load(RepoConfig, &locator)
+            .with_whatever_context(|_| "Failed to load configuration file")?;
+        let result = config.layer_of(&["tmux"]).map(|layer| (layer.layer(), layer.as_path()));
+        let tmux = config_dir.as_path().join("etc/repos.d/tmux.toml");
+        assert_eq!(result, Some((Layer::System, tmux.as_path())));
+        let result = config.layer_of(&["st"]).map(|layer| (layer.layer(), layer.as_path()));
+        let st = config_dir.as_path().join("repos.d/st.toml");
+        assert_eq!(result, Some((Layer::User, st.as_path())));
+
+        config.remove("st").with_whatever_context(|_| "Failed to remove setting")?;
+        config.save().with_whatever_context(|_| "Failed to save configuration file")?;
+        config_dir.sync_tracked()?;
+        assert!(!config_dir.get("repos.d/st.toml")?.as_str().contains("repos.st"));
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[report]
+    fn config_file_remove_return_err_read_only(
+        config_dir: Result<FixtureHarness, Whatever>,
+    ) -> Result<(), Whatever> {
+        let config_dir = config_dir?;
+        let user = config_dir.get("config.toml")?;
+        let system = config_dir.get("system.toml")?;
+        let mut locator = MockLocator::new();
+        locator.expect_repo_config_file().return_const(user.as_path().into());
+        locator.expect_system_repo_config_file().return_const(Some(system.as_path().into()));
+        locator.expect_host_repo_config_file().return_const(None);
+
+        let mut config = ConfigFile::load(RepoConfig, &locator)
+            .with_whatever_context(|_| "Failed to load configuration file")?;
+        let result = config.remove("dwm");
+        assert!(matches!(
+            result.unwrap_err().0,
+            InnerConfigError::ReadOnlyLayer { layer: Layer::System, .. }
+        ));
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::cycle("cycle.toml")]
+    #[case::bad_include("bad_include.toml")]
+    #[report]
+    fn config_file_load_return_err_include(
+        config_dir: Result<FixtureHarness, Whatever>,
+        #[case] file: &str,
+    ) -> Result<(), Whatever> {
+        let config_dir = config_dir?;
+        let fixture = config_dir.get(file)?;
+        let mut locator = MockLocator::new();
+        locator.expect_repo_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
+
+        let result = ConfigFile::load(RepoConfig, &locator);
+        assert!(matches!(
+            result.unwrap_err().0,
+            InnerConfigError::IncludeCycle { .. } | InnerConfigError::BadInclude { .. }
+        ));
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::repo_config(RepoConfig)]
+    #[case::cmd_hook_config(CmdHookConfig)]
+    #[report]
+    fn config_file_load_return_err_migrate(
+        config_dir: Result<FixtureHarness, Whatever>,
+        #[case] config_kind: impl Config,
+    ) -> Result<(), Whatever> {
+        let config_dir = config_dir?;
+        let fixture = config_dir.get("newer.toml")?;
+        let mut locator = MockLocator::new();
+        locator.expect_repo_config_file().return_const(fixture.as_path().into());
+        locator.expect_hook_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_system_hook_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
+
+        let result = ConfigFile::load(config_kind, &locator);
+        assert!(matches!(result.unwrap_err().0, InnerConfigError::Migrate { .. }));
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[report]
+    fn config_file_save_migrated_write_all_layers(
+        config_dir: Result<FixtureHarness, Whatever>,
+    ) -> Result<(), Whatever> {
+        let mut config_dir = config_dir?;
+        let user = config_dir.get("config.toml")?.as_path().to_path_buf();
+        let host = config_dir.get("host.toml")?.as_path().to_path_buf();
+        let mut locator = MockLocator::new();
+        locator.expect_repo_config_file().return_const(user);
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(Some(host));
+
+        let mut config = ConfigFile::load(RepoConfig, &locator)
+            .with_whatever_context(|_| "Failed to load configuration file")?;
+        let result = config.layers().iter().map(ConfigLayer::migrated_from).collect::<Vec<_>>();
+        assert_eq!(result, vec![None, Some(0)]);
+        config.save_migrated().with_whatever_context(|_| "Failed to save migrations")?;
+        config_dir.sync_tracked()?;
+        assert!(config_dir.get("host.toml")?.as_str().starts_with("version = 1\n"));
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[report]
+    fn config_file_save_skip_migrated_includes(
+        config_dir: Result<FixtureHarness, Whatever>,
+    ) -> Result<(), Whatever> {
+        let mut config_dir = config_dir?;
+        let user = config_dir.get("old_include.toml")?.as_path().to_path_buf();
+        let included = config_dir.get("old.d/vim.toml")?.clone();
+        let mut locator = MockLocator::new();
+        locator.expect_repo_config_file().return_const(user);
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
+
+        let mut config = ConfigFile::load(RepoConfig, &locator)
+            .with_whatever_context(|_| "Failed to load configuration file")?;
+        let result = config.layers().iter().map(ConfigLayer::migrated_from).collect::<Vec<_>>();
+        assert_eq!(result, vec![None, Some(0)]);
+        config
+            .add(RepoSettings::new("st", "master", "origin"))
+            .with_whatever_context(|_| "Failed to add setting")?;
+        config.save().with_whatever_context(|_| "Failed to save configuration file")?;
+        config_dir.sync_tracked()?;
+        assert_eq!(config_dir.get("old.d/vim.toml")?.as_str(), included.as_str());
+
+        Ok(())
+    }
 }
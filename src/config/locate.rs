@@ -2,9 +2,13 @@
 // SPDX-License-Identifier: MIT
 
 use directories::BaseDirs;
+use gethostname::gethostname;
 use log::trace;
 use snafu::prelude::*;
-use std::path::{Path, PathBuf};
+use std::{
+    env::{current_dir, current_exe},
+    path::{Path, PathBuf},
+};
 
 #[cfg(test)]
 use mockall::automock;
@@ -21,9 +25,16 @@ pub trait Locator {
     fn repos_dir(&self) -> &Path;
     fn repo_config_file(&self) -> &Path;
     fn hook_config_file(&self) -> &Path;
+    fn system_repo_config_file(&self) -> Option<PathBuf>;
+    fn system_hook_config_file(&self) -> Option<PathBuf>;
+    fn host_repo_config_file(&self) -> Option<PathBuf>;
 }
 
 /// Locator type that uses XDG Base Directory specification.
+///
+/// Also provides paths to system-wide configuration files stored in
+/// `/etc/dotfiles-ocd` on Unix-like systems, and a per-host repository
+/// configuration file named after the current hostname.
 #[derive(Debug, Clone)]
 pub struct XdgLocator {
     layout: BaseDirs,
@@ -32,6 +43,9 @@ pub struct XdgLocator {
     repos_dir: PathBuf,
     repo_config_file: PathBuf,
     hook_config_file: PathBuf,
+    system_repo_config_file: Option<PathBuf>,
+    system_hook_config_file: Option<PathBuf>,
+    host_repo_config_file: Option<PathBuf>,
 }
 
 impl XdgLocator {
@@ -51,10 +65,42 @@ pub fn locate() -> Result<Self, LocateError> {
         let repos_dir = layout.data_dir().join("dotfiles-ocd");
         let repo_config_file = config_dir.join("repos.toml");
         let hook_config_file = config_dir.join("hooks.toml");
-        Ok(Self { layout, config_dir, hooks_dir, repos_dir, repo_config_file, hook_config_file })
+        let system_config_dir = system_config_dir();
+        let system_repo_config_file = system_config_dir.as_ref().map(|dir| dir.join("repos.toml"));
+        let system_hook_config_file = system_config_dir.as_ref().map(|dir| dir.join("hooks.toml"));
+        let host_repo_config_file = host_repo_config_file(&config_dir);
+        Ok(Self {
+            layout,
+            config_dir,
+            hooks_dir,
+            repos_dir,
+            repo_config_file,
+            hook_config_file,
+            system_repo_config_file,
+            system_hook_config_file,
+            host_repo_config_file,
+        })
     }
 }
 
+#[cfg(unix)]
+fn system_config_dir() -> Option<PathBuf> {
+    Some(PathBuf::from("/etc/dotfiles-ocd"))
+}
+
+#[cfg(not(unix))]
+fn system_config_dir() -> Option<PathBuf> {
+    None
+}
+
+/// Determine path to per-host repository configuration file.
+///
+/// Will be [`None`] if hostname cannot be determined, or is not valid UTF-8.
+fn host_repo_config_file(config_dir: &Path) -> Option<PathBuf> {
+    let host = gethostname().into_string().ok().filter(|host| !host.is_empty())?;
+    Some(config_dir.join(format!("repos.{host}.toml")))
+}
+
 impl Locator for XdgLocator {
     fn home_dir(&self) -> &Path {
         self.layout.home_dir()
@@ -75,9 +121,240 @@ fn repos_dir(&self) -> &Path {
     fn repo_config_file(&self) -> &Path {
         &self.repo_config_file
     }
+
     fn hook_config_file(&self) -> &Path {
         &self.hook_config_file
     }
+
+    fn system_repo_config_file(&self) -> Option<PathBuf> {
+        self.system_repo_config_file.clone()
+    }
+
+    fn system_hook_config_file(&self) -> Option<PathBuf> {
+        self.system_hook_config_file.clone()
+    }
+
+    fn host_repo_config_file(&self) -> Option<PathBuf> {
+        self.host_repo_config_file.clone()
+    }
+}
+
+/// Locator type with user-defined paths.
+///
+/// Allows the user to relocate the home directory, the configuration
+/// directory, and the data directory where repositories are stored. Any path
+/// not given falls back to the layout of [`XdgLocator`]. System-wide
+/// configuration files are ignored when the configuration directory is
+/// relocated, so relocated setups remain isolated from the rest of the system.
+#[derive(Debug, Clone)]
+pub struct CustomLocator {
+    home_dir: PathBuf,
+    config_dir: PathBuf,
+    hooks_dir: PathBuf,
+    repos_dir: PathBuf,
+    repo_config_file: PathBuf,
+    hook_config_file: PathBuf,
+    system_repo_config_file: Option<PathBuf>,
+    system_hook_config_file: Option<PathBuf>,
+    host_repo_config_file: Option<PathBuf>,
+}
+
+impl CustomLocator {
+    /// Construct new custom locator type.
+    ///
+    /// The `config_dir` will contain configuration files and hooks, while
+    /// `data_dir` will contain repositories.
+    ///
+    /// # Errors
+    ///
+    /// Will fail if a path was not given, and home directory cannot be
+    /// determined to figure out a fallback path.
+    pub fn locate(
+        home_dir: Option<PathBuf>,
+        config_dir: Option<PathBuf>,
+        data_dir: Option<PathBuf>,
+    ) -> Result<Self, LocateError> {
+        trace!("Determine custom configuration paths");
+        let layout = BaseDirs::new();
+        let (system_repo_config_file, system_hook_config_file) = match config_dir {
+            Some(_) => (None, None),
+            None => {
+                let system_config_dir = system_config_dir();
+                (
+                    system_config_dir.as_ref().map(|dir| dir.join("repos.toml")),
+                    system_config_dir.as_ref().map(|dir| dir.join("hooks.toml")),
+                )
+            }
+        };
+
+        let home_dir = match home_dir {
+            Some(home_dir) => home_dir,
+            None => layout.as_ref().context(NoWayHomeSnafu)?.home_dir().to_path_buf(),
+        };
+        let config_dir = match config_dir {
+            Some(config_dir) => config_dir,
+            None => layout.as_ref().context(NoWayHomeSnafu)?.config_dir().join("dotfiles-ocd"),
+        };
+        let repos_dir = match data_dir {
+            Some(data_dir) => data_dir,
+            None => layout.as_ref().context(NoWayHomeSnafu)?.data_dir().join("dotfiles-ocd"),
+        };
+
+        let hooks_dir = config_dir.join("hooks");
+        let repo_config_file = config_dir.join("repos.toml");
+        let hook_config_file = config_dir.join("hooks.toml");
+        let host_repo_config_file = host_repo_config_file(&config_dir);
+        Ok(Self {
+            home_dir,
+            config_dir,
+            hooks_dir,
+            repos_dir,
+            repo_config_file,
+            hook_config_file,
+            system_repo_config_file,
+            system_hook_config_file,
+            host_repo_config_file,
+        })
+    }
+}
+
+impl Locator for CustomLocator {
+    fn home_dir(&self) -> &Path {
+        &self.home_dir
+    }
+
+    fn config_dir(&self) -> &Path {
+        &self.config_dir
+    }
+
+    fn hooks_dir(&self) -> &Path {
+        &self.hooks_dir
+    }
+
+    fn repos_dir(&self) -> &Path {
+        &self.repos_dir
+    }
+
+    fn repo_config_file(&self) -> &Path {
+        &self.repo_config_file
+    }
+
+    fn hook_config_file(&self) -> &Path {
+        &self.hook_config_file
+    }
+
+    fn system_repo_config_file(&self) -> Option<PathBuf> {
+        self.system_repo_config_file.clone()
+    }
+
+    fn system_hook_config_file(&self) -> Option<PathBuf> {
+        self.system_hook_config_file.clone()
+    }
+
+    fn host_repo_config_file(&self) -> Option<PathBuf> {
+        self.host_repo_config_file.clone()
+    }
+}
+
+/// Locator type for portable self-contained setups.
+///
+/// Keeps configuration files, hooks, and repositories under a single root
+/// directory, e.g., a USB stick, such that a full dotfile setup can be carried
+/// between machines. The root directory is marked by a special marker file
+/// named [`PortableLocator::MARKER`]. System-wide configuration files are never
+/// used, but per-host configuration files are stored in the root directory.
+#[derive(Debug, Clone)]
+pub struct PortableLocator {
+    home_dir: PathBuf,
+    root_dir: PathBuf,
+    hooks_dir: PathBuf,
+    repos_dir: PathBuf,
+    repo_config_file: PathBuf,
+    hook_config_file: PathBuf,
+    host_repo_config_file: Option<PathBuf>,
+}
+
+impl PortableLocator {
+    /// Name of marker file that marks root of portable setup.
+    pub const MARKER: &'static str = ".ocd-portable";
+
+    /// Find root directory of portable setup.
+    ///
+    /// Looks for marker file in the directory of the running binary first, and
+    /// then in the current working directory. Return [`None`] if no marker file
+    /// could be found.
+    pub fn find() -> Option<PathBuf> {
+        let exe_dir = current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf));
+        find_marker(exe_dir.into_iter().chain(current_dir().ok()))
+    }
+
+    /// Construct new portable locator type at `root_dir`.
+    ///
+    /// # Errors
+    ///
+    /// Will fail if home directory cannot be determined.
+    pub fn locate(root_dir: impl Into<PathBuf>) -> Result<Self, LocateError> {
+        let root_dir = root_dir.into();
+        trace!("Determine portable configuration paths in '{}'", root_dir.display());
+        let layout = BaseDirs::new().context(NoWayHomeSnafu)?;
+        let home_dir = layout.home_dir().to_path_buf();
+        let hooks_dir = root_dir.join("hooks");
+        let repos_dir = root_dir.join("repos");
+        let repo_config_file = root_dir.join("repos.toml");
+        let hook_config_file = root_dir.join("hooks.toml");
+        let host_repo_config_file = host_repo_config_file(&root_dir);
+        Ok(Self {
+            home_dir,
+            root_dir,
+            hooks_dir,
+            repos_dir,
+            repo_config_file,
+            hook_config_file,
+            host_repo_config_file,
+        })
+    }
+}
+
+fn find_marker(dirs: impl IntoIterator<Item = PathBuf>) -> Option<PathBuf> {
+    dirs.into_iter().find(|dir| dir.join(PortableLocator::MARKER).is_file())
+}
+
+impl Locator for PortableLocator {
+    fn home_dir(&self) -> &Path {
+        &self.home_dir
+    }
+
+    fn config_dir(&self) -> &Path {
+        &self.root_dir
+    }
+
+    fn hooks_dir(&self) -> &Path {
+        &self.hooks_dir
+    }
+
+    fn repos_dir(&self) -> &Path {
+        &self.repos_dir
+    }
+
+    fn repo_config_file(&self) -> &Path {
+        &self.repo_config_file
+    }
+
+    fn hook_config_file(&self) -> &Path {
+        &self.hook_config_file
+    }
+
+    fn system_repo_config_file(&self) -> Option<PathBuf> {
+        None
+    }
+
+    fn system_hook_config_file(&self) -> Option<PathBuf> {
+        None
+    }
+
+    fn host_repo_config_file(&self) -> Option<PathBuf> {
+        self.host_repo_config_file.clone()
+    }
 }
 
 /// Locator error type for public API.
@@ -92,3 +369,81 @@ enum InnerLocateError {
     #[snafu(display("Cannot determine path to home directory"))]
     NoWayHome,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testenv::{FileKind, FixtureHarness};
+
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use snafu::{report, Whatever};
+
+    #[rstest]
+    #[report]
+    fn custom_locator_locate_use_given_paths() -> Result<(), Whatever> {
+        let locator = CustomLocator::locate(
+            Some("/some/home".into()),
+            Some("/some/config".into()),
+            Some("/some/data".into()),
+        )
+        .with_whatever_context(|_| "Failed to construct custom locator")?;
+        assert_eq!(locator.home_dir(), Path::new("/some/home"));
+        assert_eq!(locator.config_dir(), Path::new("/some/config"));
+        assert_eq!(locator.hooks_dir(), Path::new("/some/config/hooks"));
+        assert_eq!(locator.repos_dir(), Path::new("/some/data"));
+        assert_eq!(locator.repo_config_file(), Path::new("/some/config/repos.toml"));
+        assert_eq!(locator.hook_config_file(), Path::new("/some/config/hooks.toml"));
+        assert_eq!(locator.system_repo_config_file(), None);
+        assert_eq!(locator.system_hook_config_file(), None);
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[report]
+    fn custom_locator_locate_fallback_to_xdg() -> Result<(), Whatever> {
+        let xdg = XdgLocator::locate().with_whatever_context(|_| "Failed to locate XDG paths")?;
+        let locator = CustomLocator::locate(None, None, Some("/some/data".into()))
+            .with_whatever_context(|_| "Failed to construct custom locator")?;
+        assert_eq!(locator.home_dir(), xdg.home_dir());
+        assert_eq!(locator.config_dir(), xdg.config_dir());
+        assert_eq!(locator.repo_config_file(), xdg.repo_config_file());
+        assert_eq!(locator.system_repo_config_file(), xdg.system_repo_config_file());
+        assert_eq!(locator.repos_dir(), Path::new("/some/data"));
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[report]
+    fn portable_locator_locate_use_root_dir() -> Result<(), Whatever> {
+        let locator = PortableLocator::locate("/some/usb")
+            .with_whatever_context(|_| "Failed to construct portable locator")?;
+        assert_eq!(locator.config_dir(), Path::new("/some/usb"));
+        assert_eq!(locator.hooks_dir(), Path::new("/some/usb/hooks"));
+        assert_eq!(locator.repos_dir(), Path::new("/some/usb/repos"));
+        assert_eq!(locator.repo_config_file(), Path::new("/some/usb/repos.toml"));
+        assert_eq!(locator.hook_config_file(), Path::new("/some/usb/hooks.toml"));
+        assert_eq!(locator.system_repo_config_file(), None);
+        assert_eq!(locator.system_hook_config_file(), None);
+        assert!(locator.host_repo_config_file().unwrap().starts_with("/some/usb"));
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[report]
+    fn portable_locator_find_marker() -> Result<(), Whatever> {
+        let harness = FixtureHarness::open()?
+            .with_file(format!("portable/{}", PortableLocator::MARKER), |fixture| {
+                fixture.kind(FileKind::Normal).write()
+            })?;
+        let no_marker = harness.as_path().to_path_buf();
+        let marker = harness.as_path().join("portable");
+        assert_eq!(find_marker([no_marker.clone(), marker.clone()]), Some(marker));
+        assert_eq!(find_marker([no_marker]), None);
+
+        Ok(())
+    }
+}
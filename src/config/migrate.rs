@@ -0,0 +1,175 @@
+// SPDX-FileCopyrightText: 2024 Jason Pena <jasonpena@awkless.com>
+// SPDX-License-Identifier: MIT
+
+use crate::config::Toml;
+
+use log::debug;
+use snafu::prelude::*;
+use toml_edit::{Item, Value};
+
+/// Current format version of configuration files.
+pub const CONFIG_VERSION: i64 = 1;
+
+/// Migration step that upgrades a document by one version.
+type Migration = fn(&mut Toml);
+
+/// Set of migration steps.
+///
+/// The migration at index `n` upgrades a document from version `n` to version
+/// `n + 1`. Thus, there must always be exactly [`CONFIG_VERSION`] migrations.
+const MIGRATIONS: [Migration; CONFIG_VERSION as usize] = [migrate_v0_to_v1];
+
+/// Version 0 is any configuration file that was written before versioning was
+/// introduced. No settings changed, so only the version needs to be stamped.
+fn migrate_v0_to_v1(_doc: &mut Toml) {}
+
+/// Determine format version of TOML document.
+///
+/// Documents without a `version` key are treated as version 0.
+///
+/// # Errors
+///
+/// Will fail if `version` key is not a non-negative integer.
+pub fn version_of(doc: &Toml) -> Result<i64, MigrateError> {
+    match doc.get_item("version") {
+        Some(item) => {
+            let version = item.as_integer().filter(|version| *version >= 0);
+            Ok(version.context(BadVersionSnafu { version: item.to_string().trim() })?)
+        }
+        None => Ok(0),
+    }
+}
+
+/// Migrate TOML document to current format version.
+///
+/// Applies each migration step from version of document up to
+/// [`CONFIG_VERSION`] in order. Comments and formatting of the document are
+/// preserved. Return the original version of the document if any migration
+/// took place, or [`None`] if document is already up to date.
+///
+/// # Errors
+///
+/// Will fail if version of document is invalid, or newer than the current
+/// format version.
+pub fn migrate(doc: &mut Toml) -> Result<Option<i64>, MigrateError> {
+    let version = version_of(doc)?;
+    ensure!(
+        version <= CONFIG_VERSION,
+        UnsupportedVersionSnafu { version, current: CONFIG_VERSION }
+    );
+
+    if version == CONFIG_VERSION {
+        return Ok(None);
+    }
+
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        debug!("Migrate configuration data from version {from} to {}", from + 1);
+        migration(doc);
+        set_version(doc, from as i64 + 1);
+    }
+
+    Ok(Some(version))
+}
+
+fn set_version(doc: &mut Toml, version: i64) {
+    let table = doc.as_table_mut();
+    match table.get_mut("version").and_then(Item::as_value_mut) {
+        Some(value) => {
+            let decor = value.decor().clone();
+            *value = Value::from(version);
+            *value.decor_mut() = decor;
+        }
+        None => {
+            table.insert("version", Item::Value(Value::from(version)));
+            table.sort_values_by(|key1, _, key2, _| {
+                (key2.get() == "version").cmp(&(key1.get() == "version"))
+            });
+        }
+    }
+}
+
+/// Migration error type for public API.
+#[derive(Debug, Snafu)]
+pub struct MigrateError(InnerMigrateError);
+
+/// Alias to allow one-off functions with different error type.
+pub type Result<T, E = MigrateError> = std::result::Result<T, E>;
+
+#[derive(Debug, Snafu)]
+enum InnerMigrateError {
+    #[snafu(display("Configuration version '{version}' is not a non-negative integer"))]
+    BadVersion { version: String },
+
+    #[snafu(display(
+        "Configuration version {version} is newer than supported version {current}, \
+         please upgrade dotfile-ocd"
+    ))]
+    UnsupportedVersion { version: i64, current: i64 },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use snafu::{report, Whatever};
+
+    #[rstest]
+    #[case::unversioned(
+        indoc! {r#"
+            # Comments should remain!
+            [repos.vim]
+            branch = "master"
+        "#},
+        indoc! {r#"
+            version = 1
+            # Comments should remain!
+            [repos.vim]
+            branch = "master"
+        "#},
+        Some(0),
+    )]
+    #[case::include_first(
+        indoc! {r#"
+            include = ["repos.d/*.toml"] # keep me
+        "#},
+        indoc! {r#"
+            version = 1
+            include = ["repos.d/*.toml"] # keep me
+        "#},
+        Some(0),
+    )]
+    #[case::explicit_v0("version = 0 # keep me\n", "version = 1 # keep me\n", Some(0))]
+    #[case::up_to_date("version = 1\n", "version = 1\n", None)]
+    #[report]
+    fn migrate_upgrade_to_current_version(
+        #[case] input: &str,
+        #[case] expect: &str,
+        #[case] from: Option<i64>,
+    ) -> Result<(), Whatever> {
+        let mut doc: Toml = input.parse().with_whatever_context(|_| "Failed to parse input")?;
+        let result = migrate(&mut doc).with_whatever_context(|_| "Failed to migrate")?;
+        assert_eq!(result, from);
+        assert_eq!(doc.to_string(), expect);
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::newer_version("version = 9999\n")]
+    #[case::negative_version("version = -1\n")]
+    #[case::not_integer("version = 'one'\n")]
+    #[report]
+    fn migrate_return_err(#[case] input: &str) -> Result<(), Whatever> {
+        let mut doc: Toml = input.parse().with_whatever_context(|_| "Failed to parse input")?;
+        let result = migrate(&mut doc);
+        assert!(matches!(
+            result.unwrap_err().0,
+            InnerMigrateError::UnsupportedVersion { .. } | InnerMigrateError::BadVersion { .. }
+        ));
+
+        Ok(())
+    }
+}
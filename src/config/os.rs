@@ -0,0 +1,165 @@
+// SPDX-FileCopyrightText: 2024 Jason Pena <jasonpena@awkless.com>
+// SPDX-License-Identifier: MIT
+
+use crate::config::OsKind;
+
+use log::trace;
+use std::{fs::read_to_string, path::Path};
+
+/// Operating system information of current host.
+///
+/// Holds every [`OsKind`] that describes the current host, e.g., a Ubuntu
+/// install running under WSL is described as `any`, `unix`, `linux`,
+/// `ubuntu`, `debian`, and `wsl`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct OsInfo {
+    kinds: Vec<OsKind>,
+}
+
+impl OsInfo {
+    /// Construct operating system information from given kinds.
+    pub fn new(kinds: impl IntoIterator<Item = OsKind>) -> Self {
+        Self { kinds: kinds.into_iter().collect() }
+    }
+
+    /// Detect operating system of current host.
+    ///
+    /// Linux distributions are identified through `ID` and `ID_LIKE` of
+    /// `/etc/os-release`, falling back to `/usr/lib/os-release`. WSL is
+    /// identified through the kernel release.
+    pub fn detect() -> Self {
+        let os_release = Path::new("/etc/os-release");
+        let os_release =
+            if os_release.exists() { os_release } else { Path::new("/usr/lib/os-release") };
+        Self::detect_with(os_release, Path::new("/proc/sys/kernel/osrelease"))
+    }
+
+    /// Detect operating system of current host through given files.
+    ///
+    /// Files are only read on Linux. Missing or unreadable files are treated
+    /// as empty.
+    pub fn detect_with(os_release: &Path, kernel_release: &Path) -> Self {
+        let mut kinds = vec![OsKind::Any];
+        if cfg!(unix) {
+            kinds.push(OsKind::Unix);
+        }
+
+        if cfg!(target_os = "linux") {
+            kinds.push(OsKind::Linux);
+            let data = read_to_string(os_release).unwrap_or_default();
+            kinds.extend(parse_os_release(&data).into_iter().map(OsKind::Distro));
+
+            let release = read_to_string(kernel_release).unwrap_or_default().to_lowercase();
+            if release.contains("microsoft") || release.contains("wsl") {
+                kinds.push(OsKind::Wsl);
+            }
+        } else if cfg!(target_os = "freebsd") {
+            kinds.push(OsKind::FreeBsd);
+        } else if cfg!(target_os = "openbsd") {
+            kinds.push(OsKind::OpenBsd);
+        } else if cfg!(target_os = "macos") {
+            kinds.push(OsKind::MacOs);
+        } else if cfg!(windows) {
+            kinds.push(OsKind::Windows);
+        }
+
+        trace!("Detected operating system kinds: {kinds:?}");
+        Self { kinds }
+    }
+
+    /// Check if current host is described by given kind.
+    pub fn is(&self, kind: &OsKind) -> bool {
+        self.kinds.contains(kind)
+    }
+
+    /// Iterate through all kinds that describe current host.
+    pub fn iter(&self) -> impl Iterator<Item = &OsKind> {
+        self.kinds.iter()
+    }
+}
+
+/// Extract distribution IDs from `ID` and `ID_LIKE` of os-release data.
+fn parse_os_release(data: &str) -> Vec<String> {
+    let mut ids = Vec::new();
+    let mut likes = Vec::new();
+    for line in data.lines() {
+        let Some((key, value)) = line.trim().split_once('=') else {
+            continue;
+        };
+
+        let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+        match key {
+            "ID" => ids.push(value.to_lowercase()),
+            "ID_LIKE" => likes.extend(value.split_whitespace().map(str::to_lowercase)),
+            _ => continue,
+        }
+    }
+
+    for like in likes {
+        if !ids.contains(&like) {
+            ids.push(like);
+        }
+    }
+
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::testenv::{FileKind, FixtureHarness};
+
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use snafu::{report, Whatever};
+
+    #[rstest]
+    #[case::plain(r#"ID=arch"#, vec!["arch"])]
+    #[case::quoted(
+        indoc! {r#"
+            NAME="Ubuntu"
+            ID=ubuntu
+            ID_LIKE="debian"
+        "#},
+        vec!["ubuntu", "debian"],
+    )]
+    #[case::many_likes(
+        "ID='rocky'\nID_LIKE=\"rhel centos fedora\"\n",
+        vec!["rocky", "rhel", "centos", "fedora"],
+    )]
+    #[case::empty("", vec![])]
+    fn parse_os_release_return_ids(#[case] input: &str, #[case] expect: Vec<&str>) {
+        assert_eq!(parse_os_release(input), expect);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[rstest]
+    #[case::native("6.11.5-arch1-1", false)]
+    #[case::wsl("5.15.153.1-microsoft-standard-WSL2", true)]
+    #[report]
+    fn os_info_detect_with_read_given_files(
+        #[case] kernel: &str,
+        #[case] wsl: bool,
+    ) -> Result<(), Whatever> {
+        let harness = FixtureHarness::open()?
+            .with_file("os-release", |fixture| {
+                fixture.data("ID=ubuntu\nID_LIKE=debian\n").kind(FileKind::Normal).write()
+            })?
+            .with_file("osrelease", |fixture| {
+                fixture.data(kernel).kind(FileKind::Normal).write()
+            })?;
+        let os_release = harness.get("os-release")?;
+        let kernel_release = harness.get("osrelease")?;
+
+        let info = OsInfo::detect_with(os_release.as_path(), kernel_release.as_path());
+        assert!(info.is(&OsKind::Linux));
+        assert!(info.is(&OsKind::Distro("ubuntu".into())));
+        assert!(info.is(&OsKind::Distro("debian".into())));
+        assert!(!info.is(&OsKind::Distro("arch".into())));
+        assert_eq!(info.is(&OsKind::Wsl), wsl);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,223 @@
+// SPDX-FileCopyrightText: 2024 Jason Pena <jasonpena@awkless.com>
+// SPDX-License-Identifier: MIT
+
+use std::{
+    cmp::Ordering,
+    fmt::{Display, Formatter, Result as FmtResult},
+    str::FromStr,
+};
+
+/// Constraint on checked out revision of a repository.
+///
+/// Constraints compare the version of tags with `=`, `>=`, `>`, `<=`, or
+/// `<`, e.g., `>=v2` or `<3.1`. Without an operator, a constraint either
+/// names a commit through at least seven hexadecimal digits, e.g.,
+/// `1a2b3c4d`, or names an exact tag, e.g., `v2.1.0`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RevReq {
+    source: String,
+    kind: RevReqKind,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum RevReqKind {
+    Commit(String),
+    Tag(String),
+    Version(Op, Version),
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Op {
+    Eq,
+    Ge,
+    Gt,
+    Le,
+    Lt,
+}
+
+impl RevReq {
+    /// Check if checked out revision satisfies constraint.
+    pub fn matches(&self, info: &RevInfo) -> bool {
+        match &self.kind {
+            RevReqKind::Commit(prefix) => info.commit.starts_with(prefix.as_str()),
+            RevReqKind::Tag(tag) => info.tags.contains(tag),
+            RevReqKind::Version(Op::Eq, version) => info
+                .tags
+                .iter()
+                .filter_map(|tag| tag.parse::<Version>().ok())
+                .any(|v| v == *version),
+            RevReqKind::Version(op, version) => {
+                let Some(found) = info.version() else {
+                    return false;
+                };
+                let ordering = found.cmp(version);
+                match op {
+                    Op::Ge => ordering.is_ge(),
+                    Op::Gt => ordering.is_gt(),
+                    Op::Le => ordering.is_le(),
+                    Op::Lt => ordering.is_lt(),
+                    Op::Eq => unreachable!("This should not happen. Equality is matched above"),
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for RevReq {
+    type Err = String;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let input = source.trim();
+        let ops = [(">=", Op::Ge), ("<=", Op::Le), (">", Op::Gt), ("<", Op::Lt), ("=", Op::Eq)];
+        let kind = match ops.iter().find(|(token, _)| input.starts_with(token)) {
+            Some((token, op)) => RevReqKind::Version(*op, input[token.len()..].trim().parse()?),
+            None if input.is_empty() => return Err("revision cannot be empty".into()),
+            None if input.len() >= 7 && input.chars().all(|c| c.is_ascii_hexdigit()) => {
+                RevReqKind::Commit(input.to_lowercase())
+            }
+            None => RevReqKind::Tag(input.into()),
+        };
+
+        Ok(Self { source: source.into(), kind })
+    }
+}
+
+impl Display for RevReq {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.source)
+    }
+}
+
+/// Checked out revision of a repository.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct RevInfo {
+    /// Full hash of checked out commit.
+    pub commit: String,
+
+    /// Tags that point at checked out commit.
+    pub tags: Vec<String>,
+
+    /// Closest tag reachable from checked out commit.
+    pub nearest_tag: Option<String>,
+}
+
+impl RevInfo {
+    /// Version of checked out revision.
+    ///
+    /// Highest version among tags that point at checked out commit, or the
+    /// version of the closest reachable tag otherwise.
+    fn version(&self) -> Option<Version> {
+        let at_head = self.tags.iter().filter_map(|tag| tag.parse().ok()).max();
+        at_head.or_else(|| self.nearest_tag.as_ref().and_then(|tag| tag.parse().ok()))
+    }
+}
+
+impl Display for RevInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let commit = self.commit.get(..7).unwrap_or(&self.commit);
+        match self.tags.first().or(self.nearest_tag.as_ref()) {
+            Some(tag) => write!(f, "{tag} ({commit})"),
+            None => write!(f, "{commit}"),
+        }
+    }
+}
+
+/// Dotted version of tag, e.g., `v2.1.0`.
+///
+/// Missing components compare as zero, such that `v2` equals `v2.0.0`.
+#[derive(Debug, Clone, Eq)]
+struct Version(Vec<u64>);
+
+impl FromStr for Version {
+    type Err = String;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let digits = source.strip_prefix(['v', 'V']).unwrap_or(source);
+        digits
+            .split('.')
+            .map(|part| part.parse::<u64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map(Self)
+            .map_err(|_| format!("'{source}' is not a dotted version"))
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let len = self.0.len().max(other.0.len());
+        let lhs = self.0.iter().chain(std::iter::repeat(&0)).take(len);
+        let rhs = other.0.iter().chain(std::iter::repeat(&0)).take(len);
+        lhs.cmp(rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+    use rstest::{fixture, rstest};
+
+    #[fixture]
+    fn info() -> RevInfo {
+        RevInfo {
+            commit: "1a2b3c4d5e6f7a8b9c0d1a2b3c4d5e6f7a8b9c0d".into(),
+            tags: vec!["v2.1.0".into()],
+            nearest_tag: Some("v2.1.0".into()),
+        }
+    }
+
+    #[rstest]
+    #[case::at_least(">=v2", true)]
+    #[case::at_least_newer(">= v2.2", false)]
+    #[case::greater(">v2.0.9", true)]
+    #[case::at_most("<=2.1", true)]
+    #[case::less("<v2.1", false)]
+    #[case::equal_padded("=v2.1", true)]
+    #[case::exact_tag("v2.1.0", true)]
+    #[case::other_tag("v2.0.0", false)]
+    #[case::commit("1a2b3c4", true)]
+    #[case::commit_upper("1A2B3C4D", true)]
+    #[case::other_commit("deadbeef", false)]
+    fn rev_req_matches_return_bool(info: RevInfo, #[case] input: &str, #[case] expect: bool) {
+        let req: RevReq = input.parse().unwrap();
+        assert_eq!(req.matches(&info), expect);
+        assert_eq!(req.to_string(), input);
+    }
+
+    #[rstest]
+    fn rev_req_matches_use_nearest_tag() {
+        let info =
+            RevInfo { commit: "1a2b3c4d".into(), tags: vec![], nearest_tag: Some("v1.4.2".into()) };
+        assert!(">=v1.4".parse::<RevReq>().unwrap().matches(&info));
+        assert!(!">=v2".parse::<RevReq>().unwrap().matches(&info));
+        assert!(!"=v1.4.2".parse::<RevReq>().unwrap().matches(&info));
+    }
+
+    #[rstest]
+    #[case::empty("")]
+    #[case::bad_version(">=latest")]
+    #[case::no_version("<")]
+    fn rev_req_from_str_return_err(#[case] input: &str) {
+        assert!(input.parse::<RevReq>().is_err());
+    }
+
+    #[rstest]
+    #[case::tagged(info(), "v2.1.0 (1a2b3c4)")]
+    #[case::untagged(RevInfo { commit: "1a2b3c4d".into(), ..Default::default() }, "1a2b3c4")]
+    fn rev_info_display(#[case] info: RevInfo, #[case] expect: &str) {
+        assert_eq!(info.to_string(), expect);
+    }
+}
@@ -1,26 +1,181 @@
 // SPDX-FileCopyrightText: 2024 Jason Pena <jasonpena@awkless.com>
 // SPDX-License-Identifier: MIT
 
+use crate::config::{HostInfo, OsInfo, RevReq, When, WhenError};
+
+use dotfile_ocd_derive::Settings;
+use glob::Pattern;
+use regex::Regex;
+use serde_json::{json, Value as JsonValue};
+use snafu::prelude::*;
 use std::{
     cmp::PartialEq,
     fmt::{Debug, Display, Formatter, Result as FmtResult},
+    hash::{Hash, Hasher},
     path::PathBuf,
+    str::FromStr,
 };
-use toml_edit::{
-    visit::{visit_inline_table, visit_table_like_kv, Visit},
-    Array, InlineTable, Item, Key, Table, Value,
-};
-
-pub trait Settings: From<(Key, Item)> + Default + Debug + PartialEq {
+use toml_edit::{Array, InlineTable, Item, Key, Table, Value};
+
+/// Serialization and deserialization of configuration file entries.
+///
+/// Implement through `#[derive(Settings)]` with a field marked as
+/// `#[settings(key)]`. Deserialization fails if entry is malformed.
+pub trait Settings:
+    SettingsValue + TryFrom<(Key, Item), Error = SettingsError> + Default + Debug + PartialEq
+{
     fn to_toml(&self) -> (Key, Item);
 }
 
-#[derive(Debug, Default, Eq, PartialEq, Clone)]
+/// Serialization and deserialization of a single setting value.
+///
+/// Implemented for basic setting types, and by `#[derive(Settings)]` to allow
+/// settings to be nested in each other.
+pub trait SettingsValue: Sized {
+    /// Serialize into TOML item, or [`None`] if setting should be omitted.
+    fn to_item(&self) -> Option<Item>;
+
+    /// Deserialize from TOML item located at dotted `path`.
+    ///
+    /// # Errors
+    ///
+    /// Will fail if item is not valid for setting.
+    fn from_item(item: &Item, path: &str) -> Result<Self, SettingsError>;
+
+    /// JSON Schema of setting.
+    fn schema() -> JsonValue;
+
+    /// Setting can be omitted from configuration file.
+    fn is_optional() -> bool {
+        false
+    }
+}
+
+impl SettingsValue for String {
+    fn to_item(&self) -> Option<Item> {
+        Some(Item::Value(Value::from(self)))
+    }
+
+    fn from_item(item: &Item, path: &str) -> Result<Self, SettingsError> {
+        item.as_str().map(Into::into).ok_or_else(|| SettingsError::bad_type(path, "string", item))
+    }
+
+    fn schema() -> JsonValue {
+        json!({ "type": "string" })
+    }
+}
+
+impl SettingsValue for PathBuf {
+    fn to_item(&self) -> Option<Item> {
+        Some(Item::Value(Value::from(self.to_string_lossy().into_owned())))
+    }
+
+    fn from_item(item: &Item, path: &str) -> Result<Self, SettingsError> {
+        item.as_str().map(Into::into).ok_or_else(|| SettingsError::bad_type(path, "string", item))
+    }
+
+    fn schema() -> JsonValue {
+        json!({ "type": "string" })
+    }
+}
+
+impl<T: SettingsValue> SettingsValue for Option<T> {
+    fn to_item(&self) -> Option<Item> {
+        self.as_ref().and_then(T::to_item)
+    }
+
+    fn from_item(item: &Item, path: &str) -> Result<Self, SettingsError> {
+        T::from_item(item, path).map(Some)
+    }
+
+    fn schema() -> JsonValue {
+        T::schema()
+    }
+
+    fn is_optional() -> bool {
+        true
+    }
+}
+
+impl<T: SettingsValue> SettingsValue for Vec<T> {
+    fn to_item(&self) -> Option<Item> {
+        let array = self
+            .iter()
+            .filter_map(|value| value.to_item().and_then(|item| item.into_value().ok()))
+            .collect::<Array>();
+        Some(Item::Value(Value::Array(array)))
+    }
+
+    fn from_item(item: &Item, path: &str) -> Result<Self, SettingsError> {
+        let array = item.as_array().ok_or_else(|| SettingsError::bad_type(path, "array", item))?;
+        array
+            .iter()
+            .enumerate()
+            .map(|(idx, value)| {
+                T::from_item(&Item::Value(value.clone()), &format!("{path}[{idx}]"))
+            })
+            .collect()
+    }
+
+    fn schema() -> JsonValue {
+        json!({ "type": "array", "items": T::schema() })
+    }
+}
+
+/// Place each element of array item on its own indented line.
+pub fn format_multiline(item: &mut Item) {
+    if let Some(array) = item.as_array_mut() {
+        let len = array.len();
+        for (idx, value) in array.iter_mut().enumerate() {
+            let decor = value.decor_mut();
+            decor.set_prefix("\n    ");
+            if idx + 1 == len {
+                decor.set_suffix("\n");
+            }
+        }
+    }
+}
+
+/// Place comment on its own lines before entry of `key` in `table`.
+///
+/// Sub-tables keep the blank line that separates them from previous entries.
+pub fn format_comment(table: &mut Table, key: &str, comment: &str) {
+    let prefix = comment.lines().map(|line| format!("# {line}\n")).collect::<String>();
+    match table.get_mut(key) {
+        Some(Item::Table(inner)) => inner.decor_mut().set_prefix(format!("\n{prefix}")),
+        Some(_) => {
+            if let Some(mut key) = table.key_mut(key) {
+                key.leaf_decor_mut().set_prefix(prefix);
+            }
+        }
+        None => {}
+    }
+}
+
+/// Place comment after value item on the same line.
+pub fn format_trailing_comment(item: &mut Item, comment: &str) {
+    if let Some(value) = item.as_value_mut() {
+        value.decor_mut().set_suffix(format!(" # {comment}"));
+    }
+}
+
+#[derive(Debug, Default, Eq, PartialEq, Clone, Settings)]
 pub struct RepoSettings {
+    #[settings(key)]
     pub name: String,
+
+    /// Default branch of repository.
+    #[settings(default = "master")]
     pub branch: String,
+
+    /// Default remote of repository.
+    #[settings(default = "origin")]
     pub remote: String,
+
+    /// Work tree alias of bare repository.
     pub bare_alias: Option<PathBuf>,
+
+    /// Bootstrap repository from remote.
     pub bootstrap: Option<BootstrapSettings>,
 }
 
@@ -49,100 +204,35 @@ pub fn with_bootstrap(mut self, bootstrap: BootstrapSettings) -> Self {
     }
 }
 
-impl Settings for RepoSettings {
-    fn to_toml(&self) -> (Key, Item) {
-        let mut repo_opts = Table::new();
-        let mut bootstrap_opts = Table::new();
-
-        repo_opts.insert("branch", Item::Value(Value::from(&self.branch)));
-        repo_opts.insert("remote", Item::Value(Value::from(&self.remote)));
-
-        if let Some(bare_alias) = &self.bare_alias {
-            repo_opts.insert(
-                "bare_alias",
-                Item::Value(Value::from(bare_alias.to_string_lossy().into_owned())),
-            );
-        }
-
-        if let Some(bootstrap) = &self.bootstrap {
-            bootstrap_opts.insert("clone", Item::Value(Value::from(&bootstrap.clone)));
-
-            if let Some(os) = &bootstrap.os {
-                bootstrap_opts.insert("os", Item::Value(Value::from(os.to_string())));
-            }
-
-            if let Some(depends) = &bootstrap.depends {
-                bootstrap_opts
-                    .insert("depends", Item::Value(Value::Array(Array::from_iter(depends))));
-            }
-
-            if let Some(ignores) = &bootstrap.ignores {
-                bootstrap_opts
-                    .insert("ignores", Item::Value(Value::Array(Array::from_iter(ignores))));
-            }
+#[derive(Debug, Default, Eq, PartialEq, Clone, Settings)]
+pub struct BootstrapSettings {
+    /// URL to clone repository from.
+    pub clone: String,
 
-            if let Some(users) = &bootstrap.users {
-                bootstrap_opts.insert("users", Item::Value(Value::Array(Array::from_iter(users))));
-            }
+    /// Operating systems to bootstrap on, e.g., `["linux", "!wsl"]`.
+    pub os: Option<OsFilter>,
 
-            if let Some(hosts) = &bootstrap.hosts {
-                bootstrap_opts.insert("hosts", Item::Value(Value::Array(Array::from_iter(hosts))));
-            }
+    /// Repositories to bootstrap along with repository, optionally pinned to
+    /// a revision, e.g., `{ repo = "shell-common", rev = ">=v2" }`.
+    pub depends: Option<Vec<DependSettings>>,
 
-            repo_opts.insert("bootstrap", Item::Table(bootstrap_opts));
-        }
+    /// Repositories to bootstrap along with repository if they match host.
+    pub recommends: Option<Vec<String>>,
 
-        let key = Key::new(&self.name);
-        let value = Item::Table(repo_opts);
-        (key, value)
-    }
-}
+    /// Repositories that must not be deployed along with repository.
+    pub conflicts: Option<Vec<String>>,
 
-fn repo_settings_from_toml<'toml>(entry: (&'toml Key, &'toml Item)) -> RepoSettings {
-    let (key, value) = entry;
-    let mut repo = RepoSettings { name: key.get().into(), ..Default::default() };
-    repo.visit_item(value);
-    repo
-}
+    /// Patterns of files to exclude from deployment.
+    pub ignores: Option<Vec<String>>,
 
-impl From<(Key, Item)> for RepoSettings {
-    fn from(entry: (Key, Item)) -> Self {
-        let (key, value) = entry;
-        repo_settings_from_toml((&key, &value))
-    }
-}
+    /// Users to bootstrap for, given as names, globs, or `/regex/`.
+    pub users: Option<Vec<NamePattern>>,
 
-impl<'toml> From<(&'toml Key, &'toml Item)> for RepoSettings {
-    fn from(entry: (&'toml Key, &'toml Item)) -> Self {
-        repo_settings_from_toml(entry)
-    }
-}
+    /// Hosts to bootstrap on, given as names, globs, or `/regex/`.
+    pub hosts: Option<Vec<NamePattern>>,
 
-impl<'toml> Visit<'toml> for RepoSettings {
-    fn visit_table_like_kv(&mut self, key: &'toml str, node: &'toml Item) {
-        match key {
-            "branch" => self.branch = node.as_str().unwrap_or("master").into(),
-            "remote" => self.remote = node.as_str().unwrap_or("origin").into(),
-            "bare_alias" => self.bare_alias = node.as_str().map(|s| s.into()),
-            "bootstrap" => {
-                let mut bootstrap = BootstrapSettings::default();
-                bootstrap.visit_item(node);
-                self.bootstrap = Some(bootstrap);
-            }
-            &_ => visit_table_like_kv(self, key, node),
-        };
-        visit_table_like_kv(self, key, node);
-    }
-}
-
-#[derive(Debug, Default, Eq, PartialEq, Clone)]
-pub struct BootstrapSettings {
-    pub clone: String,
-    pub os: Option<OsKind>,
-    pub depends: Option<Vec<String>>,
-    pub ignores: Option<Vec<String>>,
-    pub users: Option<Vec<String>>,
-    pub hosts: Option<Vec<String>>,
+    /// Conditional expression to bootstrap on, e.g., `os(linux) && !os(wsl)`.
+    pub when: Option<When>,
 }
 
 impl BootstrapSettings {
@@ -150,18 +240,41 @@ pub fn new(url: impl Into<String>) -> Self {
         Self { clone: url.into(), ..Default::default() }
     }
 
-    pub fn with_os(mut self, kind: OsKind) -> Self {
-        self.os = Some(kind);
+    pub fn with_os(mut self, os: impl Into<OsFilter>) -> Self {
+        self.os = Some(os.into());
         self
     }
 
-    pub fn with_depends(mut self, depends: impl IntoIterator<Item = impl Into<String>>) -> Self {
+    pub fn with_depends(
+        mut self,
+        depends: impl IntoIterator<Item = impl Into<DependSettings>>,
+    ) -> Self {
         let mut vec = Vec::new();
         vec.extend(depends.into_iter().map(Into::into));
         self.depends = Some(vec);
         self
     }
 
+    pub fn with_recommends(
+        mut self,
+        recommends: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        let mut vec = Vec::new();
+        vec.extend(recommends.into_iter().map(Into::into));
+        self.recommends = Some(vec);
+        self
+    }
+
+    pub fn with_conflicts(
+        mut self,
+        conflicts: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        let mut vec = Vec::new();
+        vec.extend(conflicts.into_iter().map(Into::into));
+        self.conflicts = Some(vec);
+        self
+    }
+
     pub fn with_ignores(mut self, ignores: impl IntoIterator<Item = impl Into<String>>) -> Self {
         let mut vec = Vec::new();
         vec.extend(ignores.into_iter().map(Into::into));
@@ -169,53 +282,255 @@ pub fn with_ignores(mut self, ignores: impl IntoIterator<Item = impl Into<String
         self
     }
 
-    pub fn with_users(mut self, users: impl IntoIterator<Item = impl Into<String>>) -> Self {
+    pub fn with_users(mut self, users: impl IntoIterator<Item = NamePattern>) -> Self {
         let mut vec = Vec::new();
-        vec.extend(users.into_iter().map(Into::into));
+        vec.extend(users);
         self.users = Some(vec);
         self
     }
 
-    pub fn with_hosts(mut self, hosts: impl IntoIterator<Item = impl Into<String>>) -> Self {
+    pub fn with_hosts(mut self, hosts: impl IntoIterator<Item = NamePattern>) -> Self {
         let mut vec = Vec::new();
-        vec.extend(hosts.into_iter().map(Into::into));
+        vec.extend(hosts);
         self.hosts = Some(vec);
         self
     }
+
+    pub fn with_when(mut self, when: When) -> Self {
+        self.when = Some(when);
+        self
+    }
+
+    /// Check if repository should be bootstrapped on given host.
+    ///
+    /// The `os`, `users`, `hosts`, and `when` settings must all hold if
+    /// given.
+    pub fn applies_to(&self, host: &HostInfo) -> bool {
+        self.os.as_ref().map_or(true, |os| os.matches(&host.os))
+            && self.users.as_ref().map_or(true, |users| matches_any(users, &host.username))
+            && self.hosts.as_ref().map_or(true, |hosts| matches_any(hosts, &host.hostname))
+            && self.when.as_ref().map_or(true, |when| when.eval(host))
+    }
 }
 
-impl<'toml> Visit<'toml> for BootstrapSettings {
-    fn visit_table_like_kv(&mut self, key: &'toml str, node: &'toml Item) {
-        match key {
-            "clone" => self.clone = node.as_str().unwrap_or_default().into(),
-            "os" => self.os = node.as_str().map(OsKind::from),
-            "depends" => {
-                self.depends = node.as_array().map(|a| {
-                    a.into_iter().map(|s| s.as_str().unwrap_or_default().to_string()).collect()
-                })
-            }
-            "ignores" => {
-                self.ignores = node.as_array().map(|a| {
-                    a.into_iter().map(|s| s.as_str().unwrap_or_default().to_string()).collect()
-                })
-            }
-            "users" => {
-                self.users = node.as_array().map(|a| {
-                    a.into_iter().map(|s| s.as_str().unwrap_or_default().to_string()).collect()
-                })
-            }
-            "hosts" => {
-                self.hosts = node.as_array().map(|a| {
-                    a.into_iter().map(|s| s.as_str().unwrap_or_default().to_string()).collect()
-                })
-            }
-            &_ => visit_table_like_kv(self, key, node),
+fn matches_any(patterns: &[NamePattern], name: &str) -> bool {
+    patterns.iter().any(|pattern| pattern.matches(name))
+}
+
+/// Hard dependency on another repository.
+///
+/// Given as the name of the repository, or as an inline table that also pins
+/// the revision the repository must have checked out.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DependSettings {
+    pub repo: String,
+    pub rev: Option<RevReq>,
+}
+
+impl DependSettings {
+    pub fn new(repo: impl Into<String>) -> Self {
+        Self { repo: repo.into(), rev: None }
+    }
+
+    pub fn with_rev(mut self, rev: RevReq) -> Self {
+        self.rev = Some(rev);
+        self
+    }
+}
+
+impl From<&str> for DependSettings {
+    fn from(repo: &str) -> Self {
+        Self::new(repo)
+    }
+}
+
+impl From<String> for DependSettings {
+    fn from(repo: String) -> Self {
+        Self::new(repo)
+    }
+}
+
+impl SettingsValue for DependSettings {
+    fn to_item(&self) -> Option<Item> {
+        let Some(rev) = &self.rev else {
+            return self.repo.to_item();
+        };
+
+        let mut table = InlineTable::new();
+        table.insert("repo", Value::from(&self.repo));
+        table.insert("rev", Value::from(rev.to_string()));
+        Some(Item::Value(Value::InlineTable(table)))
+    }
+
+    fn from_item(item: &Item, path: &str) -> Result<Self, SettingsError> {
+        if item.is_str() {
+            return Ok(Self::new(String::from_item(item, path)?));
+        }
+
+        let table = item
+            .as_table_like()
+            .ok_or_else(|| SettingsError::bad_type(path, "string or table", item))?;
+        let repo_path = format!("{path}.repo");
+        let repo = match table.get("repo") {
+            Some(item) => String::from_item(item, &repo_path)?,
+            None => return Err(SettingsError::missing(repo_path)),
+        };
+        let rev = match table.get("rev") {
+            Some(item) => Some(RevReq::from_item(item, &format!("{path}.rev"))?),
+            None => None,
         };
 
-        visit_table_like_kv(self, key, node);
+        Ok(Self { repo, rev })
+    }
+
+    fn schema() -> JsonValue {
+        json!({
+            "anyOf": [
+                { "type": "string" },
+                {
+                    "type": "object",
+                    "properties": {
+                        "repo": { "type": "string" },
+                        "rev": RevReq::schema(),
+                    },
+                    "required": ["repo"],
+                },
+            ],
+        })
+    }
+}
+
+impl SettingsValue for RevReq {
+    fn to_item(&self) -> Option<Item> {
+        Some(Item::Value(Value::from(self.to_string())))
+    }
+
+    fn from_item(item: &Item, path: &str) -> Result<Self, SettingsError> {
+        let value = String::from_item(item, path)?;
+        match value.parse() {
+            Ok(rev) => Ok(rev),
+            Err(reason) => RevisionSnafu { path, value, reason }.fail()?,
+        }
+    }
+
+    fn schema() -> JsonValue {
+        json!({
+            "type": "string",
+            "description": "Version constraint like `>=v2`, exact tag, or commit hash.",
+        })
+    }
+}
+
+/// Pattern to match user or host names with.
+///
+/// Patterns surrounded by slashes are regular expressions, e.g.,
+/// `/^ci-\d+$/`. Patterns containing any of `*`, `?`, or `[` are globs, e.g.,
+/// `build-*`. Anything else must match exactly.
+#[derive(Debug, Clone)]
+pub struct NamePattern {
+    source: String,
+    matcher: NameMatcher,
+}
+
+#[derive(Debug, Clone)]
+enum NameMatcher {
+    Exact,
+    Glob(Pattern),
+    Regex(Regex),
+}
+
+impl NamePattern {
+    /// Check if name matches pattern.
+    pub fn matches(&self, name: &str) -> bool {
+        match &self.matcher {
+            NameMatcher::Exact => self.source == name,
+            NameMatcher::Glob(glob) => glob.matches(name),
+            NameMatcher::Regex(regex) => regex.is_match(name),
+        }
+    }
+
+    /// Check if pattern makes exact `other` pattern redundant.
+    pub fn covers(&self, other: &NamePattern) -> bool {
+        !matches!(self.matcher, NameMatcher::Exact)
+            && matches!(other.matcher, NameMatcher::Exact)
+            && self.matches(&other.source)
+    }
+}
+
+impl FromStr for NamePattern {
+    type Err = String;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let matcher =
+            if let Some(regex) = source.strip_prefix('/').and_then(|rest| rest.strip_suffix('/')) {
+                NameMatcher::Regex(Regex::new(regex).map_err(|err| err.to_string())?)
+            } else if source.contains(['*', '?', '[']) {
+                NameMatcher::Glob(Pattern::new(source).map_err(|err| err.to_string())?)
+            } else {
+                NameMatcher::Exact
+            };
+
+        Ok(Self { source: source.into(), matcher })
+    }
+}
+
+impl Display for NamePattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl PartialEq for NamePattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
     }
 }
 
+impl Eq for NamePattern {}
+
+impl Hash for NamePattern {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.source.hash(state);
+    }
+}
+
+impl SettingsValue for NamePattern {
+    fn to_item(&self) -> Option<Item> {
+        Some(Item::Value(Value::from(self.to_string())))
+    }
+
+    fn from_item(item: &Item, path: &str) -> Result<Self, SettingsError> {
+        let value = String::from_item(item, path)?;
+        match value.parse() {
+            Ok(pattern) => Ok(pattern),
+            Err(reason) => PatternSnafu { path, value, reason }.fail()?,
+        }
+    }
+
+    fn schema() -> JsonValue {
+        json!({ "type": "string" })
+    }
+}
+
+impl SettingsValue for When {
+    fn to_item(&self) -> Option<Item> {
+        Some(Item::Value(Value::from(self.to_string())))
+    }
+
+    fn from_item(item: &Item, path: &str) -> Result<Self, SettingsError> {
+        let value = String::from_item(item, path)?;
+        Ok(value.parse().context(ExpressionSnafu { path })?)
+    }
+
+    fn schema() -> JsonValue {
+        json!({ "type": "string" })
+    }
+}
+
+/// Kind of operating system.
+///
+/// Any name that is not a builtin kind is taken as the `ID` of a Linux
+/// distribution, as given by `ID` or `ID_LIKE` in `/etc/os-release`.
 #[derive(Debug, Default, Eq, PartialEq, Clone)]
 pub enum OsKind {
     #[default]
@@ -223,122 +538,264 @@ pub enum OsKind {
 
     Unix,
 
+    Linux,
+
+    Distro(String),
+
+    Wsl,
+
+    FreeBsd,
+
+    OpenBsd,
+
     MacOs,
 
     Windows,
 }
 
-impl From<&str> for OsKind {
-    fn from(data: &str) -> Self {
-        match data {
+impl OsKind {
+    pub const NAMES: &'static str = "any, unix, linux, wsl, freebsd, openbsd, macos, windows, or \
+                                 Linux distribution ID";
+
+    /// Well known `ID` values of Linux distributions.
+    const KNOWN_DISTROS: &'static [&'static str] = &[
+        "almalinux",
+        "alpine",
+        "amzn",
+        "arch",
+        "artix",
+        "centos",
+        "chimera",
+        "debian",
+        "elementary",
+        "endeavouros",
+        "fedora",
+        "garuda",
+        "gentoo",
+        "kali",
+        "linuxmint",
+        "mageia",
+        "manjaro",
+        "neon",
+        "nixos",
+        "ol",
+        "opensuse",
+        "opensuse-leap",
+        "opensuse-tumbleweed",
+        "pop",
+        "raspbian",
+        "rhel",
+        "rocky",
+        "slackware",
+        "sles",
+        "solus",
+        "suse",
+        "ubuntu",
+        "void",
+        "zorin",
+    ];
+
+    /// Parse kind from name, or [`None`] if name is not a valid distribution
+    /// ID either.
+    ///
+    /// Any valid distribution ID is accepted, see
+    /// [`is_unknown_distro`](Self::is_unknown_distro) to catch likely typos.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let kind = match name {
             "any" => Self::Any,
             "unix" => Self::Unix,
+            "linux" => Self::Linux,
+            "wsl" => Self::Wsl,
+            "freebsd" => Self::FreeBsd,
+            "openbsd" => Self::OpenBsd,
             "macos" => Self::MacOs,
             "windows" => Self::Windows,
-            &_ => Self::Any,
+            id if is_distro_id(id) => Self::Distro(id.into()),
+            _ => return None,
+        };
+
+        Some(kind)
+    }
+
+    /// Check if kind is a distribution ID that is neither well known, nor
+    /// describes given host, because it is likely a typo.
+    pub fn is_unknown_distro(&self, host: &OsInfo) -> bool {
+        match self {
+            Self::Distro(id) => !Self::KNOWN_DISTROS.contains(&id.as_str()) && !host.is(self),
+            _ => false,
         }
     }
 }
 
+/// Check that name follows os-release rules for `ID`.
+fn is_distro_id(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| matches!(c, 'a'..='z' | '0'..='9' | '.' | '_' | '-'))
+}
+
 impl Display for OsKind {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         match self {
             OsKind::Any => write!(f, "any"),
             OsKind::Unix => write!(f, "unix"),
+            OsKind::Linux => write!(f, "linux"),
+            OsKind::Distro(id) => write!(f, "{id}"),
+            OsKind::Wsl => write!(f, "wsl"),
+            OsKind::FreeBsd => write!(f, "freebsd"),
+            OsKind::OpenBsd => write!(f, "openbsd"),
             OsKind::MacOs => write!(f, "macos"),
             OsKind::Windows => write!(f, "windows"),
         }
     }
 }
 
-#[derive(Clone, Debug, Default, Eq, PartialEq)]
-pub struct CmdHookSettings {
-    pub cmd: String,
-    pub hooks: Vec<HookSettings>,
+/// Operating system kind that may be negated with a leading `!`.
+#[derive(Debug, Default, Eq, PartialEq, Clone)]
+pub struct OsPattern {
+    pub kind: OsKind,
+    pub negated: bool,
 }
 
-impl CmdHookSettings {
-    pub fn new(cmd: impl Into<String>) -> Self {
-        Self { cmd: cmd.into(), ..Default::default() }
+impl OsPattern {
+    pub fn new(kind: OsKind) -> Self {
+        Self { kind, negated: false }
     }
 
-    pub fn add_hook(mut self, hook: HookSettings) -> Self {
-        self.hooks.push(hook);
+    pub fn negate(mut self) -> Self {
+        self.negated = !self.negated;
         self
     }
 }
 
-impl Settings for CmdHookSettings {
-    fn to_toml(&self) -> (Key, Item) {
-        let mut tables = Array::new();
-        let mut iter = self.hooks.iter().enumerate().peekable();
-        while let Some((_, hook)) = iter.next() {
-            let mut inline = InlineTable::new();
-            let decor = inline.decor_mut();
-
-            decor.set_prefix("\n    ");
-            if iter.peek().is_none() {
-                decor.set_suffix("\n");
-            }
+impl From<OsKind> for OsPattern {
+    fn from(kind: OsKind) -> Self {
+        Self::new(kind)
+    }
+}
 
-            if let Some(pre) = &hook.pre {
-                inline.insert("pre", Value::from(pre));
-            }
+impl Display for OsPattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        if self.negated {
+            write!(f, "!")?;
+        }
+        write!(f, "{}", self.kind)
+    }
+}
 
-            if let Some(post) = &hook.post {
-                inline.insert("post", Value::from(post));
-            }
+impl SettingsValue for OsPattern {
+    fn to_item(&self) -> Option<Item> {
+        Some(Item::Value(Value::from(self.to_string())))
+    }
 
-            if let Some(workdir) = &hook.workdir {
-                inline.insert("workdir", Value::from(workdir.to_string_lossy().into_owned()));
-            }
+    fn from_item(item: &Item, path: &str) -> Result<Self, SettingsError> {
+        let value = String::from_item(item, path)?;
+        let (negated, name) = match value.strip_prefix('!') {
+            Some(name) => (true, name),
+            None => (false, value.as_str()),
+        };
 
-            tables.push_formatted(Value::from(inline));
+        match OsKind::from_name(name) {
+            Some(kind) => Ok(Self { kind, negated }),
+            None => BadValueSnafu { path, value, expect: OsKind::NAMES }.fail()?,
         }
+    }
 
-        let key = Key::new(&self.cmd);
-        let value = Item::Value(Value::from(tables));
-        (key, value)
+    fn schema() -> JsonValue {
+        json!({ "type": "string", "pattern": "^!?[a-z0-9._-]+$" })
     }
 }
 
-fn cmd_hook_settings_from_toml<'toml>(entry: (&'toml Key, &'toml Item)) -> CmdHookSettings {
-    let (key, value) = entry;
-    let mut cmd_hook = CmdHookSettings::new(key.get());
-    cmd_hook.visit_item(value);
-    cmd_hook
+/// Set of operating system patterns a setting applies to.
+///
+/// Given as a single pattern, or as an array of patterns. Host matches if any
+/// plain pattern describes it, or if there are no plain patterns, and no
+/// negated pattern describes it.
+#[derive(Debug, Default, Eq, PartialEq, Clone)]
+pub struct OsFilter {
+    pub patterns: Vec<OsPattern>,
+}
+
+impl OsFilter {
+    pub fn new(patterns: impl IntoIterator<Item = impl Into<OsPattern>>) -> Self {
+        Self { patterns: patterns.into_iter().map(Into::into).collect() }
+    }
+
+    /// Check if host described by `info` matches filter.
+    pub fn matches(&self, info: &OsInfo) -> bool {
+        let (negated, plain): (Vec<_>, Vec<_>) =
+            self.patterns.iter().partition(|pattern| pattern.negated);
+        let included = plain.is_empty() || plain.iter().any(|pattern| info.is(&pattern.kind));
+        let excluded = negated.iter().any(|pattern| info.is(&pattern.kind));
+        included && !excluded
+    }
 }
 
-impl From<(Key, Item)> for CmdHookSettings {
-    fn from(entry: (Key, Item)) -> Self {
-        let (key, value) = entry;
-        cmd_hook_settings_from_toml((&key, &value))
+impl From<OsKind> for OsFilter {
+    fn from(kind: OsKind) -> Self {
+        Self::new([kind])
     }
 }
 
-impl<'toml> From<(&'toml Key, &'toml Item)> for CmdHookSettings {
-    fn from(entry: (&'toml Key, &'toml Item)) -> Self {
-        cmd_hook_settings_from_toml(entry)
+impl From<OsPattern> for OsFilter {
+    fn from(pattern: OsPattern) -> Self {
+        Self::new([pattern])
     }
 }
 
-impl<'toml> Visit<'toml> for CmdHookSettings {
-    fn visit_inline_table(&mut self, node: &'toml InlineTable) {
-        let hook = HookSettings {
-            pre: node.get("pre").and_then(|s| s.as_str().map(|s| s.into())),
-            post: node.get("post").and_then(|s| s.as_str().map(|s| s.into())),
-            workdir: node.get("workdir").and_then(|s| s.as_str().map(|s| s.into())),
+impl SettingsValue for OsFilter {
+    fn to_item(&self) -> Option<Item> {
+        match self.patterns.as_slice() {
+            [pattern] => pattern.to_item(),
+            patterns => patterns.to_vec().to_item(),
+        }
+    }
+
+    fn from_item(item: &Item, path: &str) -> Result<Self, SettingsError> {
+        let patterns = match item.is_array() {
+            true => Vec::from_item(item, path)?,
+            false => vec![OsPattern::from_item(item, path)?],
         };
-        self.hooks.push(hook);
 
-        visit_inline_table(self, node);
+        Ok(Self { patterns })
+    }
+
+    fn schema() -> JsonValue {
+        json!({
+            "anyOf": [OsPattern::schema(), { "type": "array", "items": OsPattern::schema() }],
+        })
     }
 }
 
-#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Settings)]
+pub struct CmdHookSettings {
+    #[settings(key)]
+    pub cmd: String,
+
+    /// Hooks to execute for command.
+    #[settings(value, multiline)]
+    pub hooks: Vec<HookSettings>,
+}
+
+impl CmdHookSettings {
+    pub fn new(cmd: impl Into<String>) -> Self {
+        Self { cmd: cmd.into(), ..Default::default() }
+    }
+
+    pub fn add_hook(mut self, hook: HookSettings) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Settings)]
+#[settings(inline)]
 pub struct HookSettings {
+    /// Script to execute before command.
     pub pre: Option<String>,
+
+    /// Script to execute after command.
     pub post: Option<String>,
+
+    /// Working directory to execute scripts in.
     pub workdir: Option<PathBuf>,
 }
 
@@ -363,6 +820,69 @@ pub fn with_workdir(mut self, path: impl Into<PathBuf>) -> Self {
     }
 }
 
+/// Settings error type for public API.
+#[derive(Debug, Snafu)]
+pub struct SettingsError(InnerSettingsError);
+
+impl SettingsError {
+    /// Construct error for item that is not of expected TOML type.
+    pub fn bad_type(path: impl Into<String>, expect: &'static str, item: &Item) -> Self {
+        Self(InnerSettingsError::BadType { path: path.into(), expect, found: item.type_name() })
+    }
+
+    /// Construct error for required setting that is missing.
+    pub fn missing(path: impl Into<String>) -> Self {
+        Self(InnerSettingsError::Missing { path: path.into() })
+    }
+
+    /// Dotted path to offending setting.
+    pub fn path(&self) -> &str {
+        match &self.0 {
+            InnerSettingsError::BadType { path, .. } => path,
+            InnerSettingsError::BadValue { path, .. } => path,
+            InnerSettingsError::Expression { path, .. } => path,
+            InnerSettingsError::Pattern { path, .. } => path,
+            InnerSettingsError::Revision { path, .. } => path,
+            InnerSettingsError::Missing { path } => path,
+        }
+    }
+
+    /// Prefix path to offending setting with path of parent table.
+    pub fn with_parent(mut self, parent: &str) -> Self {
+        let path = match &mut self.0 {
+            InnerSettingsError::BadType { path, .. } => path,
+            InnerSettingsError::BadValue { path, .. } => path,
+            InnerSettingsError::Expression { path, .. } => path,
+            InnerSettingsError::Pattern { path, .. } => path,
+            InnerSettingsError::Revision { path, .. } => path,
+            InnerSettingsError::Missing { path } => path,
+        };
+        *path = format!("{parent}.{path}");
+        self
+    }
+}
+
+#[derive(Debug, Snafu)]
+enum InnerSettingsError {
+    #[snafu(display("Setting '{path}' must be {expect}, found {found}"))]
+    BadType { path: String, expect: &'static str, found: &'static str },
+
+    #[snafu(display("Setting '{path}' has invalid value '{value}', expected one of: {expect}"))]
+    BadValue { path: String, value: String, expect: &'static str },
+
+    #[snafu(display("Setting '{path}' has invalid expression"))]
+    Expression { path: String, source: WhenError },
+
+    #[snafu(display("Setting '{path}' has invalid pattern '{value}': {reason}"))]
+    Pattern { path: String, value: String, reason: String },
+
+    #[snafu(display("Setting '{path}' has invalid revision '{value}': {reason}"))]
+    Revision { path: String, value: String, reason: String },
+
+    #[snafu(display("Setting '{path}' is required"))]
+    Missing { path: String },
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -370,9 +890,42 @@ mod tests {
     use indoc::indoc;
     use pretty_assertions::assert_eq;
     use rstest::{fixture, rstest};
-    use snafu::report;
+    use snafu::{report, Whatever};
     use toml_edit::{DocumentMut, TomlError};
 
+    fn patterns<'a>(sources: impl IntoIterator<Item = &'a str>) -> Vec<NamePattern> {
+        sources.into_iter().map(|source| source.parse().unwrap()).collect()
+    }
+
+    #[rstest]
+    #[case::exact("turing", "turing", true)]
+    #[case::exact_mismatch("turing", "turing-2", false)]
+    #[case::glob("build-*", "build-arm64", true)]
+    #[case::glob_mismatch("build-*", "ci-1", false)]
+    #[case::regex("/^ci-\\d+$/", "ci-42", true)]
+    #[case::regex_mismatch("/^ci-\\d+$/", "ci-arm", false)]
+    fn name_pattern_matches_return_bool(
+        #[case] pattern: NamePattern,
+        #[case] name: &str,
+        #[case] expect: bool,
+    ) {
+        assert_eq!(pattern.matches(name), expect);
+    }
+
+    #[rstest]
+    #[case::glob("build-*", "build-1", true)]
+    #[case::regex("/^ci-\\d+$/", "ci-7", true)]
+    #[case::not_matched("build-*", "ci-7", false)]
+    #[case::exact_never_covers("turing", "turing", false)]
+    #[case::only_exact_covered("*", "build-*", false)]
+    fn name_pattern_covers_return_bool(
+        #[case] pattern: NamePattern,
+        #[case] other: NamePattern,
+        #[case] expect: bool,
+    ) {
+        assert_eq!(pattern.covers(&other), expect);
+    }
+
     #[fixture]
     fn repo_settings_doc() -> Result<DocumentMut, TomlError> {
         let doc: DocumentMut = indoc! {r#"
@@ -393,7 +946,9 @@ fn repo_settings_doc() -> Result<DocumentMut, TomlError> {
             [bar.bootstrap]
             clone = "https://some/url"
             os = "unix"
-            depends = ["foo", "baz"]
+            depends = ["foo", { repo = "baz", rev = ">=v2" }]
+            recommends = ["fonts"]
+            conflicts = ["emacs"]
             ignores = ["LICENSE*", "README*"]
             users = ["awkless", "sedgwick"]
             hosts = ["lovelace", "turing"]
@@ -424,24 +979,137 @@ fn cmd_hook_settings_doc() -> Result<DocumentMut, TomlError> {
             .with_bootstrap(
                 BootstrapSettings::new("https://some/url")
                     .with_os(OsKind::Unix)
-                    .with_depends(["foo", "baz"])
+                    .with_depends([
+                        DependSettings::new("foo"),
+                        DependSettings::new("baz").with_rev(">=v2".parse().unwrap()),
+                    ])
+                    .with_recommends(["fonts"])
+                    .with_conflicts(["emacs"])
                     .with_ignores(["LICENSE*", "README*"])
-                    .with_users(["awkless", "sedgwick"])
-                    .with_hosts(["lovelace", "turing"])
+                    .with_users(patterns(["awkless", "sedgwick"]))
+                    .with_hosts(patterns(["lovelace", "turing"]))
             )
     )]
     fn repo_settings_from_key_item_return_self(
         repo_settings_doc: Result<DocumentMut, TomlError>,
         #[case] expect: RepoSettings,
-    ) -> Result<(), TomlError> {
-        let repo_settings_doc = repo_settings_doc?;
+    ) -> Result<(), Whatever> {
+        let repo_settings_doc = repo_settings_doc.with_whatever_context(|_| "Failed to parse")?;
         let entry = repo_settings_doc.as_table().get_key_value(expect.name.as_str()).unwrap();
-        let result = RepoSettings::from(entry);
+        let result =
+            RepoSettings::try_from(entry).with_whatever_context(|_| "Failed to deserialize")?;
         assert_eq!(result, expect);
 
         Ok(())
     }
 
+    #[rstest]
+    #[case::bad_string("branch = 5", "foo.branch")]
+    #[case::bad_table("bootstrap = 'not a table'", "foo.bootstrap")]
+    #[case::bad_array("[bootstrap]\nclone = 'url'\ndepends = 'vim'", "foo.bootstrap.depends")]
+    #[case::bad_element("[bootstrap]\nclone = 'url'\nusers = ['a', 1]", "foo.bootstrap.users[1]")]
+    #[case::bad_regex("[bootstrap]\nclone = 'url'\nhosts = ['/ci-(/']", "foo.bootstrap.hosts[0]")]
+    #[case::bad_when("[bootstrap]\nclone = 'url'\nwhen = 'os(linux) &&'", "foo.bootstrap.when")]
+    #[case::bad_os("[bootstrap]\nclone = 'url'\nos = 'Be OS'", "foo.bootstrap.os")]
+    #[case::bad_rev(
+        "[bootstrap]\nclone = 'url'\ndepends = [{ repo = 'sh', rev = '>=new' }]",
+        "foo.bootstrap.depends[0].rev"
+    )]
+    #[case::missing_clone("[bootstrap]\ndepends = ['vim']", "foo.bootstrap.clone")]
+    #[case::missing_repo(
+        "[bootstrap]\nclone = 'url'\ndepends = [{ rev = 'v2' }]",
+        "foo.bootstrap.depends[0].repo"
+    )]
+    #[report]
+    fn repo_settings_try_from_key_item_return_err(
+        #[case] input: &str,
+        #[case] expect: &str,
+    ) -> Result<(), Whatever> {
+        let doc: DocumentMut = input.parse().with_whatever_context(|_| "Failed to parse")?;
+        let result = RepoSettings::try_from((&Key::new("foo"), doc.as_item()));
+        assert_eq!(result.unwrap_err().path(), expect);
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::single(r#"os = "linux""#, OsFilter::new([OsKind::Linux]))]
+    #[case::list(
+        r#"os = ["arch", "!wsl"]"#,
+        OsFilter::new([
+            OsPattern::new(OsKind::Distro("arch".into())),
+            OsPattern::new(OsKind::Wsl).negate(),
+        ]),
+    )]
+    #[report]
+    fn os_filter_from_item_return_self(
+        #[case] input: &str,
+        #[case] expect: OsFilter,
+    ) -> Result<(), Whatever> {
+        let doc: DocumentMut = input.parse().with_whatever_context(|_| "Failed to parse")?;
+        let result = OsFilter::from_item(&doc["os"], "os")
+            .with_whatever_context(|_| "Failed to deserialize")?;
+        assert_eq!(result, expect);
+        assert_eq!(result.to_item().unwrap().to_string(), doc["os"].to_string().trim());
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::builtin("linux", false)]
+    #[case::known_distro("arch", false)]
+    #[case::host_distro("myos", false)]
+    #[case::typo("lnux", true)]
+    #[case::unknown_distro("macosx", true)]
+    fn os_kind_is_unknown_distro_flag_typos(#[case] name: &str, #[case] expect: bool) {
+        let host = OsInfo::new([OsKind::Any, OsKind::Linux, OsKind::Distro("myos".into())]);
+        let kind = OsKind::from_name(name).unwrap_or_default();
+        assert_eq!(kind.is_unknown_distro(&host), expect);
+    }
+
+    #[rstest]
+    #[case::any(OsFilter::new([OsKind::Any]), true)]
+    #[case::linux(OsFilter::new([OsKind::Linux]), true)]
+    #[case::distro_like(OsFilter::new([OsKind::Distro("debian".into())]), true)]
+    #[case::other_os(OsFilter::new([OsKind::MacOs, OsKind::FreeBsd]), false)]
+    #[case::negated(
+        OsFilter::new([OsPattern::new(OsKind::Linux), OsPattern::new(OsKind::Wsl).negate()]),
+        false,
+    )]
+    #[case::only_negated(OsFilter::new([OsPattern::new(OsKind::Windows).negate()]), true)]
+    fn os_filter_matches_return_bool(#[case] filter: OsFilter, #[case] expect: bool) {
+        let info = OsInfo::new([
+            OsKind::Any,
+            OsKind::Unix,
+            OsKind::Linux,
+            OsKind::Distro("ubuntu".into()),
+            OsKind::Distro("debian".into()),
+            OsKind::Wsl,
+        ]);
+        assert_eq!(filter.matches(&info), expect);
+    }
+
+    #[rstest]
+    #[case::no_conditions(BootstrapSettings::new("url"), true)]
+    #[case::all_hold(
+        BootstrapSettings::new("url")
+            .with_os(OsKind::Linux)
+            .with_users(patterns(["awkless"]))
+            .with_hosts(patterns(["build-*", "/^ci-\\d+$/"]))
+            .with_when("env(WORK) && !os(wsl)".parse().unwrap()),
+        true,
+    )]
+    #[case::user_mismatch(BootstrapSettings::new("url").with_users(patterns(["lovelace"])), false)]
+    #[case::when_fails(BootstrapSettings::new("url").with_when("host(lovelace)".parse().unwrap()), false)]
+    fn bootstrap_settings_applies_to_return_bool(
+        #[case] bootstrap: BootstrapSettings,
+        #[case] expect: bool,
+    ) {
+        let os = OsInfo::new([OsKind::Any, OsKind::Unix, OsKind::Linux]);
+        let host = HostInfo::new(os, "ci-42", "awkless").with_env("WORK", "1");
+        assert_eq!(bootstrap.applies_to(&host), expect);
+    }
+
     #[rstest]
     #[case::no_bootstrap(
         RepoSettings::new("foo", "main", "origin").with_bare_alias("$HOME"),
@@ -460,8 +1128,8 @@ fn repo_settings_from_key_item_return_self(
                     .with_os(OsKind::Unix)
                     .with_depends(["baz", "raz"])
                     .with_ignores(["README*", "LICENSE*"])
-                    .with_users(["awkless", "lovelace"])
-                    .with_hosts(["sedgwick", "dijkstra"])
+                    .with_users(patterns(["awkless", "lovelace"]))
+                    .with_hosts(patterns(["sedgwick", "dijkstra"]))
             ),
         indoc! {r#"
             [bar]
@@ -503,10 +1171,12 @@ fn repo_setings_to_toml_return_key_item(#[case] input: RepoSettings, #[case] exp
     fn cmd_hook_settings_from_key_item_return_self(
         cmd_hook_settings_doc: Result<DocumentMut, TomlError>,
         #[case] expect: CmdHookSettings,
-    ) -> Result<(), TomlError> {
-        let cmd_hook_settings_doc = cmd_hook_settings_doc?;
+    ) -> Result<(), Whatever> {
+        let cmd_hook_settings_doc =
+            cmd_hook_settings_doc.with_whatever_context(|_| "Failed to parse")?;
         let entry = cmd_hook_settings_doc.as_table().get_key_value(expect.cmd.as_str()).unwrap();
-        let result = CmdHookSettings::from(entry);
+        let result =
+            CmdHookSettings::try_from(entry).with_whatever_context(|_| "Failed to deserialize")?;
         assert_eq!(result, expect);
 
         Ok(())
@@ -542,4 +1212,46 @@ fn cmd_hook_settings_to_toml_return_key_item(
         table.set_implicit(true);
         assert_eq!(doc.to_string(), expect);
     }
+
+    #[derive(Debug, Default, Eq, PartialEq, Clone, Settings)]
+    struct CommentSettings {
+        #[settings(key)]
+        name: String,
+
+        #[settings(comment = "Branch to deploy.\nDefaults to master.")]
+        branch: String,
+
+        #[settings(trailing = "fetched first")]
+        remote: String,
+
+        #[settings(comment = "Clone on new hosts.")]
+        bootstrap: Option<BootstrapSettings>,
+    }
+
+    #[test]
+    fn settings_to_toml_place_comments() {
+        let input = CommentSettings {
+            name: "vim".into(),
+            branch: "main".into(),
+            remote: "origin".into(),
+            bootstrap: Some(BootstrapSettings::new("https://some/url")),
+        };
+        let expect = indoc! {r#"
+            [vim]
+            # Branch to deploy.
+            # Defaults to master.
+            branch = "main"
+            remote = "origin" # fetched first
+
+            # Clone on new hosts.
+            [vim.bootstrap]
+            clone = "https://some/url"
+        "#};
+        let (key, item) = input.to_toml();
+        let mut doc = DocumentMut::new();
+        let table = doc.as_table_mut();
+        table.insert_formatted(&key, item);
+        table.set_implicit(true);
+        assert_eq!(doc.to_string(), expect);
+    }
 }
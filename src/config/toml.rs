@@ -100,6 +100,47 @@ pub fn remove(
         Ok(entry)
     }
 
+    /// Merge another TOML document on top of this one.
+    ///
+    /// Tables are merged recursively, such that keys in `other` override keys
+    /// of the same name in this document. Any other item, including arrays and
+    /// inline tables, are replaced as a whole.
+    pub fn merge(&mut self, other: &Toml) {
+        trace!("Merge TOML documents");
+        merge_tables(self.doc.as_table_mut(), other.doc.as_table());
+    }
+
+    /// Determine if `table` contains item at nested key `path`.
+    pub fn contains(&self, table: impl AsRef<str>, path: &[&str]) -> bool {
+        let Ok(table) = self.get_table(table.as_ref()) else {
+            return false;
+        };
+
+        let mut iter = path.iter();
+        let Some(mut item) = iter.next().and_then(|key| table.get(key)) else {
+            return false;
+        };
+
+        for key in iter {
+            match item.as_table_like().and_then(|table| table.get(key)) {
+                Some(next) => item = next,
+                None => return false,
+            }
+        }
+
+        true
+    }
+
+    /// Get top-level item from TOML document.
+    pub fn get_item(&self, key: &str) -> Option<&Item> {
+        self.doc.get(key)
+    }
+
+    /// Coerces to mutable root [`Table`] of TOML document.
+    pub fn as_table_mut(&mut self) -> &mut Table {
+        self.doc.as_table_mut()
+    }
+
     pub fn get_table(&self, key: &str) -> Result<&Table, TomlError> {
         let table = self.doc.get(key).context(TableNotFoundSnafu { table: key })?;
         let table = table.as_table().context(NotTableSnafu { table: key })?;
@@ -115,6 +156,17 @@ pub fn get_table_mut(&mut self, key: &str) -> Result<&mut Table, TomlError> {
     }
 }
 
+fn merge_tables(base: &mut Table, overlay: &Table) {
+    for (key, item) in overlay.iter() {
+        match (base.get_mut(key), item) {
+            (Some(Item::Table(base)), Item::Table(overlay)) => merge_tables(base, overlay),
+            _ => {
+                base.insert(key, item.clone());
+            }
+        }
+    }
+}
+
 impl Display for Toml {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         write!(f, "{}", self.doc)
@@ -357,4 +409,69 @@ fn toml_remove_return_err(#[case] input: &str, #[case] expect: InnerTomlError) -
 
         Ok(())
     }
+
+    #[report]
+    #[rstest]
+    #[case::override_value(
+        toml_input(),
+        indoc! {r#"
+            [test]
+            foo = "overridden"
+        "#},
+        toml_input().replace(r#"foo = "hello""#, r#"foo = "overridden""#),
+    )]
+    #[case::extend_table(
+        toml_input(),
+        indoc! {r#"
+            [test]
+            baz = "extended"
+        "#},
+        formatdoc! {r#"
+            {}baz = "extended"
+        "#, toml_input()},
+    )]
+    #[case::add_table(
+        toml_input(),
+        indoc! {r#"
+            [new_test]
+            baz = "added"
+        "#},
+        formatdoc! {r#"
+            {}[new_test]
+            baz = "added"
+        "#, toml_input()},
+    )]
+    fn toml_merge_overlay_document(
+        #[case] base: String,
+        #[case] overlay: &str,
+        #[case] expect: String,
+    ) -> Result<()> {
+        let mut toml: Toml = base.parse()?;
+        let overlay: Toml = overlay.parse()?;
+        toml.merge(&overlay);
+        assert_eq!(toml.to_string(), expect);
+
+        Ok(())
+    }
+
+    #[report]
+    #[rstest]
+    #[case::entry(&["foo"], true)]
+    #[case::nested_entry(&["foo", "bar"], true)]
+    #[case::inline_entry(&["baz", "bar"], true)]
+    #[case::no_entry(&["nope"], false)]
+    #[case::no_nested_entry(&["foo", "nope"], false)]
+    fn toml_contains_return_bool(#[case] path: &[&str], #[case] expect: bool) -> Result<()> {
+        let toml: Toml = indoc! {r#"
+            [test.foo]
+            bar = true
+
+            [test]
+            baz = { bar = true }
+        "#}
+        .parse()?;
+        assert_eq!(toml.contains("test", path), expect);
+
+        Ok(())
+    }
 }
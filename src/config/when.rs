@@ -0,0 +1,385 @@
+// SPDX-FileCopyrightText: 2024 Jason Pena <jasonpena@awkless.com>
+// SPDX-License-Identifier: MIT
+
+use crate::config::{NamePattern, OsInfo, OsKind};
+
+use gethostname::gethostname;
+use snafu::prelude::*;
+use std::{
+    collections::HashMap,
+    env::{var, vars},
+    fmt::{Display, Formatter, Result as FmtResult},
+    str::FromStr,
+};
+
+/// Conditional expression over facts about current host.
+///
+/// Predicates are `os(KIND)`, `host(NAME)`, `user(NAME)`, `env(VAR)`, and
+/// `env(VAR=VALUE)`, along with the literals `true` and `false`. Names of
+/// hosts and users may be given as any [`NamePattern`]. Predicates can be
+/// combined with `!`, `&&`, and `||` in order of highest to lowest precedence,
+/// and grouped with parentheses. Arguments containing whitespace or
+/// parentheses must be double quoted, e.g.,
+/// `os(linux) && !os(wsl) && (host(turing) || env(WORK))`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct When {
+    source: String,
+    expr: Expr,
+}
+
+impl When {
+    /// Evaluate expression against facts about a host.
+    pub fn eval(&self, host: &HostInfo) -> bool {
+        self.expr.eval(host)
+    }
+
+    /// Parsed form of expression.
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+}
+
+impl FromStr for When {
+    type Err = WhenError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let expr = Parser::new(source).parse()?;
+        Ok(Self { source: source.into(), expr })
+    }
+}
+
+impl Display for When {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.source)
+    }
+}
+
+/// Abstract syntax tree of conditional expression.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Expr {
+    Bool(bool),
+    Os(OsKind),
+    Host(NamePattern),
+    User(NamePattern),
+    Env { name: String, value: Option<String> },
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// Evaluate expression against facts about a host.
+    pub fn eval(&self, host: &HostInfo) -> bool {
+        match self {
+            Expr::Bool(value) => *value,
+            Expr::Os(kind) => host.os.is(kind),
+            Expr::Host(pattern) => pattern.matches(&host.hostname),
+            Expr::User(pattern) => pattern.matches(&host.username),
+            Expr::Env { name, value: None } => {
+                host.env.get(name).is_some_and(|value| !value.is_empty())
+            }
+            Expr::Env { name, value: Some(expect) } => host.env.get(name) == Some(expect),
+            Expr::Not(expr) => !expr.eval(host),
+            Expr::And(lhs, rhs) => lhs.eval(host) && rhs.eval(host),
+            Expr::Or(lhs, rhs) => lhs.eval(host) || rhs.eval(host),
+        }
+    }
+
+    /// Operating system kinds that expression checks for.
+    pub fn os_kinds(&self) -> Vec<&OsKind> {
+        match self {
+            Expr::Os(kind) => vec![kind],
+            Expr::Not(expr) => expr.os_kinds(),
+            Expr::And(lhs, rhs) | Expr::Or(lhs, rhs) => {
+                let mut kinds = lhs.os_kinds();
+                kinds.extend(rhs.os_kinds());
+                kinds
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Facts about a host that conditional expressions are evaluated against.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct HostInfo {
+    pub os: OsInfo,
+    pub hostname: String,
+    pub username: String,
+    pub env: HashMap<String, String>,
+}
+
+impl HostInfo {
+    /// Construct host information from given facts with empty environment.
+    pub fn new(os: OsInfo, hostname: impl Into<String>, username: impl Into<String>) -> Self {
+        Self { os, hostname: hostname.into(), username: username.into(), env: HashMap::new() }
+    }
+
+    /// Detect facts about current host.
+    pub fn detect() -> Self {
+        let username = var("USER").or_else(|_| var("USERNAME")).unwrap_or_default();
+        Self {
+            os: OsInfo::detect(),
+            hostname: gethostname().to_string_lossy().into_owned(),
+            username,
+            env: vars().collect(),
+        }
+    }
+
+    pub fn with_env(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.insert(name.into(), value.into());
+        self
+    }
+}
+
+/// Recursive descent parser of conditional expressions.
+///
+/// ```text
+/// or    = and, { "||", and } ;
+/// and   = unary, { "&&", unary } ;
+/// unary = "!", unary | atom ;
+/// atom  = "(", or, ")" | "true" | "false" | name, "(", arg, ")" ;
+/// ```
+struct Parser<'src> {
+    source: &'src str,
+    pos: usize,
+}
+
+impl<'src> Parser<'src> {
+    fn new(source: &'src str) -> Self {
+        Self { source, pos: 0 }
+    }
+
+    fn parse(mut self) -> Result<Expr, WhenError> {
+        let expr = self.parse_or()?;
+        self.skip_whitespace();
+        if self.peek().is_some() {
+            return self.fail("'&&', '||', or end of expression");
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, WhenError> {
+        let mut lhs = self.parse_and()?;
+        while self.eat("||") {
+            let rhs = self.parse_and()?;
+            lhs = Expr::Or(Box::new(lhs), Box::new(rhs));
+        }
+
+        Ok(lhs)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, WhenError> {
+        let mut lhs = self.parse_unary()?;
+        while self.eat("&&") {
+            let rhs = self.parse_unary()?;
+            lhs = Expr::And(Box::new(lhs), Box::new(rhs));
+        }
+
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, WhenError> {
+        if self.eat("!") {
+            return Ok(Expr::Not(Box::new(self.parse_unary()?)));
+        }
+
+        self.parse_atom()
+    }
+
+    fn parse_atom(&mut self) -> Result<Expr, WhenError> {
+        if self.eat("(") {
+            let expr = self.parse_or()?;
+            self.expect(")")?;
+            return Ok(expr);
+        }
+
+        self.skip_whitespace();
+        let start = self.pos;
+        let name = self.take_while(|c| c.is_ascii_alphabetic());
+        let expr = match name {
+            "true" => return Ok(Expr::Bool(true)),
+            "false" => return Ok(Expr::Bool(false)),
+            "os" | "host" | "user" | "env" => {
+                self.expect("(")?;
+                let arg_pos = self.pos;
+                let arg = self.parse_arg()?;
+                let expr = match name {
+                    "os" => match OsKind::from_name(&arg) {
+                        Some(kind) => Expr::Os(kind),
+                        None => {
+                            self.pos = arg_pos;
+                            self.skip_whitespace();
+                            return self.fail("operating system kind");
+                        }
+                    },
+                    "host" | "user" => match arg.parse() {
+                        Ok(pattern) if name == "host" => Expr::Host(pattern),
+                        Ok(pattern) => Expr::User(pattern),
+                        Err(_) => {
+                            self.pos = arg_pos;
+                            self.skip_whitespace();
+                            return self.fail("name pattern");
+                        }
+                    },
+                    _ => match arg.split_once('=') {
+                        Some((name, value)) => {
+                            Expr::Env { name: name.into(), value: Some(value.into()) }
+                        }
+                        None => Expr::Env { name: arg, value: None },
+                    },
+                };
+                self.expect(")")?;
+                expr
+            }
+            _ => {
+                self.pos = start;
+                return self.fail("'!', '(', 'true', 'false', or predicate");
+            }
+        };
+
+        Ok(expr)
+    }
+
+    fn parse_arg(&mut self) -> Result<String, WhenError> {
+        self.skip_whitespace();
+        if self.eat("\"") {
+            let arg = self.take_while(|c| c != '"').to_string();
+            self.expect("\"")?;
+            return Ok(arg);
+        }
+
+        let arg = self.take_while(|c| !c.is_whitespace() && c != '(' && c != ')');
+        if arg.is_empty() {
+            return self.fail("argument");
+        }
+
+        Ok(arg.to_string())
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.take_while(char::is_whitespace);
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'src str {
+        let rest = &self.source[self.pos..];
+        let len = rest.find(|c| !predicate(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if self.source[self.pos..].starts_with(token) {
+            self.pos += token.len();
+            return true;
+        }
+
+        false
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), WhenError> {
+        if !self.eat(token) {
+            return self.fail(&format!("'{token}'"));
+        }
+
+        Ok(())
+    }
+
+    fn fail<T>(&self, expect: &str) -> Result<T, WhenError> {
+        let found = match self.peek() {
+            Some(c) => format!("'{c}'"),
+            None => "end of expression".into(),
+        };
+        let column = self.source[..self.pos].chars().count() + 1;
+        Err(WhenError { column, expect: expect.into(), found })
+    }
+}
+
+/// Parse error of conditional expression.
+#[derive(Debug, Snafu, Clone, Eq, PartialEq)]
+#[snafu(display("At column {column}, expected {expect}, found {found}"))]
+pub struct WhenError {
+    column: usize,
+    expect: String,
+    found: String,
+}
+
+impl WhenError {
+    /// Column of expression that parsing failed at, starting from 1.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+    use rstest::{fixture, rstest};
+    use snafu::{report, Whatever};
+
+    #[fixture]
+    fn host() -> HostInfo {
+        let os = OsInfo::new([
+            OsKind::Any,
+            OsKind::Unix,
+            OsKind::Linux,
+            OsKind::Distro("ubuntu".into()),
+            OsKind::Wsl,
+        ]);
+        HostInfo::new(os, "turing", "awkless").with_env("WORK", "1").with_env("SHELL", "bash")
+    }
+
+    #[rstest]
+    #[case::literal("true", true)]
+    #[case::os("os(linux)", true)]
+    #[case::distro("os(arch)", false)]
+    #[case::host("host(turing)", true)]
+    #[case::host_glob("host(tur*)", true)]
+    #[case::host_regex(r#"host("/^lovelace-\d+$/")"#, false)]
+    #[case::quoted_arg(r#"user("awkless")"#, true)]
+    #[case::env_set("env(WORK)", true)]
+    #[case::env_unset("env(HOME_LAB)", false)]
+    #[case::env_value("env(SHELL=zsh)", false)]
+    #[case::not("!os(wsl)", false)]
+    #[case::precedence("false && true || true", true)]
+    #[case::grouping("false && (true || true)", false)]
+    #[case::double_not("!!true", true)]
+    #[case::combined("os(linux) && (host(lovelace) || env(WORK))", true)]
+    #[case::work_laptop_without_wsl("env(WORK) && !os(wsl)", false)]
+    #[case::whitespace("  os( linux )&&user(awkless)  ", true)]
+    #[report]
+    fn when_eval_return_bool(
+        host: HostInfo,
+        #[case] input: &str,
+        #[case] expect: bool,
+    ) -> Result<(), Whatever> {
+        let when: When = input.parse().with_whatever_context(|_| "Failed to parse")?;
+        assert_eq!(when.eval(&host), expect);
+        assert_eq!(when.to_string(), input);
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::empty("", 1, "end of expression")]
+    #[case::unknown_predicate("os(linux) && arch(x86)", 14, "'a'")]
+    #[case::unclosed_group("(os(linux) || true", 19, "end of expression")]
+    #[case::missing_arg("host()", 6, "')'")]
+    #[case::bad_os("os(Be OS)", 4, "'B'")]
+    #[case::bad_pattern("host([turing)", 6, "'['")]
+    #[case::trailing_operand("true false", 6, "'f'")]
+    #[case::single_ampersand("true & false", 6, "'&'")]
+    fn when_from_str_return_err(#[case] input: &str, #[case] column: usize, #[case] found: &str) {
+        let result = input.parse::<When>().unwrap_err();
+        assert_eq!(result.column(), column);
+        assert_eq!(result.found, found);
+    }
+}
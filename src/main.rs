@@ -11,13 +11,19 @@
 mod testenv;
 
 use crate::{
-    cli::{Cli, CliError, Ctx},
-    config::{ConfigError, ConfigFile, LocateError, RepoConfig, XdgLocator},
-    repo::{RepoManager, RepoManagerError},
+    cli::{
+        Cli, CliError, ConfigAction, ConfigCtx, Ctx, GraphFormat, ListAction, SchemaFile,
+        VcsBackend,
+    },
+    config::{
+        CmdHookConfig, Config, ConfigError, ConfigFile, CustomLocator, HostInfo, LocateError,
+        Locator, PortableLocator, RepoConfig, XdgLocator, CONFIG_VERSION,
+    },
+    repo::{Git, RepoManager, RepoManagerError, Vcs, VcsError},
 };
 
 use env_logger::Builder as EnvLogBuilder;
-use log::{error, LevelFilter};
+use log::{error, info, LevelFilter};
 use snafu::{prelude::*, Report};
 use std::{env::args_os, ffi::OsString, process::exit};
 
@@ -50,21 +56,148 @@ fn run<I, F>(args: F) -> Result<ExitCode, BinError>
     let opts = Cli::parse_args(args()).context(CliSnafu)?;
     log::set_max_level(opts.log_opts.log_level_filter());
 
+    let home_dir = opts.shared_opts.home_dir.clone();
+    let config_dir = opts.shared_opts.config_dir.clone();
+    let data_dir = opts.shared_opts.data_dir.clone();
+    let backend = opts.shared_opts.vcs_backend;
     let ctx = Ctx::from(opts);
-    let locator = XdgLocator::locate().context(LocatorSnafu)?;
-    let config = ConfigFile::load(RepoConfig, &locator).context(ConfigFileSnafu)?;
-    let mut repo_mgr = RepoManager::manage(config, &locator).context(RepoManagerSnafu)?;
+
+    if home_dir.is_some() || config_dir.is_some() || data_dir.is_some() {
+        let locator =
+            CustomLocator::locate(home_dir, config_dir, data_dir).context(LocatorSnafu)?;
+        run_cmd(ctx, &locator, backend)
+    } else if let Some(root_dir) = PortableLocator::find() {
+        let locator = PortableLocator::locate(root_dir).context(LocatorSnafu)?;
+        run_cmd(ctx, &locator, backend)
+    } else {
+        let locator = XdgLocator::locate().context(LocatorSnafu)?;
+        run_cmd(ctx, &locator, backend)
+    }
+}
+
+fn run_cmd(ctx: Ctx, locator: &impl Locator, backend: VcsBackend) -> Result<ExitCode, BinError> {
+    if let Ctx::Config(ctx) = ctx {
+        return run_config_cmd(ctx, locator);
+    }
+
+    match backend {
+        VcsBackend::Git => {
+            Git::version_check().context(VcsSnafu)?;
+            run_repo_cmd(ctx, locator, Git::new())
+        }
+        #[cfg(feature = "gix")]
+        VcsBackend::Gix => run_repo_cmd(ctx, locator, repo::Gix::new()),
+    }
+}
+
+fn run_repo_cmd(ctx: Ctx, locator: &impl Locator, vcs: impl Vcs) -> Result<ExitCode, BinError> {
+    let config = ConfigFile::load(RepoConfig, locator).context(ConfigFileSnafu)?;
+    let mut repo_mgr = RepoManager::manage(config, locator, vcs).context(RepoManagerSnafu)?;
 
     match ctx {
         Ctx::Init(ctx) => {
             repo_mgr.init(ctx.name, ctx.branch, ctx.bare_alias).context(RepoManagerSnafu)?
         }
+        Ctx::Clone(ctx) => repo_mgr.clone(ctx.remote, ctx.repo).context(RepoManagerSnafu)?,
+        Ctx::Deploy(ctx) => {
+            let host = HostInfo::detect();
+            repo_mgr.deploy(ctx.repos, &host, ctx.locked).context(RepoManagerSnafu)?
+        }
+        Ctx::Graph(ctx) => {
+            let host = HostInfo::detect();
+            let graph = repo_mgr.graph(ctx.repo.as_deref(), &host).context(RepoManagerSnafu)?;
+            let output = match ctx.format {
+                GraphFormat::Tree => graph.to_tree(),
+                GraphFormat::Dot => graph.to_dot(),
+                GraphFormat::Mermaid => graph.to_mermaid(),
+            };
+            print!("{output}");
+        }
+        Ctx::List(ctx) => {
+            for summary in repo_mgr.list().context(RepoManagerSnafu)? {
+                let listed = match ctx.show {
+                    ListAction::All => true,
+                    ListAction::Deployed => summary.deployed,
+                    ListAction::Undeployed => !summary.deployed,
+                };
+                if listed {
+                    println!("{summary}");
+                }
+            }
+        }
+        Ctx::Status(ctx) => {
+            for (name, status) in repo_mgr.status(ctx.untracked).context(RepoManagerSnafu)? {
+                let output = if ctx.terse { status.to_short() } else { status.to_long() };
+                println!("# {name}");
+                print!("{output}");
+            }
+        }
+        Ctx::Lock(_) => {
+            let lockfile = repo_mgr.lock().context(RepoManagerSnafu)?;
+            let path = repo_mgr.lock_path();
+            info!("Locked {} repositories in '{}'", lockfile.iter().count(), path.display());
+        }
         _ => todo!(),
     };
 
     Ok(ExitCode::Success)
 }
 
+fn run_config_cmd(ctx: ConfigCtx, locator: &impl Locator) -> Result<ExitCode, BinError> {
+    match ctx.action {
+        ConfigAction::Migrate { dry_run } => {
+            migrate_config(RepoConfig, locator, dry_run)?;
+            migrate_config(CmdHookConfig, locator, dry_run)?;
+        }
+        ConfigAction::Schema { file } => {
+            let schema = match file {
+                SchemaFile::Repos => RepoConfig.schema(),
+                SchemaFile::Hooks => CmdHookConfig.schema(),
+            };
+            println!("{schema:#}");
+        }
+    }
+
+    Ok(ExitCode::Success)
+}
+
+fn migrate_config(
+    config: impl Config,
+    locator: &impl Locator,
+    dry_run: bool,
+) -> Result<(), BinError> {
+    // Dry runs must not create missing configuration files.
+    let mut config = if dry_run {
+        for (layer, path) in config.layers(locator) {
+            if !path.exists() {
+                println!("# No file for {layer} layer at '{}'", path.display());
+            }
+        }
+        ConfigFile::load_existing(config, locator)
+    } else {
+        ConfigFile::load(config, locator)
+    }
+    .context(ConfigFileSnafu)?;
+
+    for layer in config.layers().iter().filter(|layer| layer.as_path().exists()) {
+        let path = layer.as_path().display();
+        match layer.migrated_from() {
+            Some(version) if dry_run => {
+                println!("# Migrate '{path}' from version {version} to {CONFIG_VERSION}");
+                println!("{layer}");
+            }
+            Some(version) => info!("Migrate '{path}' from version {version} to {CONFIG_VERSION}"),
+            None => info!("Configuration file '{path}' is up to date"),
+        }
+    }
+
+    if !dry_run {
+        config.save_migrated().context(ConfigFileSnafu)?;
+    }
+
+    Ok(())
+}
+
 #[derive(Debug)]
 enum ExitCode {
     Success,
@@ -93,4 +226,41 @@ pub enum BinError {
 
     #[snafu(display("dotfile-ocd repository manager failure"))]
     RepoManager { source: RepoManagerError },
+
+    #[snafu(display("dotfile-ocd version control failure"))]
+    Vcs { source: VcsError },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{config::MockLocator, testenv::FixtureHarness};
+
+    use rstest::rstest;
+    use snafu::{report, Whatever};
+    use std::fs::read_dir;
+
+    #[report]
+    #[rstest]
+    fn migrate_config_dry_run_keep_config_dir_empty() -> Result<(), Whatever> {
+        let harness = FixtureHarness::open()?;
+        let config_dir = harness.as_path();
+        let mut locator = MockLocator::new();
+        locator.expect_repo_config_file().return_const(config_dir.join("repos.toml"));
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
+        locator.expect_hook_config_file().return_const(config_dir.join("hooks.toml"));
+        locator.expect_system_hook_config_file().return_const(None);
+
+        migrate_config(RepoConfig, &locator, true)
+            .with_whatever_context(|_| "Failed to migrate repository configuration")?;
+        migrate_config(CmdHookConfig, &locator, true)
+            .with_whatever_context(|_| "Failed to migrate hook configuration")?;
+        let entries = read_dir(config_dir)
+            .with_whatever_context(|_| "Failed to read configuration directory")?;
+        assert_eq!(entries.count(), 0);
+
+        Ok(())
+    }
 }
@@ -2,56 +2,79 @@
 // SPDX-License-Identifier: MIT
 
 mod deps;
+mod graph;
+mod lock;
 mod vcs;
 
 #[doc(inline)]
 pub use deps::*;
+pub use graph::*;
+pub use lock::*;
 pub use vcs::*;
 
-use crate::config::{ConfigError, ConfigFile, Locator, RepoConfig, RepoSettings};
+use crate::config::{
+    ConfigError, ConfigFile, HostInfo, Locator, NamePattern, OsInfo, OsKind, RepoConfig,
+    RepoSettings, RevInfo,
+};
 
+use log::{info, warn};
 use snafu::prelude::*;
-use std::{collections::HashSet, path::PathBuf};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{Display, Formatter, Result as FmtResult},
+    hash::Hash,
+    path::PathBuf,
+};
 
 /// Manage repository collection.
+///
+/// Version control operations are delegated to a [`Vcs`] backend.
 #[derive(Debug)]
-pub struct RepoManager<'repo, L>
+pub struct RepoManager<'repo, L, V>
 where
     L: Locator,
+    V: Vcs,
 {
-    git: Git,
+    vcs: V,
     config: ConfigFile<'repo, RepoConfig, L>,
     locator: &'repo L,
     deps: Dependencies,
 }
 
-impl<'repo, L> RepoManager<'repo, L>
+impl<'repo, L, V> RepoManager<'repo, L, V>
 where
     L: Locator,
+    V: Vcs,
 {
-    /// Construct new repository manager.
+    /// Construct new repository manager using given version control backend.
     ///
     /// # Errors
     ///
-    /// Will fail if duplicate entries are found in array values of bootstrap
-    /// configuration field, or a circular dependency is found.
+    /// Will fail if repository settings are malformed, duplicate entries are
+    /// found in array values of bootstrap configuration field, a dependency
+    /// names an unknown repository, or a circular dependency is found.
     pub fn manage(
         config: ConfigFile<'repo, RepoConfig, L>,
         locator: &'repo L,
+        vcs: V,
     ) -> Result<Self, RepoManagerError> {
         duplicate_settings_check(&config)?;
+        unknown_os_check(&config, &OsInfo::detect())?;
 
         let mut deps = Dependencies::new();
-        deps.with_config_file(&config);
+        deps.with_config_file(&config).context(ConfigFileSnafu)?;
+        deps.known_check().context(DependencySnafu)?;
         deps.acyclic_check().context(DependencySnafu)?;
 
-        Ok(Self { git: Git::new(), config, locator, deps })
+        Ok(Self { vcs, config, locator, deps })
     }
 
     /// Initialize new repository.
     ///
-    /// Initialize repository through Git, and add an entry for it in special
-    /// configuration file.
+    /// Initialize repository through version control backend, and add an
+    /// entry for it in special configuration file. Bare-alias repositories are
+    /// configured to hide untracked files, because their work tree is usually
+    /// the home directory of the user.
     ///
     /// # Errors
     ///
@@ -64,51 +87,520 @@ pub fn init(
         bare_alias: Option<PathBuf>,
     ) -> Result<(), RepoManagerError> {
         let branch = if let Some(branch) = branch { branch.to_string() } else { "master".into() };
-        self.git.with_arg("init");
 
         let mut repo = RepoSettings::new(&name, &branch, "origin");
         if let Some(alias) = bare_alias {
             repo = repo.with_bare_alias(alias.to_path_buf());
-            self.git.with_arg("--bare");
         }
 
-        let dir_path = self.locator.repos_dir().join(&name).to_string_lossy().into_owned();
-        self.git.with_args(["--initial-branc", &branch, &dir_path]);
-        self.git.run().context(GitSnafu)?;
+        let path = self.locator.repos_dir().join(&name);
+        self.vcs.init(&path, &branch, repo.bare_alias.is_some()).context(VcsSnafu)?;
+        self.hide_untracked(&repo)?;
         self.config.add(repo).context(ConfigFileSnafu)?;
         self.config.save().context(ConfigFileSnafu)?;
 
         Ok(())
     }
+
+    /// Clone repository from remote.
+    ///
+    /// Repository is named `name`, or after last component of `url` if no
+    /// name is given. Branch and bare alias are taken from existing entry of
+    /// that name in special configuration file. Otherwise, a new entry is
+    /// added for it. Bare-alias repositories are configured to hide untracked
+    /// files, just like initialized ones.
+    ///
+    /// # Errors
+    ///
+    /// Will fail if no name can be derived from `url`, repository settings are
+    /// malformed, repository cannot be cloned, or new entry cannot be added
+    /// into configuration file.
+    pub fn clone(&mut self, url: String, name: Option<String>) -> Result<(), RepoManagerError> {
+        let name = match name {
+            Some(name) => name,
+            None => name_from_url(&url).context(UnnamedRemoteSnafu { url: url.clone() })?,
+        };
+
+        let mut existing = None;
+        for repo in self.config.iter() {
+            let repo = repo.context(ConfigFileSnafu)?;
+            if repo.name == name {
+                existing = Some(repo);
+                break;
+            }
+        }
+        let repo = existing.clone().unwrap_or_else(|| RepoSettings::new(&name, "master", "origin"));
+
+        let path = self.locator.repos_dir().join(&name);
+        info!("Clone '{url}' into '{}'", path.display());
+        self.vcs
+            .clone_repo(&url, &path, &repo.branch, repo.bare_alias.is_some())
+            .context(VcsSnafu)?;
+        self.hide_untracked(&repo)?;
+        if existing.is_none() {
+            self.config.add(repo).context(ConfigFileSnafu)?;
+            self.config.save().context(ConfigFileSnafu)?;
+        }
+
+        Ok(())
+    }
+
+    /// Hide untracked files from status of bare-alias repository.
+    fn hide_untracked(&self, repo: &RepoSettings) -> Result<(), RepoManagerError> {
+        if repo.bare_alias.is_some() {
+            let path = self.locator.repos_dir().join(&repo.name);
+            self.vcs
+                .set_config(&RepoPaths::bare(path), "status.showUntrackedFiles", "no")
+                .context(VcsSnafu)?;
+        }
+
+        Ok(())
+    }
+
+    /// Deploy target repositories.
+    ///
+    /// Hard dependencies of targets are deployed along with them, while
+    /// recommended dependencies are only deployed if their bootstrap filters
+    /// match given host. Bare-alias repositories are deployed by checking them
+    /// out to their alias. If `locked` is set, then every repository is checked
+    /// out to the exact commit recorded in lockfile instead.
+    ///
+    /// # Errors
+    ///
+    /// Will fail if a target is not a known repository, a repository to deploy
+    /// conflicts with another repository to deploy or an already deployed
+    /// repository, a repository has not been cloned, a deployed repository to
+    /// check out again has uncommitted changes, a repository cannot be
+    /// checked out, or `locked` is set and lockfile cannot be loaded, does not
+    /// record a repository to deploy, or records a commit that has not been
+    /// fetched yet.
+    pub fn deploy(
+        &mut self,
+        repos: Vec<String>,
+        host: &HostInfo,
+        locked: bool,
+    ) -> Result<(), RepoManagerError> {
+        let lockfile = match locked {
+            true => Some(Lockfile::load(&self.lock_path()).context(LockSnafu)?),
+            false => None,
+        };
+
+        for name in self.deploy_plan(&repos, host)? {
+            let repo = self.config.get(&name).context(ConfigFileSnafu)?;
+            let commit = match &lockfile {
+                Some(lockfile) => {
+                    let locked = lockfile.get(&name).context(NotLockedSnafu { name: &name })?;
+                    Some(locked.commit.clone())
+                }
+                None => None,
+            };
+            if commit.is_none() && self.is_deployed(&repo) {
+                info!("Repository '{name}' is already deployed");
+                continue;
+            }
+
+            let path = self.locator.repos_dir().join(&name);
+            ensure!(path.exists(), NotClonedSnafu { name });
+            let paths = self.repo_paths(&repo);
+            if let Some(commit) = &commit {
+                let present = self.vcs.has_commit(&paths, commit).context(VcsSnafu)?;
+                ensure!(present, CommitNotPresentSnafu { name, commit });
+            }
+            if self.is_deployed(&repo) {
+                let status = self.vcs.status(&paths, false).context(VcsSnafu)?;
+                ensure!(!status.has_tracked_changes(), UncommittedChangesSnafu { name });
+            }
+            if repo.bare_alias.is_some() || commit.is_some() {
+                self.vcs.checkout(&paths, commit.as_deref()).context(VcsSnafu)?;
+            }
+            info!("Deployed repository '{name}'");
+        }
+
+        Ok(())
+    }
+
+    /// Record exact commits of all cloned repositories into lockfile.
+    ///
+    /// Lockfile is written next to repository configuration file. Remote URL
+    /// of each repository is taken from its remote, or its bootstrap clone URL
+    /// if its remote is not set up. Repositories that have not been cloned,
+    /// have no commits yet, or have no URL to record, are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Will fail if repository settings are malformed, or lockfile cannot be
+    /// written.
+    pub fn lock(&self) -> Result<Lockfile, RepoManagerError> {
+        let mut lockfile = Lockfile::new();
+        for repo in self.config.iter() {
+            let repo = repo.context(ConfigFileSnafu)?;
+            let Some(info) = self.rev_info(&repo) else {
+                warn!("Skip locking repository '{}', it has no commits to lock", repo.name);
+                continue;
+            };
+
+            let url = match self.vcs.remote_url(&self.repo_paths(&repo), &repo.remote) {
+                Ok(url) => url,
+                Err(error) => match &repo.bootstrap {
+                    Some(bootstrap) => {
+                        warn!(
+                            "Lock repository '{}' with bootstrap clone URL, remote '{}' is not \
+                             usable: {error}",
+                            repo.name, repo.remote
+                        );
+                        bootstrap.clone.clone()
+                    }
+                    None => {
+                        warn!(
+                            "Skip locking repository '{}', remote '{}' is not usable: {error}",
+                            repo.name, repo.remote
+                        );
+                        continue;
+                    }
+                },
+            };
+
+            self.unlocked_changes_check(&repo, &info.commit)?;
+            lockfile = lockfile.with_repo(LockedRepo {
+                name: repo.name,
+                url,
+                branch: repo.branch,
+                commit: info.commit,
+            });
+        }
+        lockfile.save(&self.lock_path()).context(LockSnafu)?;
+
+        Ok(lockfile)
+    }
+
+    /// Path to lockfile.
+    pub fn lock_path(&self) -> PathBuf {
+        self.locator.repo_config_file().with_file_name(LOCK_FILE_NAME)
+    }
+
+    /// Warn about changes of repository that locking `commit` leaves out.
+    ///
+    /// Uncommitted changes are not recorded at all, and commits that have not
+    /// been pushed cannot be fetched by anyone deploying from lockfile.
+    fn unlocked_changes_check(
+        &self,
+        repo: &RepoSettings,
+        commit: &str,
+    ) -> Result<(), RepoManagerError> {
+        let paths = self.repo_paths(repo);
+        let status = self.vcs.status(&paths, false).context(VcsSnafu)?;
+        if status.has_tracked_changes() {
+            warn!("Repository '{}' has uncommitted changes that are not locked", repo.name);
+        }
+
+        let tracking = format!("refs/remotes/{}/{}", repo.remote, repo.branch);
+        if self.vcs.refs(&paths, &[&tracking]).context(VcsSnafu)?.is_empty() {
+            warn!(
+                "Repository '{}' has no remote-tracking branch '{}/{}' to fetch locked commit from",
+                repo.name, repo.remote, repo.branch
+            );
+            return Ok(());
+        }
+
+        let counts = self.vcs.ahead_behind(&paths, commit, &tracking).context(VcsSnafu)?;
+        if counts.ahead > 0 {
+            warn!(
+                "Locked commit of repository '{}' is {} commits ahead of '{}/{}', push it first",
+                repo.name, counts.ahead, repo.remote, repo.branch
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Show status of all cloned repositories.
+    ///
+    /// Status of each repository is paired with its name. Untracked files are
+    /// listed if `untracked` is set, or repository does not hide them.
+    /// Repositories that have not been cloned are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Will fail if repository settings are malformed, or status of a
+    /// repository cannot be determined.
+    pub fn status(&self, untracked: bool) -> Result<Vec<(String, StatusReport)>, RepoManagerError> {
+        let mut reports = Vec::new();
+        for repo in self.config.iter() {
+            let repo = repo.context(ConfigFileSnafu)?;
+            let paths = self.repo_paths(&repo);
+            if !paths.root().exists() {
+                warn!("Skip status of repository '{}', it has not been cloned", repo.name);
+                continue;
+            }
+
+            let status = self.vcs.status(&paths, untracked).context(VcsSnafu)?;
+            reports.push((repo.name, status));
+        }
+
+        Ok(reports)
+    }
+
+    /// Summarize all repositories in order of configuration.
+    ///
+    /// # Errors
+    ///
+    /// Will fail if repository settings are malformed, or status of a cloned
+    /// repository cannot be determined.
+    pub fn list(&self) -> Result<Vec<RepoSummary>, RepoManagerError> {
+        let mut summaries = Vec::new();
+        for repo in self.config.iter() {
+            let repo = repo.context(ConfigFileSnafu)?;
+            let paths = self.repo_paths(&repo);
+            let status = match paths.root().exists() {
+                true => Some(self.vcs.status(&paths, false).context(VcsSnafu)?),
+                false => None,
+            };
+            summaries.push(RepoSummary {
+                deployed: self.is_deployed(&repo),
+                name: repo.name,
+                status,
+            });
+        }
+
+        Ok(summaries)
+    }
+
+    /// Determine repositories to deploy for target repositories in order.
+    fn deploy_plan(
+        &self,
+        repos: &[String],
+        host: &HostInfo,
+    ) -> Result<Vec<String>, RepoManagerError> {
+        let settings = self
+            .config
+            .iter()
+            .map(|repo| repo.map(|repo| (repo.name.clone(), repo)))
+            .collect::<Result<HashMap<_, _>, _>>()
+            .context(ConfigFileSnafu)?;
+        for name in repos {
+            ensure!(settings.contains_key(name), UnknownRepoSnafu { name });
+        }
+
+        let plan = self.deps.resolve(repos, |name| {
+            settings[name].bootstrap.as_ref().map_or(true, |bootstrap| bootstrap.applies_to(host))
+        });
+        self.deps
+            .conflict_check(&plan, |name| self.is_deployed(&settings[name]))
+            .context(DependencySnafu)?;
+        self.deps
+            .rev_check(&plan, |name| self.rev_info(&settings[name]))
+            .context(DependencySnafu)?;
+
+        Ok(plan)
+    }
+
+    /// Determine checked out revision of repository.
+    ///
+    /// Return [`None`] if repository has not been cloned, or has no commits.
+    fn rev_info(&self, repo: &RepoSettings) -> Option<RevInfo> {
+        let paths = self.repo_paths(repo);
+        if !paths.root().exists() {
+            return None;
+        }
+
+        self.vcs.rev_info(&paths).ok()
+    }
+
+    /// Paths of repository in repository store.
+    fn repo_paths(&self, repo: &RepoSettings) -> RepoPaths {
+        let path = self.locator.repos_dir().join(&repo.name);
+        match &repo.bare_alias {
+            Some(alias) => RepoPaths::bare_alias(path, alias),
+            None => RepoPaths::normal(path),
+        }
+    }
+
+    /// Render dependency graph of repositories.
+    ///
+    /// Each repository is annotated with its deploy state, and whether its
+    /// bootstrap filters match given host. Graph is rooted at given repository
+    /// if any.
+    ///
+    /// # Errors
+    ///
+    /// Will fail if repository settings are malformed, or given root is not a
+    /// known repository.
+    pub fn graph(
+        &self,
+        root: Option<&str>,
+        host: &HostInfo,
+    ) -> Result<DependencyGraph<'_>, RepoManagerError> {
+        let mut graph = DependencyGraph::new(&self.deps);
+        let mut known = false;
+        for repo in self.config.iter() {
+            let repo = repo.context(ConfigFileSnafu)?;
+            known |= root == Some(repo.name.as_str());
+            let applies = repo.bootstrap.as_ref().map_or(true, |b| b.applies_to(host));
+            let state = RepoState { deployed: self.is_deployed(&repo), applies };
+            graph = graph.with_state(repo.name, state);
+        }
+
+        if let Some(root) = root {
+            ensure!(known, UnknownRepoSnafu { name: root });
+            graph = graph.with_root(root);
+        }
+
+        Ok(graph)
+    }
+
+    /// Check if repository has been deployed.
+    ///
+    /// Normal repositories are deployed once they exist in repository store.
+    /// Bare-alias repositories are deployed once they have been checked out
+    /// to their alias, which gives them an index.
+    fn is_deployed(&self, repo: &RepoSettings) -> bool {
+        let path = self.locator.repos_dir().join(&repo.name);
+        match repo.bare_alias {
+            Some(_) => path.join("index").exists(),
+            None => path.exists(),
+        }
+    }
+}
+
+/// Summary of repository in collection.
+///
+/// Displayed as a single line, e.g., `vim deployed main...origin/main [ahead
+/// 1] modified`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RepoSummary {
+    pub name: String,
+    pub deployed: bool,
+
+    /// Status of repository, or [`None`] if it has not been cloned.
+    pub status: Option<StatusReport>,
+}
+
+impl Display for RepoSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let state = if self.deployed { "deployed" } else { "undeployed" };
+        write!(f, "{} {state}", self.name)?;
+        match &self.status {
+            Some(status) if status.has_tracked_changes() => {
+                write!(f, " {} modified", status.branch)
+            }
+            Some(status) => write!(f, " {}", status.branch),
+            None => write!(f, " not cloned"),
+        }
+    }
+}
+
+/// Name repository after last component of its remote `url`, e.g., `vim`
+/// for `git@host:user/vim.git`.
+fn name_from_url(url: &str) -> Option<String> {
+    let name = url.trim_end_matches('/').rsplit(['/', ':']).next()?;
+    let name = name.strip_suffix(".git").unwrap_or(name);
+
+    (!name.is_empty()).then(|| name.to_string())
 }
 
 fn duplicate_settings_check(
     config: &ConfigFile<'_, RepoConfig, impl Locator>,
 ) -> Result<(), InnerRepoManagerError> {
-    let repos: Vec<RepoSettings> = config.iter().collect();
+    let repos = config.iter().collect::<Result<Vec<RepoSettings>, _>>().context(ConfigFileSnafu)?;
     for repo in repos {
         if let Some(bootstrap) = repo.bootstrap {
-            find_duplicates(&bootstrap.depends, &format!("{}.bootstrap.depends", repo.name))?;
+            let depends = bootstrap
+                .depends
+                .as_ref()
+                .map(|depends| depends.iter().map(|dep| dep.repo.clone()).collect());
+            find_duplicates(&depends, &format!("{}.bootstrap.depends", repo.name))?;
+            find_duplicates(&bootstrap.recommends, &format!("{}.bootstrap.recommends", repo.name))?;
+            find_duplicates(&bootstrap.conflicts, &format!("{}.bootstrap.conflicts", repo.name))?;
             find_duplicates(&bootstrap.ignores, &format!("{}.bootstrap.ignores", repo.name))?;
             find_duplicates(&bootstrap.users, &format!("{}.bootstrap.users", repo.name))?;
             find_duplicates(&bootstrap.hosts, &format!("{}.bootstrap.hosts", repo.name))?;
+            warn_redundant(&bootstrap.users, &format!("{}.bootstrap.users", repo.name));
+            warn_redundant(&bootstrap.hosts, &format!("{}.bootstrap.hosts", repo.name));
         }
     }
 
     Ok(())
 }
 
-fn find_duplicates(
-    entries: &Option<Vec<String>>,
-    setting_name: &str,
+fn unknown_os_check(
+    config: &ConfigFile<'_, RepoConfig, impl Locator>,
+    host: &OsInfo,
 ) -> Result<(), InnerRepoManagerError> {
+    let repos = config.iter().collect::<Result<Vec<RepoSettings>, _>>().context(ConfigFileSnafu)?;
+    for repo in &repos {
+        for (setting_name, kind) in find_unknown_os(repo, host) {
+            warn!(
+                "Repository setting '{setting_name}' operating system kind '{kind}' is not a \
+                 known Linux distribution ID, and does not describe current host, expected {}",
+                OsKind::NAMES
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Find operating system kinds of repository that are likely typos on host.
+fn find_unknown_os<'repo>(
+    repo: &'repo RepoSettings,
+    host: &OsInfo,
+) -> Vec<(String, &'repo OsKind)> {
+    let mut unknown = Vec::new();
+    if let Some(bootstrap) = &repo.bootstrap {
+        if let Some(os) = &bootstrap.os {
+            let setting_name = format!("{}.bootstrap.os", repo.name);
+            for pattern in &os.patterns {
+                unknown.push((setting_name.clone(), &pattern.kind));
+            }
+        }
+        if let Some(when) = &bootstrap.when {
+            let setting_name = format!("{}.bootstrap.when", repo.name);
+            for kind in when.expr().os_kinds() {
+                unknown.push((setting_name.clone(), kind));
+            }
+        }
+    }
+    unknown.retain(|(_, kind)| kind.is_unknown_distro(host));
+
+    unknown
+}
+
+fn warn_redundant(entries: &Option<Vec<NamePattern>>, setting_name: &str) {
+    for (pattern, redundant) in find_redundant(entries) {
+        warn!(
+            "Repository setting '{setting_name}' entry '{redundant}' is redundant with '{pattern}'"
+        );
+    }
+}
+
+/// Find pairs of patterns where the first pattern makes the second redundant.
+fn find_redundant(entries: &Option<Vec<NamePattern>>) -> Vec<(&NamePattern, &NamePattern)> {
+    let mut redundant = Vec::new();
+    if let Some(entries) = entries {
+        for pattern in entries {
+            for other in entries.iter().filter(|other| pattern.covers(other)) {
+                redundant.push((pattern, other));
+            }
+        }
+    }
+
+    redundant
+}
+
+fn find_duplicates<T>(
+    entries: &Option<Vec<T>>,
+    setting_name: &str,
+) -> Result<(), InnerRepoManagerError>
+where
+    T: Eq + Hash + Display,
+{
     if let Some(entries) = entries {
         let mut seen = HashSet::new();
         let mut duplicates = Vec::new();
 
         for entry in entries {
             if !seen.insert(entry) {
-                duplicates.push(entry.clone());
+                duplicates.push(entry.to_string());
             }
         }
 
@@ -136,8 +628,34 @@ enum InnerRepoManagerError {
     #[snafu(display("Dependency management failure"))]
     Dependency { source: DependencyError },
 
-    #[snafu(display("Git system call failure"))]
-    Git { source: GitError },
+    #[snafu(display("Version control failure"))]
+    Vcs { source: VcsError },
+
+    #[snafu(display("Lockfile management failure"))]
+    Lock { source: LockError },
+
+    #[snafu(display("Repository '{name}' is not recorded in lockfile, run 'ocd lock' first"))]
+    NotLocked { name: String },
+
+    #[snafu(display(
+        "Locked commit '{commit}' of repository '{name}' is not present, fetch it first"
+    ))]
+    CommitNotPresent { name: String, commit: String },
+
+    #[snafu(display(
+        "Repository '{name}' has uncommitted changes that checkout would overwrite, commit or \
+         stash them first"
+    ))]
+    UncommittedChanges { name: String },
+
+    #[snafu(display("Repository '{name}' is not defined"))]
+    UnknownRepo { name: String },
+
+    #[snafu(display("Repository '{name}' has not been cloned"))]
+    NotCloned { name: String },
+
+    #[snafu(display("Cannot name repository after '{url}', name it explicitly"))]
+    UnnamedRemote { url: String },
 
     #[snafu(display("Repository setting '{setting}' contains duplicate entries: '{:?}'"))]
     DuplicateSettingValues { setting: String, duplicates: Vec<String> },
@@ -148,11 +666,11 @@ mod tests {
     use super::*;
 
     use crate::{
-        config::MockLocator,
+        config::{BootstrapSettings, MockLocator, OsFilter},
         testenv::{FileKind, FixtureHarness},
     };
 
-    use indoc::indoc;
+    use indoc::{formatdoc, indoc};
     use pretty_assertions::assert_eq;
     use rstest::{fixture, rstest};
     use snafu::{report, Whatever};
@@ -175,6 +693,96 @@ fn config_dir() -> Result<FixtureHarness, Whatever> {
                         ignores = ["LICENSE*", "README*"]
                         users = ["awkless", "lovelace"]
                         hosts = ["lovelace", "turing"]
+
+                        [repos.foo]
+                        branch = "master"
+                        remote = "origin"
+
+                        [repos.baz]
+                        branch = "master"
+                        remote = "origin"
+                    "#})
+                    .kind(FileKind::Normal)
+                    .write()
+            })?
+            .with_file("unknown_deps.toml", |fixture| {
+                fixture
+                    .data(indoc! {r#"
+                        [repos.vim]
+                        branch = "master"
+                        remote = "origin"
+
+                        [repos.vim.bootstrap]
+                        clone = "https://some/url"
+                        depends = ["shel-common"]
+
+                        [repos.shell-common]
+                        branch = "master"
+                        remote = "origin"
+
+                        [repos.shell-common.bootstrap]
+                        clone = "https://some/url"
+                        conflicts = ["bsh"]
+
+                        [repos.bash]
+                        branch = "master"
+                        remote = "origin"
+                    "#})
+                    .kind(FileKind::Normal)
+                    .write()
+            })?
+            .with_file("cycle.toml", |fixture| {
+                fixture
+                    .data(indoc! {r#"
+                        [repos.vim.bootstrap]
+                        clone = "https://some/url"
+                        depends = ["foo"]
+
+                        [repos.foo.bootstrap]
+                        clone = "https://some/url"
+                        depends = [
+                            "vim",
+                        ]
+                    "#})
+                    .kind(FileKind::Normal)
+                    .write()
+            })?
+            .with_file("desktop.toml", |fixture| {
+                fixture
+                    .data(indoc! {r#"
+                        [repos.i3.bootstrap]
+                        clone = "https://some/url"
+                        depends = ["polybar"]
+                        recommends = ["picom"]
+                        conflicts = ["sway"]
+
+                        [repos.polybar]
+                        branch = "master"
+                        remote = "origin"
+
+                        [repos.picom.bootstrap]
+                        clone = "https://some/url"
+                        hosts = ["lovelace"]
+
+                        [repos.sway]
+                        branch = "master"
+                        remote = "origin"
+                    "#})
+                    .kind(FileKind::Normal)
+                    .write()
+            })?
+            .with_file("pinned.toml", |fixture| {
+                fixture
+                    .data(indoc! {r#"
+                        [repos.vim.bootstrap]
+                        clone = "https://some/url"
+                        depends = [
+                            { repo = "shell-common", rev = ">=v2" },
+                        ]
+
+                        [repos.shell-common]
+                        branch = "master"
+                        remote = "origin"
                     "#})
                     .kind(FileKind::Normal)
                     .write()
@@ -211,10 +819,12 @@ fn repo_manager_manage_duplicate_settings_check_return_ok(
         let fixture = config_dir.get("repos.toml")?;
         let mut locator = MockLocator::new();
         locator.expect_repo_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
         let config = ConfigFile::load(RepoConfig, &locator)
             .with_whatever_context(|_| "Failed to load configuration file")?;
 
-        let result = RepoManager::manage(config, &locator);
+        let result = RepoManager::manage(config, &locator, FakeVcs::new());
         assert!(result.is_ok());
 
         Ok(())
@@ -229,10 +839,12 @@ fn repo_manager_manage_duplicate_settings_check_return_err(
         let fixture = config_dir.get("duplicates.toml")?;
         let mut locator = MockLocator::new();
         locator.expect_repo_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
         let config = ConfigFile::load(RepoConfig, &locator)
             .with_whatever_context(|_| "Failed to load configuration file")?;
 
-        let result = RepoManager::manage(config, &locator);
+        let result = RepoManager::manage(config, &locator, FakeVcs::new());
         assert!(matches!(
             result.unwrap_err().0,
             InnerRepoManagerError::DuplicateSettingValues { .. }
@@ -241,6 +853,408 @@ fn repo_manager_manage_duplicate_settings_check_return_err(
         Ok(())
     }
 
+    #[report]
+    #[rstest]
+    fn repo_manager_manage_unknown_dependency_return_err(
+        config_dir: Result<FixtureHarness, Whatever>,
+    ) -> Result<(), Whatever> {
+        let config_dir = config_dir?;
+        let fixture = config_dir.get("unknown_deps.toml")?;
+        let mut locator = MockLocator::new();
+        locator.expect_repo_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
+        let config = ConfigFile::load(RepoConfig, &locator)
+            .with_whatever_context(|_| "Failed to load configuration file")?;
+
+        let result = RepoManager::manage(config, &locator, FakeVcs::new());
+        let InnerRepoManagerError::Dependency { source } = result.unwrap_err().0 else {
+            panic!("Expected dependency error");
+        };
+        let path = fixture.as_path().display();
+        assert_eq!(
+            source.to_string(),
+            format!(
+                "Found dependencies on unknown repositories:\n  \
+                 'vim' depends on 'shel-common' at '{path}:7', did you mean 'shell-common'?\n  \
+                 'shell-common' conflicts with 'bsh' at '{path}:15', did you mean 'bash'?"
+            )
+        );
+
+        Ok(())
+    }
+
+    #[report]
+    #[rstest]
+    fn repo_manager_manage_cycle_return_err(
+        config_dir: Result<FixtureHarness, Whatever>,
+    ) -> Result<(), Whatever> {
+        let config_dir = config_dir?;
+        let fixture = config_dir.get("cycle.toml")?;
+        let mut locator = MockLocator::new();
+        locator.expect_repo_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
+        let config = ConfigFile::load(RepoConfig, &locator)
+            .with_whatever_context(|_| "Failed to load configuration file")?;
+
+        let result = RepoManager::manage(config, &locator, FakeVcs::new());
+        let InnerRepoManagerError::Dependency { source } = result.unwrap_err().0 else {
+            panic!("Expected dependency error");
+        };
+        let path = fixture.as_path().display();
+        assert_eq!(
+            source.to_string(),
+            format!(
+                "Found circular dependencies:\n  foo -> vim -> foo\n    \
+                 'foo' depends on 'vim' at '{path}:8'\n    \
+                 'vim' depends on 'foo' at '{path}:3'"
+            )
+        );
+
+        Ok(())
+    }
+
+    #[report]
+    #[rstest]
+    fn repo_manager_graph_annotate_repos(
+        config_dir: Result<FixtureHarness, Whatever>,
+    ) -> Result<(), Whatever> {
+        let config_dir = config_dir?;
+        let data_dir = FixtureHarness::open()?
+            .with_file("vim/index", |fixture| fixture.kind(FileKind::Normal).write())?
+            .with_file("foo/HEAD", |fixture| fixture.kind(FileKind::Normal).write())?;
+        let fixture = config_dir.get("repos.toml")?;
+        let mut locator = MockLocator::new();
+        locator.expect_repo_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
+        locator.expect_repos_dir().return_const(data_dir.as_path().into());
+        let config = ConfigFile::load(RepoConfig, &locator)
+            .with_whatever_context(|_| "Failed to load configuration file")?;
+        let repo_mgr = RepoManager::manage(config, &locator, FakeVcs::new())
+            .with_whatever_context(|_| "Failed to manage repositories")?;
+
+        let os = OsInfo::new([OsKind::Any, OsKind::Unix]);
+        let host = HostInfo::new(os, "turing", "awkless");
+        let graph = repo_mgr
+            .graph(None, &host)
+            .with_whatever_context(|_| "Failed to render dependency graph")?;
+        let expect = indoc! {"
+            vim [deployed]
+            ├── foo [deployed]
+            └── baz [undeployed]
+        "};
+        assert_eq!(graph.to_tree(), expect);
+
+        let host = HostInfo::new(OsInfo::new([OsKind::Any, OsKind::Windows]), "turing", "awkless");
+        let graph = repo_mgr
+            .graph(Some("vim"), &host)
+            .with_whatever_context(|_| "Failed to render dependency graph")?;
+        let expect = indoc! {"
+            vim [deployed, host mismatch]
+            ├── foo [deployed]
+            └── baz [undeployed]
+        "};
+        assert_eq!(graph.to_tree(), expect);
+
+        let result = repo_mgr.graph(Some("vin"), &host);
+        assert!(matches!(result.unwrap_err().0, InnerRepoManagerError::UnknownRepo { .. }));
+
+        Ok(())
+    }
+
+    #[report]
+    #[rstest]
+    #[case::skip_recommends(vec!["i3"], "turing", vec!["polybar", "i3"])]
+    #[case::deploy_recommends(vec!["i3"], "lovelace", vec!["polybar", "picom", "i3"])]
+    #[case::explicit_target(vec!["picom"], "turing", vec!["picom"])]
+    fn repo_manager_deploy_plan_return_order(
+        config_dir: Result<FixtureHarness, Whatever>,
+        #[case] repos: Vec<&str>,
+        #[case] hostname: &str,
+        #[case] expect: Vec<&str>,
+    ) -> Result<(), Whatever> {
+        let config_dir = config_dir?;
+        let data_dir = FixtureHarness::open()?;
+        let fixture = config_dir.get("desktop.toml")?;
+        let mut locator = MockLocator::new();
+        locator.expect_repo_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
+        locator.expect_repos_dir().return_const(data_dir.as_path().into());
+        let config = ConfigFile::load(RepoConfig, &locator)
+            .with_whatever_context(|_| "Failed to load configuration file")?;
+        let repo_mgr = RepoManager::manage(config, &locator, FakeVcs::new())
+            .with_whatever_context(|_| "Failed to manage repositories")?;
+
+        let host = HostInfo::new(OsInfo::new([OsKind::Any]), hostname, "awkless");
+        let repos = repos.into_iter().map(String::from).collect::<Vec<_>>();
+        let result = repo_mgr
+            .deploy_plan(&repos, &host)
+            .with_whatever_context(|_| "Failed to plan deployment")?;
+        assert_eq!(result, expect);
+
+        Ok(())
+    }
+
+    #[report]
+    #[rstest]
+    #[case::both_targeted(vec!["i3", "sway"], false, "which would be deployed along with it")]
+    #[case::already_deployed(vec!["i3"], true, "which is already deployed")]
+    fn repo_manager_deploy_plan_conflict_return_err(
+        config_dir: Result<FixtureHarness, Whatever>,
+        #[case] repos: Vec<&str>,
+        #[case] sway_deployed: bool,
+        #[case] reason: &str,
+    ) -> Result<(), Whatever> {
+        let config_dir = config_dir?;
+        let mut data_dir = FixtureHarness::open()?;
+        if sway_deployed {
+            data_dir = data_dir
+                .with_file("sway/HEAD", |fixture| fixture.kind(FileKind::Normal).write())?;
+        }
+        let fixture = config_dir.get("desktop.toml")?;
+        let mut locator = MockLocator::new();
+        locator.expect_repo_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
+        locator.expect_repos_dir().return_const(data_dir.as_path().into());
+        let config = ConfigFile::load(RepoConfig, &locator)
+            .with_whatever_context(|_| "Failed to load configuration file")?;
+        let repo_mgr = RepoManager::manage(config, &locator, FakeVcs::new())
+            .with_whatever_context(|_| "Failed to manage repositories")?;
+
+        let host = HostInfo::new(OsInfo::new([OsKind::Any]), "turing", "awkless");
+        let repos = repos.into_iter().map(String::from).collect::<Vec<_>>();
+        let result = repo_mgr.deploy_plan(&repos, &host);
+        let InnerRepoManagerError::Dependency { source } = result.unwrap_err().0 else {
+            panic!("Expected dependency error");
+        };
+        let path = fixture.as_path().display();
+        assert_eq!(
+            source.to_string(),
+            format!("Repository 'i3' conflicts with repository 'sway' at '{path}:5', {reason}")
+        );
+
+        Ok(())
+    }
+
+    #[report]
+    #[rstest]
+    fn repo_manager_deploy_locked_not_recorded_return_err(
+        config_dir: Result<FixtureHarness, Whatever>,
+    ) -> Result<(), Whatever> {
+        let config_dir = config_dir?.with_file(LOCK_FILE_NAME, |fixture| {
+            fixture
+                .data(indoc! {r#"
+                    version = 1
+
+                    [repos.polybar]
+                    url = "https://some/url"
+                    branch = "master"
+                    commit = "1a2b3c4d"
+                "#})
+                .kind(FileKind::Normal)
+                .write()
+        })?;
+        let data_dir = FixtureHarness::open()?;
+        let fixture = config_dir.get("desktop.toml")?;
+        let mut locator = MockLocator::new();
+        locator.expect_repo_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
+        locator.expect_repos_dir().return_const(data_dir.as_path().into());
+        let config = ConfigFile::load(RepoConfig, &locator)
+            .with_whatever_context(|_| "Failed to load configuration file")?;
+        let mut repo_mgr = RepoManager::manage(config, &locator, FakeVcs::new())
+            .with_whatever_context(|_| "Failed to manage repositories")?;
+
+        let host = HostInfo::new(OsInfo::new([OsKind::Any]), "turing", "awkless");
+        let result = repo_mgr.deploy(vec!["sway".into()], &host, true);
+        assert!(matches!(result.unwrap_err().0, InnerRepoManagerError::NotLocked { .. }));
+
+        Ok(())
+    }
+
+    #[report]
+    #[rstest]
+    #[case::present("1a2b3c4d", None, None)]
+    #[case::not_fetched(
+        "9c0d1e2f",
+        None,
+        Some("Locked commit '9c0d1e2f' of repository 'foo' is not present, fetch it first")
+    )]
+    #[case::modified(
+        "1a2b3c4d",
+        Some("vimrc"),
+        Some(
+            "Repository 'foo' has uncommitted changes that checkout would overwrite, commit or \
+             stash them first"
+        )
+    )]
+    fn repo_manager_deploy_locked_check_out_recorded_commit(
+        config_dir: Result<FixtureHarness, Whatever>,
+        #[case] commit: &str,
+        #[case] modified: Option<&str>,
+        #[case] expect_err: Option<&str>,
+    ) -> Result<(), Whatever> {
+        let config_dir = config_dir?.with_file(LOCK_FILE_NAME, |fixture| {
+            fixture
+                .data(formatdoc! {r#"
+                    version = 1
+
+                    [repos.foo]
+                    url = "https://some/url"
+                    branch = "master"
+                    commit = "{commit}"
+                "#})
+                .kind(FileKind::Normal)
+                .write()
+        })?;
+        let data_dir = FixtureHarness::open()?;
+        let fixture = config_dir.get("repos.toml")?;
+        let mut locator = MockLocator::new();
+        locator.expect_repo_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
+        locator.expect_repos_dir().return_const(data_dir.as_path().into());
+        let config = ConfigFile::load(RepoConfig, &locator)
+            .with_whatever_context(|_| "Failed to load configuration file")?;
+
+        let foo = data_dir.as_path().join("foo");
+        std::fs::create_dir(&foo).with_whatever_context(|_| "Failed to create repository")?;
+        let mut repo = FakeRepo::new("master").with_commit("1a2b3c4d").with_commit("5e6f7a8b");
+        if let Some(path) = modified {
+            repo = repo.with_modified(path);
+        }
+        let vcs = FakeVcs::new().with_repo(&foo, repo);
+        let mut repo_mgr = RepoManager::manage(config, &locator, vcs)
+            .with_whatever_context(|_| "Failed to manage repositories")?;
+
+        let host = HostInfo::new(OsInfo::new([OsKind::Any]), "turing", "awkless");
+        let result = repo_mgr.deploy(vec!["foo".into()], &host, true);
+        let head = repo_mgr.vcs.repo(&foo).and_then(|repo| repo.head);
+        let checkout = format!("checkout {} {commit}", foo.display());
+        match expect_err {
+            None => {
+                result.with_whatever_context(|_| "Failed to deploy locked repository")?;
+                assert_eq!(head.as_deref(), Some(commit));
+                assert!(repo_mgr.vcs.calls().contains(&checkout));
+            }
+            Some(msg) => {
+                assert_eq!(result.unwrap_err().to_string(), msg);
+                assert_eq!(head.as_deref(), Some("5e6f7a8b"));
+                assert!(!repo_mgr.vcs.calls().contains(&checkout));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[report]
+    #[rstest]
+    fn repo_manager_deploy_plan_unknown_revision_return_err(
+        config_dir: Result<FixtureHarness, Whatever>,
+    ) -> Result<(), Whatever> {
+        let config_dir = config_dir?;
+        let data_dir = FixtureHarness::open()?;
+        let fixture = config_dir.get("pinned.toml")?;
+        let mut locator = MockLocator::new();
+        locator.expect_repo_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
+        locator.expect_repos_dir().return_const(data_dir.as_path().into());
+        let config = ConfigFile::load(RepoConfig, &locator)
+            .with_whatever_context(|_| "Failed to load configuration file")?;
+        let repo_mgr = RepoManager::manage(config, &locator, FakeVcs::new())
+            .with_whatever_context(|_| "Failed to manage repositories")?;
+
+        let host = HostInfo::new(OsInfo::new([OsKind::Any]), "turing", "awkless");
+        let result = repo_mgr.deploy_plan(&["vim".into()], &host);
+        let InnerRepoManagerError::Dependency { source } = result.unwrap_err().0 else {
+            panic!("Expected dependency error");
+        };
+        let path = fixture.as_path().display();
+        assert_eq!(
+            source.to_string(),
+            format!(
+                "Repository 'vim' requires revision '>=v2' of 'shell-common' at '{path}:4', \
+                 but its revision could not be determined"
+            )
+        );
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::no_patterns(vec!["turing", "lovelace"], vec![])]
+    #[case::glob(vec!["build-*", "build-1", "turing"], vec![("build-*", "build-1")])]
+    #[case::regex(
+        vec!["ci-1", "/^ci-\\d+$/", "ci-2"],
+        vec![("/^ci-\\d+$/", "ci-1"), ("/^ci-\\d+$/", "ci-2")],
+    )]
+    fn find_redundant_return_pairs(#[case] entries: Vec<&str>, #[case] expect: Vec<(&str, &str)>) {
+        let entries = Some(entries.into_iter().map(|entry| entry.parse().unwrap()).collect());
+        let result = find_redundant(&entries)
+            .into_iter()
+            .map(|(pattern, redundant)| (pattern.to_string(), redundant.to_string()))
+            .collect::<Vec<_>>();
+        let expect = expect
+            .into_iter()
+            .map(|(pattern, redundant)| (pattern.to_string(), redundant.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(result, expect);
+    }
+
+    #[rstest]
+    #[case::known_distros(
+        BootstrapSettings::new("https://some/url")
+            .with_os(OsFilter::new([OsKind::Linux, OsKind::Distro("arch".into())]))
+            .with_when("os(ubuntu) || os(windows)".parse().unwrap()),
+        vec![],
+    )]
+    #[case::host_distro(
+        BootstrapSettings::new("https://some/url")
+            .with_os(OsFilter::new([OsKind::Distro("myos".into())])),
+        vec![],
+    )]
+    #[case::typos(
+        BootstrapSettings::new("https://some/url")
+            .with_os(OsFilter::new([OsKind::Distro("ubunut".into()), OsKind::Linux]))
+            .with_when("!os(wsl) && os(archh)".parse().unwrap()),
+        vec![("foo.bootstrap.os", "ubunut"), ("foo.bootstrap.when", "archh")],
+    )]
+    #[report]
+    fn find_unknown_os_return_typos(
+        #[case] bootstrap: BootstrapSettings,
+        #[case] expect: Vec<(&str, &str)>,
+    ) -> Result<(), Whatever> {
+        let harness = FixtureHarness::open()?
+            .with_file("os-release", |fixture| {
+                fixture.data("ID=myos\n").kind(FileKind::Normal).write()
+            })?
+            .with_file("osrelease", |fixture| {
+                fixture.data("6.11.5-arch1-1").kind(FileKind::Normal).write()
+            })?;
+        let os_release = harness.get("os-release")?;
+        let kernel_release = harness.get("osrelease")?;
+        let host = OsInfo::detect_with(os_release.as_path(), kernel_release.as_path());
+
+        let repo = RepoSettings::new("foo", "main", "origin").with_bootstrap(bootstrap);
+        let result = find_unknown_os(&repo, &host)
+            .into_iter()
+            .map(|(setting_name, kind)| (setting_name, kind.to_string()))
+            .collect::<Vec<_>>();
+        let expect = expect
+            .into_iter()
+            .map(|(setting_name, kind)| (setting_name.to_string(), kind.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(result, expect);
+
+        Ok(())
+    }
+
     #[report]
     #[rstest]
     #[case::use_defaults("foo".to_string(), None, None)]
@@ -258,18 +1272,214 @@ fn repo_manager_init_add_repo_to_config_and_repo_dir(
         let mut locator = MockLocator::new();
         locator.expect_repo_config_file().return_const(fixture.as_path().into());
         locator.expect_repos_dir().return_const(repos_dir.clone());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
         let config = ConfigFile::load(RepoConfig, &locator)
             .with_whatever_context(|_| "Failed to load configuration file")?;
 
-        let mut repo_mgr = RepoManager::manage(config, &locator)
+        let mut repo_mgr = RepoManager::manage(config, &locator, FakeVcs::new())
             .with_whatever_context(|_| "Failed to construct repository manager")?;
+        let bare = bare_alias.is_some();
+        let expect_branch = branch.clone().unwrap_or("master".into());
         repo_mgr
             .init(repo_name.clone(), branch, bare_alias)
             .with_whatever_context(|_| "Failed to initialize new repository")?;
         fixture.sync()?;
-        assert!(repos_dir.join(repo_name).exists());
+        let repo = repo_mgr.vcs.repo(&repos_dir.join(repo_name)).unwrap();
+        let config = match bare {
+            true => vec![("status.showUntrackedFiles".into(), "no".into())],
+            false => Vec::new(),
+        };
+        assert_eq!(repo, FakeRepo { bare, config, ..FakeRepo::new(expect_branch) });
         assert_eq!(repo_mgr.config.to_string(), fixture.as_str());
 
         Ok(())
     }
+
+    #[report]
+    #[rstest]
+    fn repo_manager_lock_record_checked_out_commits(
+        config_dir: Result<FixtureHarness, Whatever>,
+    ) -> Result<(), Whatever> {
+        let config_dir = config_dir?;
+        let data_dir = FixtureHarness::open()?;
+        let fixture = config_dir.get("repos.toml")?;
+        let mut locator = MockLocator::new();
+        locator.expect_repo_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
+        locator.expect_repos_dir().return_const(data_dir.as_path().into());
+        let config = ConfigFile::load(RepoConfig, &locator)
+            .with_whatever_context(|_| "Failed to load configuration file")?;
+
+        let vim = data_dir.as_path().join("vim");
+        let foo = data_dir.as_path().join("foo");
+        for path in [&vim, &foo] {
+            std::fs::create_dir(path).with_whatever_context(|_| "Failed to create repository")?;
+        }
+        let vcs = FakeVcs::new()
+            .with_repo(
+                &vim,
+                FakeRepo::new("master")
+                    .with_commit("1a2b3c4d")
+                    .with_remote("origin", "https://some/vim.git"),
+            )
+            .with_repo(&foo, FakeRepo::new("master").with_commit("5e6f7a8b"));
+        let repo_mgr = RepoManager::manage(config, &locator, vcs)
+            .with_whatever_context(|_| "Failed to manage repositories")?;
+        let lockfile = repo_mgr.lock().with_whatever_context(|_| "Failed to lock repositories")?;
+        let expect = Lockfile::new().with_repo(LockedRepo {
+            name: "vim".into(),
+            url: "https://some/vim.git".into(),
+            branch: "master".into(),
+            commit: "1a2b3c4d".into(),
+        });
+        assert_eq!(lockfile, expect);
+
+        Ok(())
+    }
+
+    #[report]
+    #[rstest]
+    fn repo_manager_list_summarize_repos(
+        config_dir: Result<FixtureHarness, Whatever>,
+    ) -> Result<(), Whatever> {
+        let config_dir = config_dir?;
+        let data_dir = FixtureHarness::open()?
+            .with_file("vim/index", |fixture| fixture.kind(FileKind::Normal).write())?;
+        let fixture = config_dir.get("repos.toml")?;
+        let mut locator = MockLocator::new();
+        locator.expect_repo_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
+        locator.expect_repos_dir().return_const(data_dir.as_path().into());
+        let config = ConfigFile::load(RepoConfig, &locator)
+            .with_whatever_context(|_| "Failed to load configuration file")?;
+
+        let vim = data_dir.as_path().join("vim");
+        let foo = data_dir.as_path().join("foo");
+        std::fs::create_dir(&foo).with_whatever_context(|_| "Failed to create repository")?;
+        let vcs = FakeVcs::new()
+            .with_repo(&vim, FakeRepo::new("master").with_commit("1a2b3c4d"))
+            .with_repo(&foo, FakeRepo::new("main").with_commit("5e6f7a8b").with_modified("notes"));
+        let repo_mgr = RepoManager::manage(config, &locator, vcs)
+            .with_whatever_context(|_| "Failed to manage repositories")?;
+
+        let result = repo_mgr
+            .list()
+            .with_whatever_context(|_| "Failed to list repositories")?
+            .into_iter()
+            .map(|summary| summary.to_string())
+            .collect::<Vec<_>>();
+        let expect =
+            vec!["vim deployed master", "foo deployed main modified", "baz undeployed not cloned"];
+        assert_eq!(result, expect);
+
+        Ok(())
+    }
+
+    #[report]
+    #[rstest]
+    #[case::derive_name("https://some/url/dots.git", None, "dots")]
+    #[case::derive_name_scp("git@some.host:user/dots", None, "dots")]
+    #[case::explicit_name("https://some/url/dots.git", Some("st".into()), "st")]
+    fn repo_manager_clone_add_repo_to_config_and_repo_dir(
+        config_dir: Result<FixtureHarness, Whatever>,
+        #[case] url: &str,
+        #[case] name: Option<String>,
+        #[case] expect: &str,
+    ) -> Result<(), Whatever> {
+        let mut config_dir = config_dir?;
+        let repos_dir = config_dir.as_path().join("repos");
+        let fixture = config_dir.get_mut("repos.toml")?;
+        let mut locator = MockLocator::new();
+        locator.expect_repo_config_file().return_const(fixture.as_path().into());
+        locator.expect_repos_dir().return_const(repos_dir.clone());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
+        let config = ConfigFile::load(RepoConfig, &locator)
+            .with_whatever_context(|_| "Failed to load configuration file")?;
+
+        let mut repo_mgr = RepoManager::manage(config, &locator, FakeVcs::new())
+            .with_whatever_context(|_| "Failed to construct repository manager")?;
+        repo_mgr.clone(url.into(), name).with_whatever_context(|_| "Failed to clone")?;
+        fixture.sync()?;
+        let path = repos_dir.join(expect);
+        let clone = format!("clone {} {url} master", path.display());
+        assert_eq!(repo_mgr.vcs.calls(), vec![clone]);
+        let repo = repo_mgr.config.get(expect).with_whatever_context(|_| "Repository not added")?;
+        assert_eq!(repo, RepoSettings::new(expect, "master", "origin"));
+        assert_eq!(repo_mgr.config.to_string(), fixture.as_str());
+
+        Ok(())
+    }
+
+    #[report]
+    #[rstest]
+    fn repo_manager_clone_return_err_unnamed_remote(
+        config_dir: Result<FixtureHarness, Whatever>,
+    ) -> Result<(), Whatever> {
+        let config_dir = config_dir?;
+        let fixture = config_dir.get("repos.toml")?;
+        let mut locator = MockLocator::new();
+        locator.expect_repo_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
+        let config = ConfigFile::load(RepoConfig, &locator)
+            .with_whatever_context(|_| "Failed to load configuration file")?;
+
+        let mut repo_mgr = RepoManager::manage(config, &locator, FakeVcs::new())
+            .with_whatever_context(|_| "Failed to construct repository manager")?;
+        let result = repo_mgr.clone("https://some/url/.git".into(), None);
+        assert!(matches!(
+            result.map_err(|err| err.0),
+            Err(InnerRepoManagerError::UnnamedRemote { .. })
+        ));
+        assert!(repo_mgr.vcs.calls().is_empty());
+
+        Ok(())
+    }
+
+    #[report]
+    #[rstest]
+    fn repo_manager_init_and_clone_hide_untracked_of_bare_alias(
+        config_dir: Result<FixtureHarness, Whatever>,
+    ) -> Result<(), Whatever> {
+        let config_dir = config_dir?;
+        let data_dir = FixtureHarness::open()?;
+        let fixture = config_dir.get("repos.toml")?;
+        let mut locator = MockLocator::new();
+        locator.expect_repo_config_file().return_const(fixture.as_path().into());
+        locator.expect_system_repo_config_file().return_const(None);
+        locator.expect_host_repo_config_file().return_const(None);
+        locator.expect_repos_dir().return_const(data_dir.as_path().into());
+        let config = ConfigFile::load(RepoConfig, &locator)
+            .with_whatever_context(|_| "Failed to load configuration file")?;
+
+        let mut repo_mgr = RepoManager::manage(config, &locator, FakeVcs::new())
+            .with_whatever_context(|_| "Failed to manage repositories")?;
+        repo_mgr
+            .clone("https://some/url/vim.git".into(), None)
+            .with_whatever_context(|_| "Failed to clone bare-alias repository")?;
+        repo_mgr
+            .clone("https://some/url/foo.git".into(), None)
+            .with_whatever_context(|_| "Failed to clone normal repository")?;
+        repo_mgr
+            .init("dots".into(), None, Some("/some/path".into()))
+            .with_whatever_context(|_| "Failed to initialize bare-alias repository")?;
+
+        let hide = |name: &str| {
+            let path = data_dir.as_path().join(name);
+            format!("config {} status.showUntrackedFiles no", path.display())
+        };
+        let calls = repo_mgr.vcs.calls();
+        assert!(calls.contains(&hide("vim")));
+        assert!(calls.contains(&hide("dots")));
+        assert!(!calls.contains(&hide("foo")));
+        let vim = repo_mgr.vcs.repo(&data_dir.as_path().join("vim")).unwrap_or_default();
+        assert!(vim.bare);
+        assert_eq!(vim.config, vec![("status.showUntrackedFiles".into(), "no".into())]);
+
+        Ok(())
+    }
 }
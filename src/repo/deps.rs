@@ -1,10 +1,13 @@
 // SPDX-FileCopyrightText: 2024 Jason Pena <jasonpena@awkless.com>
 // SPDX-License-Identifier: MIT
 
-use crate::config::{ConfigFile, Locator, RepoConfig};
+use crate::config::{
+    ConfigError, ConfigFile, Location, Locator, RepoConfig, RevInfo, RevReq, Sources,
+};
 
+use log::debug;
 use snafu::prelude::*;
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{hash_map::Entry, HashMap, HashSet, VecDeque};
 
 /// Handle repository dependencies.
 ///
@@ -16,61 +19,430 @@
 /// This type handles dependency information as a DAG (Directed Acyclic Graph).
 /// This type also can handle the edge case where a repository does not have
 /// a `depends` field.
+///
+/// Edges are typed by [`DependencyKind`]. Hard and recommended dependencies
+/// both order deployment, and are subject to cycle checks. Conflicts only
+/// restrict which repositories can be deployed together.
+///
+/// Vertices are always visited in a stable order: declared repositories in
+/// order of declaration, followed by any other vertex by name. Edges are
+/// visited in order of declaration.
 #[derive(Debug)]
 pub struct Dependencies {
     adj_list: HashMap<String, Vec<String>>,
+    kinds: HashMap<(String, String), DependencyKind>,
+    revs: HashMap<(String, String), RevReq>,
+    conflicts: HashMap<String, Vec<String>>,
+    declared: Vec<String>,
+    sources: Sources,
 }
 
 impl Dependencies {
     /// Construct new dependency handler.
     pub fn new() -> Self {
-        Self { adj_list: HashMap::new() }
+        Self {
+            adj_list: HashMap::new(),
+            kinds: HashMap::new(),
+            revs: HashMap::new(),
+            conflicts: HashMap::new(),
+            declared: Vec::new(),
+            sources: Sources::default(),
+        }
     }
 
     /// Load configuration file dependencies.
-    pub fn with_config_file(&mut self, config: &ConfigFile<'_, RepoConfig, impl Locator>) {
+    ///
+    /// # Errors
+    ///
+    /// Will fail if repository settings are malformed.
+    pub fn with_config_file(
+        &mut self,
+        config: &ConfigFile<'_, RepoConfig, impl Locator>,
+    ) -> Result<(), ConfigError> {
+        self.sources = config.sources();
         for repo in config.iter() {
+            let repo = repo?;
             self.add_vertex(repo.name.clone());
-            let deps = repo.bootstrap.as_ref().and_then(|b| b.depends.clone()).unwrap_or_default();
-            for dep in deps {
-                self.add_edge(repo.name.clone(), dep.clone());
+            let Some(bootstrap) = repo.bootstrap else {
+                continue;
+            };
+
+            let depends = bootstrap.depends.unwrap_or_default();
+            let depends = depends.into_iter().map(|dep| (dep.repo, dep.rev));
+            let plain = |deps: Option<Vec<String>>| {
+                deps.unwrap_or_default().into_iter().map(|dep| (dep, None)).collect::<Vec<_>>()
+            };
+            for (kind, deps) in [
+                (DependencyKind::Depends, depends.collect()),
+                (DependencyKind::Recommends, plain(bootstrap.recommends)),
+                (DependencyKind::Conflicts, plain(bootstrap.conflicts)),
+            ] {
+                for (dep, rev) in deps {
+                    if let Some(rev) = rev {
+                        self.pin(repo.name.clone(), dep.clone(), rev);
+                    }
+                    self.add_typed_edge(repo.name.clone(), dep, kind);
+                }
             }
         }
+
+        Ok(())
     }
 
     /// Add new vertex.
+    ///
+    /// Vertex is treated as a declared repository.
     pub fn add_vertex(&mut self, vertex: impl Into<String>) {
-        self.adj_list.entry(vertex.into()).or_default();
+        let vertex = vertex.into();
+        self.adj_list.entry(vertex.clone()).or_default();
+        if !self.declared.contains(&vertex) {
+            self.declared.push(vertex);
+        }
+    }
+
+    /// List all vertices in stable order.
+    pub fn vertices(&self) -> Vec<&String> {
+        let mut undeclared = self
+            .adj_list
+            .keys()
+            .filter(|vertex| !self.declared.contains(vertex))
+            .collect::<Vec<_>>();
+        undeclared.sort();
+
+        let mut vertices = self.declared.iter().collect::<Vec<_>>();
+        vertices.extend(undeclared);
+        vertices
     }
 
-    /// Add new edge to given vertex.
+    /// Add new hard dependency edge to given vertex.
+    ///
+    /// Vertices are created for both ends of the edge if needed, but are not
+    /// treated as declared repositories.
     pub fn add_edge(&mut self, vertex: impl Into<String>, edge: impl Into<String>) {
-        let edge = edge.into();
-        self.adj_list.entry(vertex.into()).or_default().push(edge.clone());
-        self.adj_list.entry(edge).or_default();
+        self.add_typed_edge(vertex, edge, DependencyKind::Depends);
+    }
+
+    /// Add new edge of given kind to given vertex.
+    ///
+    /// Vertices are created for both ends of the edge if needed, but are not
+    /// treated as declared repositories. An edge that orders deployment is
+    /// only added once, keeping the kind it was first added with.
+    pub fn add_typed_edge(
+        &mut self,
+        vertex: impl Into<String>,
+        edge: impl Into<String>,
+        kind: DependencyKind,
+    ) {
+        let (vertex, edge) = (vertex.into(), edge.into());
+        self.adj_list.entry(edge.clone()).or_default();
+        if kind == DependencyKind::Conflicts {
+            self.adj_list.entry(vertex.clone()).or_default();
+            self.conflicts.entry(vertex).or_default().push(edge);
+            return;
+        }
+
+        if let Entry::Vacant(entry) = self.kinds.entry((vertex.clone(), edge.clone())) {
+            entry.insert(kind);
+            self.adj_list.entry(vertex).or_default().push(edge);
+        }
+    }
+
+    /// List direct hard and recommended dependencies of given vertex in order
+    /// of declaration.
+    pub fn edges(&self, vertex: &str) -> &[String] {
+        self.adj_list.get(vertex).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Pin dependency of vertex to revisions that satisfy given constraint.
+    pub fn pin(&mut self, vertex: impl Into<String>, edge: impl Into<String>, rev: RevReq) {
+        self.revs.insert((vertex.into(), edge.into()), rev);
+    }
+
+    /// Kind of dependency between vertex and one of its edges.
+    pub fn kind(&self, vertex: &str, edge: &str) -> Option<DependencyKind> {
+        self.kinds.get(&(vertex.to_string(), edge.to_string())).copied()
+    }
+
+    /// List repositories that given vertex declares conflicts with in order of
+    /// declaration.
+    pub fn conflicts(&self, vertex: &str) -> &[String] {
+        self.conflicts.get(vertex).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Resolve repositories to deploy for given targets.
+    ///
+    /// Repositories are ordered such that dependencies come before the
+    /// repositories that need them. Hard dependencies are always pulled in,
+    /// while recommended dependencies are only pulled in if `accept` allows
+    /// them.
+    pub fn resolve(
+        &self,
+        targets: impl IntoIterator<Item = impl AsRef<str>>,
+        accept: impl Fn(&str) -> bool,
+    ) -> Vec<String> {
+        let mut visited = HashSet::new();
+        let mut resolved = Vec::new();
+        for target in targets {
+            self.resolve_vertex(target.as_ref(), &accept, &mut visited, &mut resolved);
+        }
+
+        resolved
+    }
+
+    fn resolve_vertex(
+        &self,
+        vertex: &str,
+        accept: &impl Fn(&str) -> bool,
+        visited: &mut HashSet<String>,
+        resolved: &mut Vec<String>,
+    ) {
+        if !visited.insert(vertex.to_string()) {
+            return;
+        }
+
+        for edge in self.edges(vertex) {
+            if self.kind(vertex, edge) == Some(DependencyKind::Recommends) && !accept(edge) {
+                debug!("Skip '{edge}' recommended by '{vertex}'");
+                continue;
+            }
+            self.resolve_vertex(edge, accept, visited, resolved);
+        }
+        resolved.push(vertex.to_string());
+    }
+
+    /// Check that dependencies of repositories to deploy have revisions that
+    /// satisfy their pins.
+    ///
+    /// Revision of each pinned dependency is given by `rev_of`, which returns
+    /// [`None`] if revision cannot be determined.
+    ///
+    /// # Errors
+    ///
+    /// Will fail if a pinned dependency of a repository of `plan` does not
+    /// satisfy its pin, or its revision cannot be determined.
+    pub fn rev_check(
+        &self,
+        plan: &[String],
+        mut rev_of: impl FnMut(&str) -> Option<RevInfo>,
+    ) -> Result<(), DependencyError> {
+        for vertex in plan {
+            for edge in self.edges(vertex) {
+                let Some(rev) = self.revs.get(&(vertex.clone(), edge.clone())) else {
+                    continue;
+                };
+
+                let found = match rev_of(edge) {
+                    Some(info) if rev.matches(&info) => continue,
+                    Some(info) => format!("'{info}' is checked out"),
+                    None => "its revision could not be determined".into(),
+                };
+                let location = self
+                    .locate(vertex, edge, DependencyKind::Depends)
+                    .map(|location| format!(" at '{location}'"))
+                    .unwrap_or_default();
+                return Err(DependencyError(InnerDependencyError::UnsatisfiedRevision {
+                    repo: vertex.clone(),
+                    dep: edge.clone(),
+                    rev: rev.to_string(),
+                    details: format!("{location}, but {found}"),
+                }));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that repositories to deploy do not conflict.
+    ///
+    /// # Errors
+    ///
+    /// Will fail if two repositories of `plan` conflict with each other, or a
+    /// repository of `plan` conflicts with an already deployed repository.
+    pub fn conflict_check(
+        &self,
+        plan: &[String],
+        is_deployed: impl Fn(&str) -> bool,
+    ) -> Result<(), DependencyError> {
+        for vertex in self.vertices() {
+            for other in self.conflicts(vertex) {
+                let reason = match (plan.contains(vertex), plan.contains(other)) {
+                    (true, true) => "which would be deployed along with it",
+                    (true, false) if is_deployed(other) => "which is already deployed",
+                    (false, true) if is_deployed(vertex) => "which is already deployed",
+                    _ => continue,
+                };
+
+                // Always name the repository being deployed first...
+                let (repo, other) =
+                    if plan.contains(vertex) { (vertex, other) } else { (other, vertex) };
+                let location = self
+                    .locate(vertex, other, DependencyKind::Conflicts)
+                    .or_else(|| self.locate(other, vertex, DependencyKind::Conflicts))
+                    .map(|location| format!(" at '{location}'"))
+                    .unwrap_or_default();
+                return Err(DependencyError(InnerDependencyError::Conflict {
+                    repo: repo.clone(),
+                    other: other.clone(),
+                    details: format!("{location}, {reason}"),
+                }));
+            }
+        }
+
+        Ok(())
     }
 
     /// Determine list of dependencies to iterate through using DFS.
     pub fn iter_dfs(&self, start: impl Into<String>) -> DependenciesDfsIterator<'_> {
-        DependenciesDfsIterator::new(&self.adj_list, start)
+        DependenciesDfsIterator::new(&self.adj_list, self.vertices(), start)
     }
 
-    /// Check that no dependencies are circular.
+    /// Check that all dependencies name declared repositories.
     ///
     /// # Errors
     ///
-    /// Will fail if cycle is found.
-    pub fn acyclic_check(&self) -> Result<(), DependencyError> {
-        let result = self.topological_sort();
-        if result.len() != self.adj_list.len() {
-            return Err(DependencyError(InnerDependencyError::FoundCycle {
-                deps: result.join(" "),
+    /// Will fail if any dependency names an unknown repository, listing every
+    /// such dependency along with the closest declared repository name if
+    /// there is one.
+    pub fn known_check(&self) -> Result<(), DependencyError> {
+        let mut unknown = Vec::new();
+        let mut details = String::new();
+        for vertex in self.vertices() {
+            let edges = self.adj_list[vertex].iter().chain(self.conflicts(vertex));
+            for edge in edges {
+                if self.declared.contains(edge) {
+                    continue;
+                }
+
+                let kind = self.kind(vertex, edge).unwrap_or(DependencyKind::Conflicts);
+                details.push_str(&format!("\n  '{vertex}' {} '{edge}'", kind.relation()));
+                if let Some(location) = self.locate(vertex, edge, kind) {
+                    details.push_str(&format!(" at '{location}'"));
+                }
+                if let Some(name) = self.suggest(edge) {
+                    details.push_str(&format!(", did you mean '{name}'?"));
+                }
+                unknown.push((vertex.clone(), edge.clone()));
+            }
+        }
+
+        if !unknown.is_empty() {
+            return Err(DependencyError(InnerDependencyError::UnknownDependency {
+                unknown,
+                details,
             }));
         }
 
         Ok(())
     }
 
+    /// Find declared repository name closest to given name by edit distance.
+    fn suggest(&self, name: &str) -> Option<&str> {
+        let max_distance = name.chars().count().max(3) / 3;
+        let mut candidates = self
+            .declared
+            .iter()
+            .map(|declared| (edit_distance(name, declared), declared.as_str()))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .collect::<Vec<_>>();
+        candidates.sort();
+        candidates.first().map(|(_, declared)| *declared)
+    }
+
+    /// Check that no dependencies are circular.
+    ///
+    /// # Errors
+    ///
+    /// Will fail if cycle is found, reporting every cycle along with the
+    /// location of each `depends` entry that forms it.
+    pub fn acyclic_check(&self) -> Result<(), DependencyError> {
+        // Kahn's algorithm sorts every vertex unless there is a cycle...
+        if self.topological_sort().len() == self.adj_list.len() {
+            return Ok(());
+        }
+
+        let cycles = self.find_cycles();
+        let details = cycles.iter().map(|cycle| self.describe_cycle(cycle)).collect();
+        Err(DependencyError(InnerDependencyError::FoundCycle { cycles, details }))
+    }
+
+    /// Find all distinct cycles.
+    ///
+    /// Each cycle starts and ends with the same vertex, and is rotated such
+    /// that it starts with its smallest vertex, e.g., `["bar", "foo", "bar"]`.
+    /// Every elementary cycle is reported, even if it shares vertices with
+    /// other cycles. Cycles are sorted.
+    ///
+    /// Uses Johnson's algorithm on each strongly connected component that can
+    /// contain a cycle, so time spent is linear in number of cycles found.
+    pub fn find_cycles(&self) -> Vec<Vec<String>> {
+        let mut cycles = Vec::new();
+        for mut component in self.strongly_connected_components() {
+            let cyclic = component.len() > 1 || self.edges(component[0]).contains(component[0]);
+            if !cyclic {
+                continue;
+            }
+
+            // Enumerate cycles rooted at their smallest vertex, such that each
+            // cycle is only found once. Vertices smaller than the root are
+            // left out of the search.
+            component.sort();
+            for (idx, root) in component.iter().enumerate() {
+                let mut search = CycleSearch {
+                    deps: self,
+                    root,
+                    allowed: component[idx..].iter().copied().collect(),
+                    path: Vec::new(),
+                    blocked: HashSet::new(),
+                    blocked_by: HashMap::new(),
+                    cycles: &mut cycles,
+                };
+                search.circuit(root);
+            }
+        }
+        cycles.sort();
+
+        cycles
+    }
+
+    /// Partition vertices into strongly connected components through Tarjan's
+    /// algorithm.
+    fn strongly_connected_components(&self) -> Vec<Vec<&String>> {
+        let mut tarjan = Tarjan {
+            deps: self,
+            index: HashMap::new(),
+            low_link: HashMap::new(),
+            stack: Vec::new(),
+            on_stack: HashSet::new(),
+            components: Vec::new(),
+        };
+        for vertex in self.vertices() {
+            if !tarjan.index.contains_key(vertex) {
+                tarjan.connect(vertex);
+            }
+        }
+
+        tarjan.components
+    }
+
+    /// Locate entry of `dep` in dependency setting of given kind of `repo`.
+    fn locate(&self, repo: &str, dep: &str, kind: DependencyKind) -> Option<Location> {
+        self.sources.locate(&[repo, "bootstrap", kind.setting()], Some(dep))
+    }
+
+    fn describe_cycle(&self, cycle: &[String]) -> String {
+        let mut details = format!("\n  {}", cycle.join(" -> "));
+        for pair in cycle.windows(2) {
+            let (repo, dep) = (&pair[0], &pair[1]);
+            let kind = self.kind(repo, dep).unwrap_or(DependencyKind::Depends);
+            details.push_str(&format!("\n    '{repo}' {} '{dep}'", kind.relation()));
+            if let Some(location) = self.locate(repo, dep, kind) {
+                details.push_str(&format!(" at '{location}'"));
+            }
+        }
+
+        details
+    }
+
     /// Produce topological sort of dependencies.
     pub fn topological_sort(&self) -> Vec<String> {
         // Use Kahn's algorithm for topological sorting...
@@ -86,7 +458,7 @@ pub fn topological_sort(&self) -> Vec<String> {
         }
 
         // Add vertex with in-degree 0 to queue...
-        for vertex in self.adj_list.keys() {
+        for vertex in self.vertices() {
             if !in_degree.contains_key(vertex) {
                 queue.push_back(vertex.clone());
             }
@@ -109,21 +481,162 @@ pub fn topological_sort(&self) -> Vec<String> {
     }
 }
 
+/// State of Tarjan's strongly connected components algorithm.
+struct Tarjan<'deps> {
+    deps: &'deps Dependencies,
+    index: HashMap<&'deps String, usize>,
+    low_link: HashMap<&'deps String, usize>,
+    stack: Vec<&'deps String>,
+    on_stack: HashSet<&'deps String>,
+    components: Vec<Vec<&'deps String>>,
+}
+
+impl<'deps> Tarjan<'deps> {
+    fn connect(&mut self, vertex: &'deps String) {
+        let index = self.index.len();
+        self.index.insert(vertex, index);
+        self.low_link.insert(vertex, index);
+        self.stack.push(vertex);
+        self.on_stack.insert(vertex);
+
+        for edge in self.deps.edges(vertex) {
+            if !self.index.contains_key(edge) {
+                self.connect(edge);
+                let low_link = self.low_link[vertex].min(self.low_link[edge]);
+                self.low_link.insert(vertex, low_link);
+            } else if self.on_stack.contains(edge) {
+                let low_link = self.low_link[vertex].min(self.index[edge]);
+                self.low_link.insert(vertex, low_link);
+            }
+        }
+
+        if self.low_link[vertex] == self.index[vertex] {
+            let mut component = Vec::new();
+            while let Some(member) = self.stack.pop() {
+                self.on_stack.remove(member);
+                component.push(member);
+                if member == vertex {
+                    break;
+                }
+            }
+            self.components.push(component);
+        }
+    }
+}
+
+/// State of Johnson's elementary circuit search from a single root.
+struct CycleSearch<'deps, 'out> {
+    deps: &'deps Dependencies,
+    root: &'deps String,
+    allowed: HashSet<&'deps String>,
+    path: Vec<&'deps String>,
+    blocked: HashSet<&'deps String>,
+    blocked_by: HashMap<&'deps String, HashSet<&'deps String>>,
+    cycles: &'out mut Vec<Vec<String>>,
+}
+
+impl<'deps> CycleSearch<'deps, '_> {
+    /// Search cycles through `vertex`, returning true if any was found.
+    fn circuit(&mut self, vertex: &'deps String) -> bool {
+        let mut found = false;
+        self.path.push(vertex);
+        self.blocked.insert(vertex);
+
+        let mut edges = self
+            .deps
+            .edges(vertex)
+            .iter()
+            .filter(|edge| self.allowed.contains(edge))
+            .collect::<Vec<_>>();
+        edges.sort();
+        for edge in &edges {
+            if *edge == self.root {
+                let mut cycle = self.path.iter().map(|entry| entry.to_string()).collect::<Vec<_>>();
+                cycle.push(self.root.to_string());
+                self.cycles.push(cycle);
+                found = true;
+            } else if !self.blocked.contains(edge) && self.circuit(edge) {
+                found = true;
+            }
+        }
+
+        // Vertices that cannot reach the root stay blocked until one of their
+        // edges gets unblocked.
+        if found {
+            self.unblock(vertex);
+        } else {
+            for edge in edges {
+                self.blocked_by.entry(edge).or_default().insert(vertex);
+            }
+        }
+        self.path.pop();
+
+        found
+    }
+
+    fn unblock(&mut self, vertex: &'deps String) {
+        self.blocked.remove(vertex);
+        for waiting in self.blocked_by.remove(vertex).unwrap_or_default() {
+            if self.blocked.contains(waiting) {
+                self.unblock(waiting);
+            }
+        }
+    }
+}
+
+/// Kind of dependency between repositories.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum DependencyKind {
+    /// Always deploy dependency along with repository.
+    Depends,
+
+    /// Deploy dependency along with repository if its bootstrap filters match
+    /// current host.
+    Recommends,
+
+    /// Never deploy dependency along with repository.
+    Conflicts,
+}
+
+impl DependencyKind {
+    /// Bootstrap setting that declares dependency kind.
+    pub fn setting(&self) -> &'static str {
+        match self {
+            DependencyKind::Depends => "depends",
+            DependencyKind::Recommends => "recommends",
+            DependencyKind::Conflicts => "conflicts",
+        }
+    }
+
+    fn relation(&self) -> &'static str {
+        match self {
+            DependencyKind::Depends => "depends on",
+            DependencyKind::Recommends => "recommends",
+            DependencyKind::Conflicts => "conflicts with",
+        }
+    }
+}
+
 pub struct DependenciesDfsIterator<'deps> {
     adj_list: &'deps HashMap<String, Vec<String>>,
+    vertices: Vec<&'deps String>,
     visited: HashSet<String>,
     stack: VecDeque<String>,
 }
 
 impl<'deps> DependenciesDfsIterator<'deps> {
-    fn new(adj_list: &'deps HashMap<String, Vec<String>>, start: impl Into<String>) -> Self {
+    fn new(
+        adj_list: &'deps HashMap<String, Vec<String>>,
+        vertices: Vec<&'deps String>,
+        start: impl Into<String>,
+    ) -> Self {
         let start = start.into();
         let mut stack: VecDeque<String> = VecDeque::new();
         stack.push_front(start.clone());
         let mut visited: HashSet<String> = HashSet::new();
         visited.insert(start);
 
-        Self { adj_list, visited, stack }
+        Self { adj_list, vertices, visited, stack }
     }
 }
 
@@ -132,17 +645,18 @@ impl Iterator for DependenciesDfsIterator<'_> {
 
     fn next(&mut self) -> Option<String> {
         if self.stack.is_empty() {
-            for vertex in self.adj_list.keys() {
-                if !self.visited.contains(vertex) {
-                    self.stack.push_front(vertex.clone());
-                    self.visited.insert(vertex.clone());
+            for vertex in &self.vertices {
+                if !self.visited.contains(*vertex) {
+                    self.stack.push_front((*vertex).clone());
+                    self.visited.insert((*vertex).clone());
                     break;
                 }
             }
         }
 
         if let Some(vertex) = self.stack.pop_front() {
-            for edge in &self.adj_list[&vertex] {
+            // Push in reverse, such that first declared edge is visited first...
+            for edge in self.adj_list[&vertex].iter().rev() {
                 if !self.visited.contains(edge) {
                     self.stack.push_front(edge.clone());
                     self.visited.insert(edge.clone());
@@ -155,6 +669,23 @@ fn next(&mut self) -> Option<String> {
     }
 }
 
+/// Levenshtein distance between two strings.
+fn edit_distance(lhs: &str, rhs: &str) -> usize {
+    let rhs = rhs.chars().collect::<Vec<_>>();
+    let mut prev = (0..=rhs.len()).collect::<Vec<_>>();
+    let mut curr = vec![0; rhs.len() + 1];
+    for (i, lc) in lhs.chars().enumerate() {
+        curr[0] = i + 1;
+        for (j, rc) in rhs.iter().enumerate() {
+            let cost = usize::from(lc != *rc);
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    prev[rhs.len()]
+}
+
 #[derive(Debug, Snafu)]
 pub struct DependencyError(InnerDependencyError);
 
@@ -162,8 +693,17 @@ fn next(&mut self) -> Option<String> {
 
 #[derive(Debug, Snafu)]
 enum InnerDependencyError {
-    #[snafu(display("Following repositories defined as circular dependencies: '{deps}'"))]
-    FoundCycle { deps: String },
+    #[snafu(display("Found circular dependencies:{details}"))]
+    FoundCycle { cycles: Vec<Vec<String>>, details: String },
+
+    #[snafu(display("Found dependencies on unknown repositories:{details}"))]
+    UnknownDependency { unknown: Vec<(String, String)>, details: String },
+
+    #[snafu(display("Repository '{repo}' requires revision '{rev}' of '{dep}'{details}"))]
+    UnsatisfiedRevision { repo: String, dep: String, rev: String, details: String },
+
+    #[snafu(display("Repository '{repo}' conflicts with repository '{other}'{details}"))]
+    Conflict { repo: String, other: String, details: String },
 }
 
 #[cfg(test)]
@@ -183,7 +723,98 @@ fn dependencies_acyclic_check_return_err() {
         deps.add_edge("bar", "foo");
         deps.add_edge("foo", "vim");
         let result = deps.acyclic_check();
-        assert!(matches!(result.unwrap_err().0, InnerDependencyError::FoundCycle { .. }));
+        let InnerDependencyError::FoundCycle { cycles, .. } = result.unwrap_err().0 else {
+            panic!("Expected cycle error");
+        };
+        assert_eq!(cycles, vec![vec!["bar", "foo", "vim", "bar"]]);
+    }
+
+    #[rstest]
+    #[case::self_loop(vec![("vim", "vim")], vec![vec!["vim", "vim"]])]
+    #[case::disjoint(
+        vec![("a", "b"), ("b", "a"), ("c", "d"), ("d", "e"), ("e", "c")],
+        vec![vec!["a", "b", "a"], vec!["c", "d", "e", "c"]],
+    )]
+    #[case::shared_vertex(
+        vec![("a", "b"), ("b", "a"), ("b", "c"), ("c", "b")],
+        vec![vec!["a", "b", "a"], vec!["b", "c", "b"]],
+    )]
+    #[case::diamond(
+        vec![("a", "b"), ("a", "c"), ("b", "d"), ("c", "d"), ("d", "a")],
+        vec![vec!["a", "b", "d", "a"], vec!["a", "c", "d", "a"]],
+    )]
+    #[case::no_cycle(vec![("a", "b"), ("b", "c"), ("a", "c")], vec![])]
+    fn dependencies_find_cycles_return_all_cycles(
+        #[case] edges: Vec<(&str, &str)>,
+        #[case] expect: Vec<Vec<&str>>,
+    ) {
+        let mut deps = Dependencies::new();
+        for (vertex, edge) in edges {
+            deps.add_vertex(vertex);
+            deps.add_edge(vertex, edge);
+        }
+        assert_eq!(deps.find_cycles(), expect);
+    }
+
+    #[rstest]
+    #[case::acyclic(false, vec![])]
+    #[case::cyclic(true, vec![vec!["layer0-a", "layer1-a", "layer0-a"]])]
+    fn dependencies_find_cycles_stacked_diamonds(
+        #[case] close: bool,
+        #[case] expect: Vec<Vec<&str>>,
+    ) {
+        // Every layer doubles simple paths through the graph, so searching
+        // them all would never finish.
+        let mut deps = Dependencies::new();
+        for layer in 0..64 {
+            let next = layer + 1;
+            for (from, to) in [("a", "a"), ("a", "b"), ("b", "a"), ("b", "b")] {
+                deps.add_edge(format!("layer{layer}-{from}"), format!("layer{next}-{to}"));
+            }
+        }
+        if close {
+            deps.add_edge("layer1-a", "layer0-a");
+        }
+        assert_eq!(deps.find_cycles(), expect);
+        assert_eq!(deps.acyclic_check().is_ok(), !close);
+    }
+
+    #[rstest]
+    #[case::typo("shel-common", Some("shell-common"))]
+    #[case::no_close_match("emacs", None)]
+    fn dependencies_known_check_return_err(#[case] dep: &str, #[case] suggestion: Option<&str>) {
+        let mut deps = Dependencies::new();
+        deps.add_vertex("vim");
+        deps.add_vertex("shell-common");
+        deps.add_edge("vim", dep);
+        let result = deps.known_check();
+        let InnerDependencyError::UnknownDependency { unknown, details } = result.unwrap_err().0
+        else {
+            panic!("Expected unknown dependency error");
+        };
+        assert_eq!(unknown, vec![("vim".to_string(), dep.to_string())]);
+        assert_eq!(details.contains("did you mean"), suggestion.is_some());
+        if let Some(suggestion) = suggestion {
+            assert!(details.contains(suggestion));
+        }
+    }
+
+    #[rstest]
+    fn dependencies_known_check_return_ok() {
+        let mut deps = Dependencies::new();
+        deps.add_vertex("vim");
+        deps.add_vertex("shell-common");
+        deps.add_edge("vim", "shell-common");
+        assert!(deps.known_check().is_ok());
+    }
+
+    #[rstest]
+    #[case::equal("vim", "vim", 0)]
+    #[case::insert("shel", "shell", 1)]
+    #[case::substitute("kitten", "sitting", 3)]
+    #[case::empty("", "abc", 3)]
+    fn edit_distance_return_distance(#[case] lhs: &str, #[case] rhs: &str, #[case] expect: usize) {
+        assert_eq!(edit_distance(lhs, rhs), expect);
     }
 
     #[rstest]
@@ -198,19 +829,174 @@ fn dependencies_acyclic_check_return_ok() {
         assert!(result.is_ok());
     }
 
-    #[rstest]
-    fn dependencies_iter_dfs_produces_correct_path() {
+    fn deps_fixture() -> Dependencies {
         let mut deps = Dependencies::new();
         deps.add_vertex("vim");
         deps.add_vertex("foo");
         deps.add_vertex("bar");
         deps.add_vertex("baz");
+        deps.add_vertex("zsh");
         deps.add_edge("vim", "foo");
         deps.add_edge("vim", "bar");
         deps.add_edge("vim", "baz");
-        let expect = vec!["bar", "baz", "foo", "vim"];
-        let mut result = deps.iter_dfs("vim").collect::<Vec<String>>();
-        result.sort();
-        assert_eq!(result, expect);
+        deps.add_edge("foo", "shell");
+        deps.add_edge("zsh", "shell");
+        deps
+    }
+
+    #[rstest]
+    #[case::from_root("vim", vec!["vim", "foo", "shell", "bar", "baz", "zsh"])]
+    #[case::restart_in_declaration_order("zsh", vec!["zsh", "shell", "vim", "foo", "bar", "baz"])]
+    fn dependencies_iter_dfs_produces_correct_path(#[case] start: &str, #[case] expect: Vec<&str>) {
+        let deps = deps_fixture();
+        for _ in 0..10 {
+            let result = deps.iter_dfs(start).collect::<Vec<String>>();
+            assert_eq!(result, expect);
+        }
+    }
+
+    #[rstest]
+    fn dependencies_topological_sort_produces_stable_order() {
+        let deps = deps_fixture();
+        let expect = vec!["vim", "zsh", "foo", "bar", "baz", "shell"];
+        for _ in 0..10 {
+            assert_eq!(deps.topological_sort(), expect);
+        }
+    }
+
+    fn desktop_fixture() -> Dependencies {
+        let mut deps = Dependencies::new();
+        for vertex in ["i3", "sway", "polybar", "fonts", "picom", "waybar"] {
+            deps.add_vertex(vertex);
+        }
+        deps.add_edge("i3", "polybar");
+        deps.add_typed_edge("i3", "picom", DependencyKind::Recommends);
+        deps.add_typed_edge("i3", "sway", DependencyKind::Conflicts);
+        deps.add_edge("polybar", "fonts");
+        deps.add_edge("sway", "waybar");
+        deps.add_typed_edge("sway", "fonts", DependencyKind::Recommends);
+        deps
+    }
+
+    #[rstest]
+    #[case::accept_recommends(vec!["i3"], true, vec!["fonts", "polybar", "picom", "i3"])]
+    #[case::skip_recommends(vec!["i3"], false, vec!["fonts", "polybar", "i3"])]
+    #[case::shared_dependency(
+        vec!["polybar", "sway"],
+        true,
+        vec!["fonts", "polybar", "waybar", "sway"],
+    )]
+    fn dependencies_resolve_return_deploy_order(
+        #[case] targets: Vec<&str>,
+        #[case] accept: bool,
+        #[case] expect: Vec<&str>,
+    ) {
+        let deps = desktop_fixture();
+        assert_eq!(deps.resolve(targets, |_| accept), expect);
+    }
+
+    #[rstest]
+    #[case::both_planned(vec!["i3", "sway"], vec![], Some(("i3", "sway")))]
+    #[case::other_deployed(vec!["i3"], vec!["sway"], Some(("i3", "sway")))]
+    #[case::declarer_deployed(vec!["sway"], vec!["i3"], Some(("sway", "i3")))]
+    #[case::no_conflict(vec!["i3", "polybar"], vec!["fonts"], None)]
+    fn dependencies_conflict_check_return_result(
+        #[case] plan: Vec<&str>,
+        #[case] deployed: Vec<&str>,
+        #[case] expect: Option<(&str, &str)>,
+    ) {
+        let deps = desktop_fixture();
+        let plan = plan.into_iter().map(String::from).collect::<Vec<_>>();
+        let result = deps.conflict_check(&plan, |repo| deployed.contains(&repo));
+        match expect {
+            Some((expect_repo, expect_other)) => {
+                let InnerDependencyError::Conflict { repo, other, .. } = result.unwrap_err().0
+                else {
+                    panic!("Expected conflict error");
+                };
+                assert_eq!((repo.as_str(), other.as_str()), (expect_repo, expect_other));
+            }
+            None => assert!(result.is_ok()),
+        }
+    }
+
+    #[rstest]
+    #[case::satisfied(Some("v2.3.0"), None)]
+    #[case::too_old(Some("v1.9.0"), Some("but 'v1.9.0 (1a2b3c4)' is checked out"))]
+    #[case::unknown(None, Some("but its revision could not be determined"))]
+    fn dependencies_rev_check_return_result(
+        #[case] tag: Option<&str>,
+        #[case] expect: Option<&str>,
+    ) {
+        let mut deps = Dependencies::new();
+        deps.add_vertex("vim");
+        deps.add_vertex("shell-common");
+        deps.add_edge("vim", "shell-common");
+        deps.pin("vim", "shell-common", ">=v2".parse().unwrap());
+        let plan = vec!["shell-common".to_string(), "vim".to_string()];
+        let result = deps.rev_check(&plan, |_| {
+            tag.map(|tag| RevInfo {
+                commit: "1a2b3c4d".into(),
+                tags: vec![tag.into()],
+                nearest_tag: Some(tag.into()),
+            })
+        });
+        match expect {
+            Some(expect) => {
+                let message = result.unwrap_err().to_string();
+                assert!(message.starts_with("Repository 'vim' requires revision '>=v2'"));
+                assert!(message.ends_with(expect));
+            }
+            None => assert!(result.is_ok()),
+        }
+    }
+
+    #[rstest]
+    fn dependencies_known_check_unknown_conflict_return_err() {
+        let mut deps = Dependencies::new();
+        deps.add_vertex("i3");
+        deps.add_typed_edge("i3", "swey", DependencyKind::Conflicts);
+        deps.add_vertex("sway");
+        let result = deps.known_check().unwrap_err();
+        assert_eq!(
+            result.to_string(),
+            "Found dependencies on unknown repositories:\n  \
+             'i3' conflicts with 'swey', did you mean 'sway'?"
+        );
+    }
+
+    #[rstest]
+    fn dependencies_known_check_report_every_unknown() {
+        let mut deps = Dependencies::new();
+        deps.add_vertex("vim");
+        deps.add_vertex("i3");
+        deps.add_vertex("sway");
+        deps.add_edge("vim", "shel-common");
+        deps.add_edge("vim", "emacs");
+        deps.add_typed_edge("i3", "swey", DependencyKind::Conflicts);
+        let InnerDependencyError::UnknownDependency { unknown, .. } =
+            deps.known_check().unwrap_err().0
+        else {
+            panic!("Expected unknown dependency error");
+        };
+        assert_eq!(
+            unknown,
+            vec![
+                ("vim".to_string(), "shel-common".to_string()),
+                ("vim".to_string(), "emacs".to_string()),
+                ("i3".to_string(), "swey".to_string()),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn dependencies_vertices_return_declared_then_named() {
+        let mut deps = Dependencies::new();
+        deps.add_vertex("zsh");
+        deps.add_vertex("vim");
+        deps.add_edge("vim", "undeclared-b");
+        deps.add_edge("zsh", "undeclared-a");
+        let result = deps.vertices();
+        assert_eq!(result, vec!["zsh", "vim", "undeclared-a", "undeclared-b"]);
     }
 }
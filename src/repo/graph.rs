@@ -0,0 +1,343 @@
+// SPDX-FileCopyrightText: 2024 Jason Pena <jasonpena@awkless.com>
+// SPDX-License-Identifier: MIT
+
+use crate::repo::{Dependencies, DependencyKind};
+
+use std::collections::{HashMap, HashSet};
+
+/// State of repository annotated onto dependency graph.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct RepoState {
+    /// Repository has been deployed.
+    pub deployed: bool,
+
+    /// Bootstrap filters of repository match current host.
+    pub applies: bool,
+}
+
+impl RepoState {
+    fn describe(&self) -> String {
+        let mut notes = vec![if self.deployed { "deployed" } else { "undeployed" }];
+        if !self.applies {
+            notes.push("host mismatch");
+        }
+
+        notes.join(", ")
+    }
+}
+
+/// Renderer of repository dependencies.
+///
+/// Renders dependencies as an indented tree for the terminal, or as Graphviz
+/// DOT or Mermaid for documentation. Graph can optionally be rooted at a
+/// single repository, such that only that repository and everything it pulls
+/// in are rendered. Recommended dependencies are marked as such, while
+/// conflicts are not rendered.
+#[derive(Debug)]
+pub struct DependencyGraph<'deps> {
+    deps: &'deps Dependencies,
+    states: HashMap<String, RepoState>,
+    root: Option<String>,
+}
+
+impl<'deps> DependencyGraph<'deps> {
+    /// Construct new dependency graph renderer.
+    pub fn new(deps: &'deps Dependencies) -> Self {
+        Self { deps, states: HashMap::new(), root: None }
+    }
+
+    /// Annotate repository with its state.
+    pub fn with_state(mut self, repo: impl Into<String>, state: RepoState) -> Self {
+        self.states.insert(repo.into(), state);
+        self
+    }
+
+    /// Only render given repository and its dependencies.
+    pub fn with_root(mut self, root: impl Into<String>) -> Self {
+        self.root = Some(root.into());
+        self
+    }
+
+    /// Render as indented tree.
+    ///
+    /// Repositories that were already expanded earlier in the tree are marked
+    /// with `(*)` instead of being expanded again.
+    pub fn to_tree(&self) -> String {
+        let roots = match &self.root {
+            Some(root) => vec![root.as_str()],
+            None => {
+                let targets = self
+                    .deps
+                    .vertices()
+                    .into_iter()
+                    .flat_map(|vertex| self.deps.edges(vertex))
+                    .collect::<HashSet<_>>();
+                self.deps
+                    .vertices()
+                    .into_iter()
+                    .filter(|vertex| !targets.contains(vertex))
+                    .map(String::as_str)
+                    .collect()
+            }
+        };
+
+        let mut output = String::new();
+        let mut expanded = HashSet::new();
+        for root in roots {
+            self.render_subtree(root, "", "", &mut expanded, &mut output);
+        }
+
+        output
+    }
+
+    /// Render as Graphviz DOT.
+    ///
+    /// Repositories whose bootstrap filters do not match current host are
+    /// drawn dashed.
+    pub fn to_dot(&self) -> String {
+        let vertices = self.reachable();
+        let mut output = String::from("digraph dependencies {\n");
+        for vertex in &vertices {
+            let state = self.state(vertex);
+            let name = escape_dot(vertex);
+            let label = escape_dot(&state.describe());
+            let style = if state.applies { "" } else { ", style=dashed" };
+            output.push_str(&format!("    \"{name}\" [label=\"{name}\\n({label})\"{style}];\n"));
+        }
+
+        for vertex in &vertices {
+            for edge in self.deps.edges(vertex) {
+                let style = if self.is_recommended(vertex, edge) { " [style=dotted]" } else { "" };
+                let (vertex, edge) = (escape_dot(vertex), escape_dot(edge));
+                output.push_str(&format!("    \"{vertex}\" -> \"{edge}\"{style};\n"));
+            }
+        }
+        output.push_str("}\n");
+
+        output
+    }
+
+    /// Render as Mermaid flowchart.
+    ///
+    /// Repositories whose bootstrap filters do not match current host are
+    /// drawn dashed.
+    pub fn to_mermaid(&self) -> String {
+        let vertices = self.reachable();
+        let ids = vertices
+            .iter()
+            .enumerate()
+            .map(|(idx, vertex)| (*vertex, format!("n{idx}")))
+            .collect::<HashMap<_, _>>();
+
+        let mut output = String::from("graph TD\n");
+        let mut mismatched = Vec::new();
+        for vertex in &vertices {
+            let state = self.state(vertex);
+            let label = format!("{vertex} ({})", state.describe()).replace('"', "#quot;");
+            output.push_str(&format!("    {}[\"{label}\"]\n", ids[vertex]));
+            if !state.applies {
+                mismatched.push(ids[vertex].as_str());
+            }
+        }
+
+        for vertex in &vertices {
+            for edge in self.deps.edges(vertex) {
+                let arrow = if self.is_recommended(vertex, edge) { "-.->" } else { "-->" };
+                output.push_str(&format!("    {} {arrow} {}\n", ids[vertex], ids[edge.as_str()]));
+            }
+        }
+
+        if !mismatched.is_empty() {
+            output.push_str("    classDef mismatch stroke-dasharray: 5 5\n");
+            output.push_str(&format!("    class {} mismatch\n", mismatched.join(",")));
+        }
+
+        output
+    }
+
+    fn render_subtree<'a>(
+        &'a self,
+        vertex: &'a str,
+        prefix: &str,
+        child_prefix: &str,
+        expanded: &mut HashSet<&'a str>,
+        output: &mut String,
+    ) {
+        let state = self.state(vertex).describe();
+        let edges = self.deps.edges(vertex);
+        if !expanded.insert(vertex) && !edges.is_empty() {
+            output.push_str(&format!("{prefix}{vertex} [{state}] (*)\n"));
+            return;
+        }
+
+        output.push_str(&format!("{prefix}{vertex} [{state}]\n"));
+        for (idx, edge) in edges.iter().enumerate() {
+            let (branch, indent) = if idx + 1 == edges.len() {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+            let branch = if self.is_recommended(vertex, edge) {
+                format!("{branch}(recommended) ")
+            } else {
+                branch.to_string()
+            };
+            self.render_subtree(
+                edge,
+                &format!("{child_prefix}{branch}"),
+                &format!("{child_prefix}{indent}"),
+                expanded,
+                output,
+            );
+        }
+    }
+
+    /// Vertices to render in stable order.
+    fn reachable(&self) -> Vec<&'deps str> {
+        let vertices = self.deps.vertices().into_iter().map(String::as_str);
+        let Some(root) = &self.root else {
+            return vertices.collect();
+        };
+
+        let mut seen = HashSet::new();
+        let mut stack = vec![root.as_str()];
+        while let Some(vertex) = stack.pop() {
+            if seen.insert(vertex) {
+                stack.extend(self.deps.edges(vertex).iter().map(String::as_str));
+            }
+        }
+
+        vertices.filter(|vertex| seen.contains(vertex)).collect()
+    }
+
+    fn is_recommended(&self, vertex: &str, edge: &str) -> bool {
+        self.deps.kind(vertex, edge) == Some(DependencyKind::Recommends)
+    }
+
+    fn state(&self, vertex: &str) -> RepoState {
+        self.states.get(vertex).copied().unwrap_or(RepoState { deployed: false, applies: true })
+    }
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+    use rstest::{fixture, rstest};
+
+    #[fixture]
+    fn deps() -> Dependencies {
+        let mut deps = Dependencies::new();
+        for vertex in ["i3", "polybar", "fonts", "dunst", "vim"] {
+            deps.add_vertex(vertex);
+        }
+        deps.add_edge("i3", "polybar");
+        deps.add_typed_edge("i3", "dunst", DependencyKind::Recommends);
+        deps.add_edge("polybar", "fonts");
+        deps.add_edge("dunst", "fonts");
+        deps
+    }
+
+    fn graph(deps: &Dependencies) -> DependencyGraph<'_> {
+        DependencyGraph::new(deps)
+            .with_state("i3", RepoState { deployed: true, applies: true })
+            .with_state("polybar", RepoState { deployed: true, applies: true })
+            .with_state("fonts", RepoState { deployed: false, applies: true })
+            .with_state("dunst", RepoState { deployed: false, applies: false })
+            .with_state("vim", RepoState { deployed: true, applies: true })
+    }
+
+    #[rstest]
+    #[case::all(
+        None,
+        indoc! {"
+            i3 [deployed]
+            ├── polybar [deployed]
+            │   └── fonts [undeployed]
+            └── (recommended) dunst [undeployed, host mismatch]
+                └── fonts [undeployed]
+            vim [deployed]
+        "},
+    )]
+    #[case::rooted(
+        Some("polybar"),
+        indoc! {"
+            polybar [deployed]
+            └── fonts [undeployed]
+        "},
+    )]
+    fn dependency_graph_to_tree_return_tree(
+        deps: Dependencies,
+        #[case] root: Option<&str>,
+        #[case] expect: &str,
+    ) {
+        let mut graph = graph(&deps);
+        if let Some(root) = root {
+            graph = graph.with_root(root);
+        }
+        assert_eq!(graph.to_tree(), expect);
+    }
+
+    #[rstest]
+    fn dependency_graph_to_tree_mark_repeated_subtree() {
+        let mut deps = Dependencies::new();
+        deps.add_vertex("sway");
+        deps.add_vertex("waybar");
+        deps.add_vertex("fonts");
+        deps.add_vertex("icons");
+        deps.add_edge("sway", "waybar");
+        deps.add_edge("sway", "fonts");
+        deps.add_edge("fonts", "icons");
+        deps.add_edge("waybar", "fonts");
+        let expect = indoc! {"
+            sway [undeployed]
+            ├── waybar [undeployed]
+            │   └── fonts [undeployed]
+            │       └── icons [undeployed]
+            └── fonts [undeployed] (*)
+        "};
+        assert_eq!(DependencyGraph::new(&deps).to_tree(), expect);
+    }
+
+    #[rstest]
+    fn dependency_graph_to_dot_return_dot(deps: Dependencies) {
+        let expect = indoc! {r#"
+            digraph dependencies {
+                "i3" [label="i3\n(deployed)"];
+                "polybar" [label="polybar\n(deployed)"];
+                "fonts" [label="fonts\n(undeployed)"];
+                "dunst" [label="dunst\n(undeployed, host mismatch)", style=dashed];
+                "i3" -> "polybar";
+                "i3" -> "dunst" [style=dotted];
+                "polybar" -> "fonts";
+                "dunst" -> "fonts";
+            }
+        "#};
+        assert_eq!(graph(&deps).with_root("i3").to_dot(), expect);
+    }
+
+    #[rstest]
+    fn dependency_graph_to_mermaid_return_mermaid(deps: Dependencies) {
+        let expect = indoc! {r#"
+            graph TD
+                n0["i3 (deployed)"]
+                n1["polybar (deployed)"]
+                n2["fonts (undeployed)"]
+                n3["dunst (undeployed, host mismatch)"]
+                n4["vim (deployed)"]
+                n0 --> n1
+                n0 -.-> n3
+                n1 --> n2
+                n3 --> n2
+                classDef mismatch stroke-dasharray: 5 5
+                class n3 mismatch
+        "#};
+        assert_eq!(graph(&deps).to_mermaid(), expect);
+    }
+}
@@ -0,0 +1,236 @@
+// SPDX-FileCopyrightText: 2024 Jason Pena <jasonpena@awkless.com>
+// SPDX-License-Identifier: MIT
+
+use log::debug;
+use mkdirp::mkdirp;
+use snafu::prelude::*;
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    fs::{read_to_string, write},
+    io::Error as IoError,
+    path::{Path, PathBuf},
+};
+use toml_edit::{value, DocumentMut, Item, Table, TomlError};
+
+/// Current format version of lockfile.
+pub const LOCK_VERSION: i64 = 1;
+
+/// Name of lockfile, placed next to repository configuration file.
+pub const LOCK_FILE_NAME: &str = "ocd.lock";
+
+/// Exact commits of repositories for reproducible setups.
+///
+/// Records the remote URL, branch, and checked out commit of each repository,
+/// such that the same set of commits can be deployed on another machine
+/// later on.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct Lockfile {
+    repos: Vec<LockedRepo>,
+}
+
+impl Lockfile {
+    /// Construct new empty lockfile.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Record repository, replacing any existing record of it.
+    pub fn with_repo(mut self, repo: LockedRepo) -> Self {
+        match self.repos.iter_mut().find(|locked| locked.name == repo.name) {
+            Some(locked) => *locked = repo,
+            None => self.repos.push(repo),
+        }
+        self
+    }
+
+    /// Get record of repository.
+    pub fn get(&self, name: &str) -> Option<&LockedRepo> {
+        self.repos.iter().find(|repo| repo.name == name)
+    }
+
+    /// Iterate through records of repositories in order of recording.
+    pub fn iter(&self) -> impl Iterator<Item = &LockedRepo> {
+        self.repos.iter()
+    }
+
+    /// Load lockfile from given path.
+    ///
+    /// # Errors
+    ///
+    /// Will fail if lockfile cannot be read, is not valid TOML, has an
+    /// unsupported format version, or has a malformed record.
+    pub fn load(path: &Path) -> Result<Self, LockError> {
+        debug!("Load lockfile from '{}'", path.display());
+        let data = read_to_string(path).context(ReadSnafu { path })?;
+        let doc: DocumentMut = data.parse().context(SyntaxSnafu { path })?;
+
+        let version = doc.get("version").and_then(Item::as_integer);
+        ensure!(version == Some(LOCK_VERSION), UnsupportedVersionSnafu { path });
+
+        let mut lockfile = Self::new();
+        let Some(repos) = doc.get("repos") else {
+            return Ok(lockfile);
+        };
+
+        let repos = repos.as_table_like().context(BadRecordSnafu { path, name: "repos" })?;
+        for (name, item) in repos.iter() {
+            let field = |field: &str| {
+                item.get(field)
+                    .and_then(Item::as_str)
+                    .map(String::from)
+                    .context(BadRecordSnafu { path, name: format!("repos.{name}.{field}") })
+            };
+            lockfile = lockfile.with_repo(LockedRepo {
+                name: name.into(),
+                url: field("url")?,
+                branch: field("branch")?,
+                commit: field("commit")?,
+            });
+        }
+
+        Ok(lockfile)
+    }
+
+    /// Save lockfile to given path.
+    ///
+    /// # Errors
+    ///
+    /// Will fail if lockfile cannot be written to.
+    pub fn save(&self, path: &Path) -> Result<(), LockError> {
+        debug!("Save lockfile to '{}'", path.display());
+        if let Some(root) = path.parent() {
+            mkdirp(root).context(WriteSnafu { path })?;
+        }
+        write(path, self.to_string()).context(WriteSnafu { path })?;
+
+        Ok(())
+    }
+}
+
+impl Display for Lockfile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let mut doc = DocumentMut::new();
+        doc["version"] = value(LOCK_VERSION);
+        doc.decor_mut().set_prefix("# Generated by `ocd lock`. Do not edit by hand.\n");
+
+        let mut repos = Table::new();
+        repos.set_implicit(true);
+        for repo in &self.repos {
+            let mut table = Table::new();
+            table["url"] = value(&repo.url);
+            table["branch"] = value(&repo.branch);
+            table["commit"] = value(&repo.commit);
+            repos.insert(&repo.name, Item::Table(table));
+        }
+        doc["repos"] = Item::Table(repos);
+
+        write!(f, "{doc}")
+    }
+}
+
+/// Record of repository in lockfile.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct LockedRepo {
+    pub name: String,
+    pub url: String,
+    pub branch: String,
+    pub commit: String,
+}
+
+/// Lockfile error type for public API.
+#[derive(Debug, Snafu)]
+pub struct LockError(InnerLockError);
+
+#[derive(Debug, Snafu)]
+enum InnerLockError {
+    #[snafu(display("Failed to read lockfile '{}'", path.display()))]
+    Read { path: PathBuf, source: IoError },
+
+    #[snafu(display("Failed to write lockfile '{}'", path.display()))]
+    Write { path: PathBuf, source: IoError },
+
+    #[snafu(display("Failed to parse lockfile '{}'", path.display()))]
+    Syntax {
+        path: PathBuf,
+        #[snafu(source(from(TomlError, Box::new)))]
+        source: Box<TomlError>,
+    },
+
+    #[snafu(display("Lockfile '{}' must have 'version = {LOCK_VERSION}'", path.display()))]
+    UnsupportedVersion { path: PathBuf },
+
+    #[snafu(display("Lockfile '{}' has malformed record '{name}'", path.display()))]
+    BadRecord { path: PathBuf, name: String },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::testenv::{FileKind, FixtureHarness};
+
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use snafu::{report, Whatever};
+
+    fn locked(name: &str, commit: &str) -> LockedRepo {
+        LockedRepo {
+            name: name.into(),
+            url: format!("https://some/{name}.git"),
+            branch: "master".into(),
+            commit: commit.into(),
+        }
+    }
+
+    #[report]
+    #[rstest]
+    fn lockfile_save_load_round_trip() -> Result<(), Whatever> {
+        let harness = FixtureHarness::open()?;
+        let path = harness.as_path().join(LOCK_FILE_NAME);
+        let lockfile = Lockfile::new()
+            .with_repo(locked("vim", "1a2b3c4d"))
+            .with_repo(locked("sh", "5e6f7a8b"))
+            .with_repo(locked("vim", "9c0d1e2f"));
+        lockfile.save(&path).with_whatever_context(|_| "Failed to save lockfile")?;
+
+        let expect = indoc! {r#"
+            # Generated by `ocd lock`. Do not edit by hand.
+            version = 1
+
+            [repos.vim]
+            url = "https://some/vim.git"
+            branch = "master"
+            commit = "9c0d1e2f"
+
+            [repos.sh]
+            url = "https://some/sh.git"
+            branch = "master"
+            commit = "5e6f7a8b"
+        "#};
+        let data = read_to_string(&path).with_whatever_context(|_| "Failed to read lockfile")?;
+        assert_eq!(data, expect);
+
+        let result = Lockfile::load(&path).with_whatever_context(|_| "Failed to load lockfile")?;
+        assert_eq!(result, lockfile);
+        assert_eq!(result.get("vim").map(|repo| repo.commit.as_str()), Some("9c0d1e2f"));
+
+        Ok(())
+    }
+
+    #[report]
+    #[rstest]
+    #[case::no_version("[repos.vim]\nurl = 'url'\nbranch = 'master'\ncommit = 'abc'\n")]
+    #[case::future_version("version = 2\n")]
+    #[case::missing_commit("version = 1\n[repos.vim]\nurl = 'url'\nbranch = 'master'\n")]
+    #[case::bad_syntax("version = ")]
+    fn lockfile_load_return_err(#[case] data: &str) -> Result<(), Whatever> {
+        let harness = FixtureHarness::open()?.with_file(LOCK_FILE_NAME, |fixture| {
+            fixture.data(data).kind(FileKind::Normal).write()
+        })?;
+        let fixture = harness.get(LOCK_FILE_NAME)?;
+        assert!(Lockfile::load(fixture.as_path()).is_err());
+
+        Ok(())
+    }
+}
@@ -1,25 +1,186 @@
 // SPDX-FileCopyrightText: 2024 Jason Pena <jasonpena@awkless.com>
 // SPDX-License-Identifier: MIT
 
-use log::info;
+#[cfg(feature = "gix")]
+mod native;
+mod porcelain;
+
+#[cfg(feature = "gix")]
+pub use native::*;
+pub use porcelain::*;
+
+use crate::config::RevInfo;
+
+use log::{debug, info};
 use snafu::prelude::*;
-use std::{ffi::OsString, io::Error as IoError, process::Command};
+use std::{
+    ffi::OsString,
+    fmt::{Display, Formatter, Result as FmtResult},
+    io::{self, BufRead, BufReader, Error as IoError, Read, Write},
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus, Stdio},
+    str::FromStr,
+    sync::OnceLock,
+    thread,
+};
+
+/// Oldest version of Git binary that is supported.
+///
+/// Needed for `git init --initial-branch` and `git sparse-checkout`.
+pub const MIN_GIT_VERSION: GitVersion = GitVersion { major: 2, minor: 25, patch: 0 };
+
+/// Version control backend of repository manager.
+///
+/// Covers every version control operation that repository manager needs,
+/// such that repository logic does not depend on any one backend.
+pub trait Vcs {
+    /// Initialize new repository at `path` with `branch` as initial branch.
+    fn init(&self, path: &Path, branch: &str, bare: bool) -> Result<(), VcsError>;
+
+    /// Clone repository from `url` into `path`, checking out `branch`.
+    fn clone_repo(&self, url: &str, path: &Path, branch: &str, bare: bool) -> Result<(), VcsError>;
+
+    /// Check out `rev` into work tree of repository, or current branch if no
+    /// revision is given.
+    fn checkout(&self, repo: &RepoPaths, rev: Option<&str>) -> Result<(), VcsError>;
+
+    /// Determine status of index and work tree of repository.
+    ///
+    /// Untracked files are listed if `untracked` is set, or repository is not
+    /// configured to hide them.
+    fn status(&self, repo: &RepoPaths, untracked: bool) -> Result<StatusReport, VcsError>;
+
+    /// Count commits of `left` and `right` that the other does not have.
+    fn ahead_behind(
+        &self,
+        repo: &RepoPaths,
+        left: &str,
+        right: &str,
+    ) -> Result<AheadBehind, VcsError>;
+
+    /// List references of repository matching `patterns`, e.g., `refs/tags`.
+    fn refs(&self, repo: &RepoPaths, patterns: &[&str]) -> Result<Vec<RefRecord>, VcsError>;
+
+    /// Commit staged changes of repository with `message`, or let user write
+    /// message in their editor if no message is given.
+    fn commit(&self, repo: &RepoPaths, message: Option<&str>) -> Result<(), VcsError>;
+
+    /// Push `branch` of repository to `remote`.
+    fn push(&self, repo: &RepoPaths, remote: &str, branch: &str) -> Result<(), VcsError>;
+
+    /// Pull `branch` of `remote` into repository.
+    fn pull(&self, repo: &RepoPaths, remote: &str, branch: &str) -> Result<(), VcsError>;
+
+    /// List files tracked by repository.
+    fn ls_files(&self, repo: &RepoPaths) -> Result<Vec<PathBuf>, VcsError>;
+
+    /// Determine checked out revision of repository.
+    fn rev_info(&self, repo: &RepoPaths) -> Result<RevInfo, VcsError>;
+
+    /// Check if `commit` exists in object database of repository.
+    fn has_commit(&self, repo: &RepoPaths, commit: &str) -> Result<bool, VcsError>;
+
+    /// Determine URL of `remote` of repository.
+    fn remote_url(&self, repo: &RepoPaths, remote: &str) -> Result<String, VcsError>;
+
+    /// Set configuration `key` of repository to `value`, e.g.,
+    /// `status.showUntrackedFiles`.
+    fn set_config(&self, repo: &RepoPaths, key: &str, value: &str) -> Result<(), VcsError>;
+}
+
+/// Paths of repository that version control operations act on.
+///
+/// Normal repositories keep their Git directory inside of their work tree.
+/// Bare-alias repositories keep their Git directory in the repository store,
+/// and use their alias as work tree. Plain bare repositories have no work
+/// tree at all.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RepoPaths {
+    pub git_dir: PathBuf,
+    pub work_tree: Option<PathBuf>,
+}
+
+impl RepoPaths {
+    /// Paths of normal repository with work tree at `path`.
+    pub fn normal(path: impl Into<PathBuf>) -> Self {
+        let work_tree = path.into();
+        Self { git_dir: work_tree.join(".git"), work_tree: Some(work_tree) }
+    }
+
+    /// Paths of bare-alias repository at `git_dir` aliased to `work_tree`.
+    pub fn bare_alias(git_dir: impl Into<PathBuf>, work_tree: impl Into<PathBuf>) -> Self {
+        Self { git_dir: git_dir.into(), work_tree: Some(work_tree.into()) }
+    }
+
+    /// Paths of bare repository at `git_dir`.
+    pub fn bare(git_dir: impl Into<PathBuf>) -> Self {
+        Self { git_dir: git_dir.into(), work_tree: None }
+    }
+
+    /// Location of repository, i.e., work tree of normal repositories, or Git
+    /// directory of any other repository.
+    pub fn root(&self) -> &Path {
+        match &self.work_tree {
+            Some(work_tree) if !self.is_bare_alias() => work_tree,
+            _ => &self.git_dir,
+        }
+    }
+
+    /// Short name of repository for labeling its output.
+    pub fn label(&self) -> String {
+        let root = self.root();
+        root.file_name().unwrap_or(root.as_os_str()).to_string_lossy().into_owned()
+    }
+
+    /// Check if Git directory lives outside of work tree.
+    pub fn is_bare_alias(&self) -> bool {
+        self.work_tree.as_ref().is_some_and(|work_tree| self.git_dir != work_tree.join(".git"))
+    }
+}
+
+/// Environment variables of Git that are never passed on to Git binary.
+///
+/// These variables make Git operate on some other repository, index, or object
+/// database than the one that Git binary was explicitly pointed at.
+pub const SCRUBBED_GIT_ENV: &[&str] = &[
+    "GIT_DIR",
+    "GIT_WORK_TREE",
+    "GIT_COMMON_DIR",
+    "GIT_INDEX_FILE",
+    "GIT_OBJECT_DIRECTORY",
+    "GIT_ALTERNATE_OBJECT_DIRECTORIES",
+    "GIT_NAMESPACE",
+    "GIT_PREFIX",
+    "GIT_CEILING_DIRECTORIES",
+    "GIT_DISCOVERY_ACROSS_FILESYSTEM",
+    "GIT_IMPLICIT_WORK_TREE",
+    "GIT_QUARANTINE_PATH",
+];
 
 /// Git binary handler.
 ///
 /// Manages system calls to user's Git binary to help manage Git repository
-/// data.
+/// data. Handlers constructed for a repository always pass its Git directory
+/// and work tree to Git binary explicitly, and run from its work tree. Any
+/// variable of [`SCRUBBED_GIT_ENV`] in the environment is ignored, such that
+/// Git binary cannot be redirected to some other repository.
 #[derive(Debug, Default, Clone)]
 pub struct Git {
     args: Vec<OsString>,
+    repo: Option<RepoPaths>,
 }
 
 impl Git {
-    /// Construct new Git handler.
+    /// Construct new Git handler that is not bound to any repository.
     pub fn new() -> Self {
         Default::default()
     }
 
+    /// Construct new Git handler bound to given repository.
+    pub fn for_repo(repo: &RepoPaths) -> Self {
+        Self { args: Vec::new(), repo: Some(repo.clone()) }
+    }
+
     /// Add single argument to pass to Git binary.
     pub fn with_arg(&mut self, arg: impl Into<OsString>) {
         self.args.push(arg.into());
@@ -34,50 +195,751 @@ pub fn with_args(&mut self, args: impl IntoIterator<Item = impl Into<OsString>>)
     ///
     /// Will pass given arguments to Git binary. Will log and return any output
     /// Git has written to stdout after calling it. Any arguments given will
-    /// also be cleared for new arguments to be passed later on.
+    /// also be cleared for new arguments to be passed later on, even if Git
+    /// binary fails.
     ///
     /// # Errors
     ///
     /// Will fail if system call to Git binary fails, or Git binary itself fails
     /// to execute with given arguments.
-    pub fn run(&mut self) -> Result<String, GitError> {
-        let output = Command::new("git").args(&self.args).output().context(SyscallSnafu)?;
+    pub fn run(&mut self) -> Result<String, VcsError> {
+        let output = self.capture()?;
         if !output.status.success() {
-            let msg = String::from_utf8_lossy(output.stderr.as_slice()).into_owned();
-            return Err(GitError(InnerGitError::GitBin { msg }));
+            return Err(VcsError(InnerVcsError::GitBin { msg: output.stderr }));
+        }
+
+        info!("{}", output.stdout);
+        Ok(output.stdout)
+    }
+
+    /// Call Git binary, capturing its output.
+    ///
+    /// Unlike [`Git::run`], Git binary exiting with failure is not treated as
+    /// an error. Any arguments given will be cleared.
+    ///
+    /// # Errors
+    ///
+    /// Will fail if system call to Git binary fails.
+    pub fn capture(&mut self) -> Result<GitOutput, VcsError> {
+        let args = std::mem::take(&mut self.args);
+        let output = self.command().args(args).output().context(SyscallSnafu)?;
+
+        Ok(GitOutput {
+            stdout: String::from_utf8_lossy(output.stdout.as_slice()).into_owned(),
+            stderr: String::from_utf8_lossy(output.stderr.as_slice()).into_owned(),
+            status: output.status,
+        })
+    }
+
+    /// Call Git binary, streaming its output live.
+    ///
+    /// Every line Git writes is forwarded as it arrives, prefixed with
+    /// `[label]`, such that output of long running calls on several
+    /// repositories can be told apart. Progress lines that Git keeps
+    /// rewriting are only forwarded once they are complete. Any arguments
+    /// given will be cleared.
+    ///
+    /// # Errors
+    ///
+    /// Will fail if system call to Git binary fails, or Git binary exits with
+    /// failure.
+    pub fn stream(&mut self, label: &str) -> Result<(), VcsError> {
+        let args = std::mem::take(&mut self.args);
+        let mut child = self
+            .command()
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context(SyscallSnafu)?;
+
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        thread::scope(|scope| {
+            if let Some(stdout) = stdout {
+                scope.spawn(|| prefix_lines(stdout, label, io::stdout()));
+            }
+            if let Some(stderr) = stderr {
+                scope.spawn(|| prefix_lines(stderr, label, io::stderr()));
+            }
+        });
+
+        let status = child.wait().context(SyscallSnafu)?;
+        ensure!(status.success(), ExitSnafu { status });
+
+        Ok(())
+    }
+
+    /// Call Git binary interactively.
+    ///
+    /// Git binary inherits the terminal of the current process, such that it
+    /// can open editors or pagers. Any arguments given will be cleared.
+    ///
+    /// # Errors
+    ///
+    /// Will fail if system call to Git binary fails, or Git binary exits with
+    /// failure.
+    pub fn interactive(&mut self) -> Result<(), VcsError> {
+        let args = std::mem::take(&mut self.args);
+        let status = self
+            .command()
+            .args(args)
+            .stdin(Stdio::inherit())
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit())
+            .status()
+            .context(SyscallSnafu)?;
+        ensure!(status.success(), ExitSnafu { status });
+
+        Ok(())
+    }
+
+    /// Determine version of Git binary.
+    ///
+    /// Git binary is only probed once, with its version cached for the rest of
+    /// the process.
+    ///
+    /// # Errors
+    ///
+    /// Will fail if Git binary cannot be called, or it reports a version that
+    /// cannot be parsed.
+    pub fn version() -> Result<GitVersion, VcsError> {
+        static VERSION: OnceLock<GitVersion> = OnceLock::new();
+        cached_version(&VERSION, || {
+            let output = Git::new().command().arg("--version").output().context(SyscallSnafu)?;
+            let output = String::from_utf8_lossy(output.stdout.as_slice()).trim().to_string();
+            Ok(output.parse().ok().context(UnknownVersionSnafu { output })?)
+        })
+    }
+
+    /// Ensure that Git binary is at least [`MIN_GIT_VERSION`].
+    ///
+    /// # Errors
+    ///
+    /// Will fail if version of Git binary cannot be determined, or is too old.
+    pub fn version_check() -> Result<GitVersion, VcsError> {
+        supported_check(Self::version()?)
+    }
+
+    /// Command to call Git binary with scrubbed environment.
+    fn command(&self) -> Command {
+        let mut cmd = Command::new("git");
+        for var in SCRUBBED_GIT_ENV {
+            cmd.env_remove(var);
+        }
+
+        if let Some(repo) = &self.repo {
+            // Relative paths would be resolved against the directory Git runs in.
+            let git_dir = absolute(&repo.git_dir);
+            cmd.arg("--git-dir").arg(&git_dir);
+            match repo.work_tree.as_deref().map(absolute) {
+                Some(work_tree) => cmd.arg("--work-tree").arg(&work_tree).current_dir(work_tree),
+                None => cmd.current_dir(git_dir),
+            };
+        }
+
+        cmd
+    }
+
+    /// Run Git binary on repository to query data with given arguments.
+    ///
+    /// Unlike [`Git::run`], output is only logged for debugging, because it is
+    /// meant to be parsed rather than read.
+    fn query_on(
+        &self,
+        repo: &RepoPaths,
+        args: impl IntoIterator<Item = impl Into<OsString>>,
+    ) -> Result<String, VcsError> {
+        let mut git = Git::for_repo(repo);
+        git.with_args(args);
+        let output = git.capture()?;
+        ensure!(output.status.success(), GitBinSnafu { msg: output.stderr });
+
+        debug!("{}", output.stdout);
+        Ok(output.stdout)
+    }
+
+    /// Run Git binary on repository with given arguments.
+    fn run_on(
+        &self,
+        repo: &RepoPaths,
+        args: impl IntoIterator<Item = impl Into<OsString>>,
+    ) -> Result<String, VcsError> {
+        let mut git = Git::for_repo(repo);
+        git.with_args(args);
+        git.run()
+    }
+}
+
+impl Vcs for Git {
+    fn init(&self, path: &Path, branch: &str, bare: bool) -> Result<(), VcsError> {
+        let mut git = Git::new();
+        git.with_arg("init");
+        if bare {
+            git.with_arg("--bare");
+        }
+        git.with_args(["--initial-branch", branch]);
+        git.with_arg(path);
+        git.run().map(drop)
+    }
+
+    fn clone_repo(&self, url: &str, path: &Path, branch: &str, bare: bool) -> Result<(), VcsError> {
+        let mut git = Git::new();
+        git.with_arg("clone");
+        if bare {
+            git.with_arg("--bare");
+        }
+        git.with_args(["--branch", branch, url]);
+        git.with_arg(path);
+        git.run().map(drop)
+    }
+
+    fn checkout(&self, repo: &RepoPaths, rev: Option<&str>) -> Result<(), VcsError> {
+        self.run_on(repo, ["checkout"].into_iter().chain(rev)).map(drop)
+    }
+
+    fn status(&self, repo: &RepoPaths, untracked: bool) -> Result<StatusReport, VcsError> {
+        let mut args = vec!["status", "--porcelain=v2", "--branch", "-z"];
+        if untracked {
+            args.push("--untracked-files=normal");
+        }
+        parse_status(&self.query_on(repo, args)?)
+    }
+
+    fn ahead_behind(
+        &self,
+        repo: &RepoPaths,
+        left: &str,
+        right: &str,
+    ) -> Result<AheadBehind, VcsError> {
+        let range = format!("{left}...{right}");
+        parse_ahead_behind(&self.query_on(repo, ["rev-list", "--left-right", "--count", &range])?)
+    }
+
+    fn refs(&self, repo: &RepoPaths, patterns: &[&str]) -> Result<Vec<RefRecord>, VcsError> {
+        let mut args = vec!["for-each-ref", "--format", REF_FORMAT];
+        args.extend(patterns);
+        parse_refs(&self.query_on(repo, args)?)
+    }
+
+    fn commit(&self, repo: &RepoPaths, message: Option<&str>) -> Result<(), VcsError> {
+        match message {
+            Some(message) => self.run_on(repo, ["commit", "-m", message]).map(drop),
+            None => {
+                let mut git = Git::for_repo(repo);
+                git.with_arg("commit");
+                git.interactive()
+            }
         }
+    }
+
+    fn push(&self, repo: &RepoPaths, remote: &str, branch: &str) -> Result<(), VcsError> {
+        let mut git = Git::for_repo(repo);
+        git.with_args(["push", "--progress", remote, branch]);
+        git.stream(&repo.label())
+    }
+
+    fn pull(&self, repo: &RepoPaths, remote: &str, branch: &str) -> Result<(), VcsError> {
+        let mut git = Git::for_repo(repo);
+        git.with_args(["pull", "--progress", remote, branch]);
+        git.stream(&repo.label())
+    }
+
+    fn ls_files(&self, repo: &RepoPaths) -> Result<Vec<PathBuf>, VcsError> {
+        Ok(parse_ls_files(&self.query_on(repo, ["ls-files", "-z"])?))
+    }
 
-        let msg = String::from_utf8_lossy(output.stdout.as_slice()).into_owned();
-        info!("{msg}");
-        self.args.clear();
+    fn rev_info(&self, repo: &RepoPaths) -> Result<RevInfo, VcsError> {
+        let commit = self.query_on(repo, ["rev-parse", "HEAD"])?.trim().to_string();
+        let tags = self
+            .refs(repo, &["refs/tags"])?
+            .into_iter()
+            .filter(|record| record.target() == commit)
+            .filter_map(|record| record.name.strip_prefix("refs/tags/").map(Into::into))
+            .collect();
+        let nearest_tag = self
+            .query_on(repo, ["describe", "--tags", "--abbrev=0", "HEAD"])
+            .ok()
+            .map(|tag| tag.trim().to_string());
 
-        Ok(msg)
+        Ok(RevInfo { commit, tags, nearest_tag })
+    }
+
+    fn has_commit(&self, repo: &RepoPaths, commit: &str) -> Result<bool, VcsError> {
+        let mut git = Git::for_repo(repo);
+        git.with_args(["cat-file", "-e", &format!("{commit}^{{commit}}")]);
+        Ok(git.capture()?.status.success())
+    }
+
+    fn remote_url(&self, repo: &RepoPaths, remote: &str) -> Result<String, VcsError> {
+        Ok(self.query_on(repo, ["remote", "get-url", remote])?.trim().to_string())
+    }
+
+    fn set_config(&self, repo: &RepoPaths, key: &str, value: &str) -> Result<(), VcsError> {
+        self.run_on(repo, ["config", key, value]).map(drop)
     }
 }
 
-/// Git error type public API.
+/// Captured output of Git binary.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct GitOutput {
+    pub stdout: String,
+    pub stderr: String,
+    pub status: ExitStatus,
+}
+
+/// Forward lines of `reader` into `writer`, prefixing each with `[label]`.
+///
+/// Carriage returns end a line too, but only the last segment of a line that
+/// Git kept rewriting is forwarded.
+fn prefix_lines(reader: impl Read, label: &str, mut writer: impl Write) -> io::Result<()> {
+    for line in BufReader::new(reader).split(b'\n') {
+        let line = line?;
+        let line = line.rsplit(|byte| *byte == b'\r').find(|part| !part.is_empty());
+        let Some(line) = line else {
+            continue;
+        };
+        writeln!(writer, "[{label}] {}", String::from_utf8_lossy(line))?;
+        writer.flush()?;
+    }
+
+    Ok(())
+}
+
+/// Take version from `cache`, or `probe` for it if not cached yet.
+///
+/// Failed probes are not cached, such that they can be retried.
+fn cached_version(
+    cache: &OnceLock<GitVersion>,
+    probe: impl FnOnce() -> Result<GitVersion, VcsError>,
+) -> Result<GitVersion, VcsError> {
+    if let Some(version) = cache.get() {
+        return Ok(*version);
+    }
+
+    let version = probe()?;
+    Ok(*cache.get_or_init(|| version))
+}
+
+/// Ensure that `version` is at least [`MIN_GIT_VERSION`].
+fn supported_check(version: GitVersion) -> Result<GitVersion, VcsError> {
+    ensure!(version >= MIN_GIT_VERSION, UnsupportedVersionSnafu { version });
+
+    Ok(version)
+}
+
+/// Resolve `path` against current working directory if it is relative.
+fn absolute(path: &Path) -> PathBuf {
+    match std::env::current_dir() {
+        Ok(cwd) if path.is_relative() => cwd.join(path),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Version of Git binary.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub struct GitVersion {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl FromStr for GitVersion {
+    type Err = String;
+
+    /// Parse output of `git --version`, e.g., `git version 2.39.5`.
+    ///
+    /// Vendor suffixes like `.windows.1` or ` (Apple Git-146)` are ignored.
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("'{source}' is not a Git version");
+        let version = source.trim().strip_prefix("git version ").ok_or_else(invalid)?;
+        let mut parts = version.split(['.', ' ']).map(|part| part.parse::<u64>());
+        let mut next = || parts.next().and_then(Result::ok);
+        let major = next().ok_or_else(invalid)?;
+        let minor = next().ok_or_else(invalid)?;
+        let patch = next().unwrap_or(0);
+
+        Ok(Self { major, minor, patch })
+    }
+}
+
+impl Display for GitVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Version control error type for public API.
 #[derive(Debug, Snafu)]
-pub struct GitError(InnerGitError);
+pub struct VcsError(InnerVcsError);
+
+impl VcsError {
+    /// Construct error for operation that backend rejected with `msg`.
+    pub fn rejected(msg: impl Into<String>) -> Self {
+        Self(InnerVcsError::Rejected { msg: msg.into() })
+    }
+}
 
 /// Alias to allow one-off functions with different error type.
-pub type Result<T, E = GitError> = std::result::Result<T, E>;
+pub type Result<T, E = VcsError> = std::result::Result<T, E>;
 
 #[derive(Debug, Snafu)]
-enum InnerGitError {
+enum InnerVcsError {
     #[snafu(display("Failed to make syscall to Git binary"))]
     Syscall { source: IoError },
 
     #[snafu(display("{msg}"))]
     GitBin { msg: String },
+
+    #[snafu(display("Git binary failed with {status}"))]
+    Exit { status: ExitStatus },
+
+    #[snafu(display("{msg}"))]
+    Rejected { msg: String },
+
+    #[snafu(display("Failed to parse {what} output of Git binary at '{record}'"))]
+    Porcelain { what: &'static str, record: String },
+
+    #[snafu(display("Failed to determine Git version from '{output}'"))]
+    UnknownVersion { output: String },
+
+    #[snafu(display("Git {version} is not supported, install Git {MIN_GIT_VERSION} or newer"))]
+    UnsupportedVersion { version: GitVersion },
+
+    #[cfg(feature = "gix")]
+    #[snafu(display("Failed to operate on repository through gitoxide"))]
+    Native { source: Box<dyn std::error::Error + Send + Sync> },
+
+    #[cfg(feature = "gix")]
+    #[snafu(display("Cannot {op} through gitoxide backend, use git backend instead"))]
+    Unsupported { op: &'static str },
+}
+
+#[cfg(test)]
+pub use fake::*;
+
+#[cfg(test)]
+mod fake {
+    use super::*;
+
+    use std::{cell::RefCell, collections::HashMap};
+
+    /// In-memory version control backend for unit tests.
+    ///
+    /// Keeps repositories as plain data keyed by their location, and
+    /// records every operation it performs.
+    #[derive(Debug, Default)]
+    pub struct FakeVcs {
+        repos: RefCell<HashMap<PathBuf, FakeRepo>>,
+        calls: RefCell<Vec<String>>,
+    }
+
+    /// Repository of [`FakeVcs`].
+    #[derive(Debug, Default, Clone, Eq, PartialEq)]
+    pub struct FakeRepo {
+        pub bare: bool,
+        pub branch: String,
+        pub commits: Vec<String>,
+        pub head: Option<String>,
+        pub tags: Vec<(String, String)>,
+        pub refs: Vec<(String, String)>,
+        pub remotes: Vec<(String, String)>,
+        pub files: Vec<PathBuf>,
+        pub modified: Vec<PathBuf>,
+        pub untracked: Vec<PathBuf>,
+        pub config: Vec<(String, String)>,
+    }
+
+    impl FakeRepo {
+        pub fn new(branch: impl Into<String>) -> Self {
+            Self { branch: branch.into(), ..Default::default() }
+        }
+
+        pub fn with_commit(mut self, commit: impl Into<String>) -> Self {
+            let commit = commit.into();
+            self.head = Some(commit.clone());
+            self.commits.push(commit);
+            self
+        }
+
+        pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+            let head = self.head.clone().unwrap_or_default();
+            self.tags.push((tag.into(), head));
+            self
+        }
+
+        /// Point reference of full `name` at current head, e.g.,
+        /// `refs/remotes/origin/master`.
+        pub fn with_ref(mut self, name: impl Into<String>) -> Self {
+            let head = self.head.clone().unwrap_or_default();
+            self.refs.push((name.into(), head));
+            self
+        }
+
+        pub fn with_remote(mut self, name: impl Into<String>, url: impl Into<String>) -> Self {
+            self.remotes.push((name.into(), url.into()));
+            self
+        }
+
+        pub fn with_modified(mut self, path: impl Into<PathBuf>) -> Self {
+            self.modified.push(path.into());
+            self
+        }
+
+        pub fn with_untracked(mut self, path: impl Into<PathBuf>) -> Self {
+            self.untracked.push(path.into());
+            self
+        }
+
+        /// Position of `rev` in linear history of commits.
+        fn position(&self, rev: &str) -> Result<usize, VcsError> {
+            let commit = match rev {
+                "HEAD" => self.head.as_deref(),
+                _ => self
+                    .refs
+                    .iter()
+                    .find(|(name, _)| name == rev)
+                    .map(|(_, commit)| commit.as_str()),
+            }
+            .unwrap_or(rev);
+            self.commits
+                .iter()
+                .position(|known| known.starts_with(commit))
+                .ok_or_else(|| VcsError::rejected(format!("Unknown revision '{rev}'")))
+        }
+    }
+
+    impl FakeVcs {
+        pub fn new() -> Self {
+            Default::default()
+        }
+
+        pub fn with_repo(self, path: impl Into<PathBuf>, repo: FakeRepo) -> Self {
+            self.repos.borrow_mut().insert(path.into(), repo);
+            self
+        }
+
+        /// Get copy of repository at `path`.
+        pub fn repo(&self, path: &Path) -> Option<FakeRepo> {
+            self.repos.borrow().get(path).cloned()
+        }
+
+        /// Operations performed so far, e.g., `checkout /repos/vim 1a2b3c4`.
+        pub fn calls(&self) -> Vec<String> {
+            self.calls.borrow().clone()
+        }
+
+        fn record(&self, op: &str, path: &Path, args: &[&str]) {
+            let mut call = format!("{op} {}", path.display());
+            for arg in args {
+                call.push(' ');
+                call.push_str(arg);
+            }
+            self.calls.borrow_mut().push(call);
+        }
+
+        fn with_existing<T>(
+            &self,
+            repo: &RepoPaths,
+            callback: impl FnOnce(&mut FakeRepo) -> Result<T, VcsError>,
+        ) -> Result<T, VcsError> {
+            let mut repos = self.repos.borrow_mut();
+            let path = repo.root().display();
+            let repo = repos
+                .get_mut(repo.root())
+                .ok_or_else(|| VcsError::rejected(format!("No repository at '{path}'")))?;
+            callback(repo)
+        }
+    }
+
+    impl Vcs for FakeVcs {
+        fn init(&self, path: &Path, branch: &str, bare: bool) -> Result<(), VcsError> {
+            self.record("init", path, &[branch]);
+            let repo = FakeRepo { bare, ..FakeRepo::new(branch) };
+            self.repos.borrow_mut().insert(path.into(), repo);
+            Ok(())
+        }
+
+        fn clone_repo(
+            &self,
+            url: &str,
+            path: &Path,
+            branch: &str,
+            bare: bool,
+        ) -> Result<(), VcsError> {
+            self.record("clone", path, &[url, branch]);
+            let repo = FakeRepo { bare, ..FakeRepo::new(branch).with_remote("origin", url) };
+            self.repos.borrow_mut().insert(path.into(), repo);
+            Ok(())
+        }
+
+        fn checkout(&self, repo: &RepoPaths, rev: Option<&str>) -> Result<(), VcsError> {
+            self.record("checkout", repo.root(), rev.as_slice());
+            self.with_existing(repo, |fake| {
+                let Some(rev) = rev else {
+                    return Ok(());
+                };
+
+                let commit = fake
+                    .commits
+                    .iter()
+                    .find(|commit| commit.starts_with(rev))
+                    .cloned()
+                    .ok_or_else(|| VcsError::rejected(format!("Unknown revision '{rev}'")))?;
+                fake.head = Some(commit);
+                Ok(())
+            })
+        }
+
+        fn status(&self, repo: &RepoPaths, untracked: bool) -> Result<StatusReport, VcsError> {
+            self.record("status", repo.root(), &[]);
+            self.with_existing(repo, |fake| {
+                let hidden = fake
+                    .config
+                    .iter()
+                    .any(|(key, value)| key == "status.showUntrackedFiles" && value == "no");
+                let mut entries = fake
+                    .modified
+                    .iter()
+                    .map(|path| StatusEntry::Changed {
+                        index: FileStatus::Unmodified,
+                        worktree: FileStatus::Modified,
+                        path: path.clone(),
+                    })
+                    .collect::<Vec<_>>();
+                if untracked || !hidden {
+                    entries.extend(
+                        fake.untracked
+                            .iter()
+                            .map(|path| StatusEntry::Untracked { path: path.clone() }),
+                    );
+                }
+                let branch = BranchStatus {
+                    oid: fake.head.clone(),
+                    head: Some(fake.branch.clone()),
+                    ..Default::default()
+                };
+
+                Ok(StatusReport { branch, entries })
+            })
+        }
+
+        fn ahead_behind(
+            &self,
+            repo: &RepoPaths,
+            left: &str,
+            right: &str,
+        ) -> Result<AheadBehind, VcsError> {
+            self.with_existing(repo, |fake| {
+                let (left, right) = (fake.position(left)?, fake.position(right)?);
+                let ahead = left.saturating_sub(right) as u64;
+                let behind = right.saturating_sub(left) as u64;
+                Ok(AheadBehind { ahead, behind })
+            })
+        }
+
+        fn refs(&self, repo: &RepoPaths, patterns: &[&str]) -> Result<Vec<RefRecord>, VcsError> {
+            self.with_existing(repo, |fake| {
+                let tags =
+                    fake.tags.iter().map(|(tag, commit)| (format!("refs/tags/{tag}"), commit));
+                let refs = fake.refs.iter().map(|(name, commit)| (name.clone(), commit));
+                let records = tags
+                    .chain(refs)
+                    .filter(|(name, _)| {
+                        patterns.is_empty()
+                            || patterns.iter().any(|pattern| {
+                                name == pattern || name.starts_with(&format!("{pattern}/"))
+                            })
+                    })
+                    .map(|(name, commit)| RefRecord {
+                        name,
+                        oid: commit.clone(),
+                        peeled: None,
+                        upstream: None,
+                    })
+                    .collect();
+                Ok(records)
+            })
+        }
+
+        fn commit(&self, repo: &RepoPaths, message: Option<&str>) -> Result<(), VcsError> {
+            self.record("commit", repo.root(), message.as_slice());
+            self.with_existing(repo, |fake| {
+                let commit = format!("{:040x}", fake.commits.len() + 1);
+                fake.commits.push(commit.clone());
+                fake.head = Some(commit);
+                Ok(())
+            })
+        }
+
+        fn push(&self, repo: &RepoPaths, remote: &str, branch: &str) -> Result<(), VcsError> {
+            self.record("push", repo.root(), &[remote, branch]);
+            self.with_existing(repo, |_| Ok(()))
+        }
+
+        fn pull(&self, repo: &RepoPaths, remote: &str, branch: &str) -> Result<(), VcsError> {
+            self.record("pull", repo.root(), &[remote, branch]);
+            self.with_existing(repo, |_| Ok(()))
+        }
+
+        fn ls_files(&self, repo: &RepoPaths) -> Result<Vec<PathBuf>, VcsError> {
+            self.record("ls-files", repo.root(), &[]);
+            self.with_existing(repo, |fake| Ok(fake.files.clone()))
+        }
+
+        fn rev_info(&self, repo: &RepoPaths) -> Result<RevInfo, VcsError> {
+            self.with_existing(repo, |fake| {
+                let commit =
+                    fake.head.clone().ok_or_else(|| VcsError::rejected("No commits yet"))?;
+                let tags = fake
+                    .tags
+                    .iter()
+                    .filter(|(_, target)| *target == commit)
+                    .map(|(tag, _)| tag.clone())
+                    .collect::<Vec<_>>();
+                let nearest_tag = tags.first().cloned();
+                Ok(RevInfo { commit, tags, nearest_tag })
+            })
+        }
+
+        fn has_commit(&self, repo: &RepoPaths, commit: &str) -> Result<bool, VcsError> {
+            self.with_existing(repo, |fake| {
+                Ok(fake.commits.iter().any(|known| known.starts_with(commit)))
+            })
+        }
+
+        fn remote_url(&self, repo: &RepoPaths, remote: &str) -> Result<String, VcsError> {
+            self.with_existing(repo, |fake| {
+                fake.remotes
+                    .iter()
+                    .find(|(name, _)| name == remote)
+                    .map(|(_, url)| url.clone())
+                    .ok_or_else(|| VcsError::rejected(format!("No such remote '{remote}'")))
+            })
+        }
+
+        fn set_config(&self, repo: &RepoPaths, key: &str, value: &str) -> Result<(), VcsError> {
+            self.record("config", repo.root(), &[key, value]);
+            self.with_existing(repo, |fake| {
+                fake.config.retain(|(name, _)| name != key);
+                fake.config.push((key.into(), value.into()));
+                Ok(())
+            })
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::testenv::FixtureHarness;
+
     use pretty_assertions::assert_eq;
     use rstest::rstest;
     use snafu::{report, Whatever};
+    use std::cell::Cell;
 
     #[rstest]
     #[report]
@@ -96,6 +958,335 @@ fn git_run_return_err() {
         let mut git = Git::new();
         git.with_args(["unknown-cmd", "--fail"]);
         let result = git.run();
-        assert!(matches!(result.unwrap_err().0, InnerGitError::GitBin { .. }));
+        assert!(matches!(result.unwrap_err().0, InnerVcsError::GitBin { .. }));
+    }
+
+    #[rstest]
+    fn git_run_clear_args_on_failure() {
+        let mut git = Git::new();
+        git.with_args(["unknown-cmd", "--fail"]);
+        let _ = git.run();
+        assert!(git.args.is_empty());
+    }
+
+    #[rstest]
+    fn git_capture_return_output_and_status() -> Result<(), Whatever> {
+        let mut git = Git::new();
+        git.with_args(["unknown-cmd", "--fail"]);
+        let output = git.capture().with_whatever_context(|_| "Failed to capture Git output")?;
+        assert!(!output.status.success());
+        assert!(output.stdout.is_empty());
+        assert!(!output.stderr.is_empty());
+        assert!(git.args.is_empty());
+
+        git.with_arg("--version");
+        let output = git.capture().with_whatever_context(|_| "Failed to capture Git output")?;
+        assert!(output.status.success());
+        assert!(output.stdout.starts_with("git version"));
+
+        Ok(())
+    }
+
+    #[rstest]
+    fn git_stream_return_exit_err() {
+        let mut git = Git::new();
+        git.with_args(["unknown-cmd", "--fail"]);
+        let result = git.stream("vim");
+        assert!(matches!(result.unwrap_err().0, InnerVcsError::Exit { .. }));
+        assert!(git.args.is_empty());
+    }
+
+    #[rstest]
+    fn git_interactive_return_exit_err() {
+        let mut git = Git::new();
+        git.with_args(["unknown-cmd", "--fail"]);
+        let result = git.interactive();
+        assert!(matches!(result.unwrap_err().0, InnerVcsError::Exit { .. }));
+        assert!(git.args.is_empty());
+    }
+
+    #[rstest]
+    #[report]
+    fn git_commit_with_message() -> Result<(), Whatever> {
+        let harness = FixtureHarness::open()?;
+        let path = harness.as_path().join("vim");
+        let repo = RepoPaths::normal(&path);
+        let git = Git::new();
+        git.init(&path, "main", false).with_whatever_context(|_| "Failed to init")?;
+        git.set_config(&repo, "user.name", "ocd").with_whatever_context(|_| "Failed to config")?;
+        git.set_config(&repo, "user.email", "ocd@localhost")
+            .with_whatever_context(|_| "Failed to config")?;
+        std::fs::write(path.join("vimrc"), "").with_whatever_context(|_| "Failed to write")?;
+        git.run_on(&repo, ["add", "vimrc"]).with_whatever_context(|_| "Failed to stage")?;
+        git.commit(&repo, Some("Add vimrc")).with_whatever_context(|_| "Failed to commit")?;
+        let subject = git
+            .run_on(&repo, ["log", "--format=%s"])
+            .with_whatever_context(|_| "Failed to read log")?;
+        assert_eq!(subject, "Add vimrc\n");
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::plain("remote: done\nup to date\n", "[vim] remote: done\n[vim] up to date\n")]
+    #[case::no_newline("up to date", "[vim] up to date\n")]
+    #[case::progress(
+        "Receiving objects:  50%\rReceiving objects: 100%, done.\n",
+        "[vim] Receiving objects: 100%, done.\n"
+    )]
+    #[case::trailing_cr("Counting: 1\rCounting: 2\r\n", "[vim] Counting: 2\n")]
+    #[case::empty_lines("a\n\nb\n", "[vim] a\n[vim] b\n")]
+    fn prefix_lines_label_each_line(
+        #[case] input: &str,
+        #[case] expect: &str,
+    ) -> Result<(), Whatever> {
+        let mut output = Vec::new();
+        prefix_lines(input.as_bytes(), "vim", &mut output)
+            .with_whatever_context(|_| "Failed to prefix lines")?;
+        assert_eq!(String::from_utf8_lossy(&output), expect);
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::normal(
+        RepoPaths::normal("/repos/vim"),
+        vec!["--git-dir", "/repos/vim/.git", "--work-tree", "/repos/vim"],
+        "/repos/vim",
+    )]
+    #[case::bare_alias(
+        RepoPaths::bare_alias("/repos/dots", "/home/awkless"),
+        vec!["--git-dir", "/repos/dots", "--work-tree", "/home/awkless"],
+        "/home/awkless",
+    )]
+    #[case::bare(RepoPaths::bare("/repos/dots"), vec!["--git-dir", "/repos/dots"], "/repos/dots")]
+    fn git_for_repo_command_use_explicit_paths(
+        #[case] repo: RepoPaths,
+        #[case] expect_args: Vec<&str>,
+        #[case] expect_dir: &str,
+    ) {
+        let cmd = Git::for_repo(&repo).command();
+        let args = cmd.get_args().map(|arg| arg.to_string_lossy()).collect::<Vec<_>>();
+        assert_eq!(args, expect_args);
+        assert_eq!(cmd.get_current_dir(), Some(Path::new(expect_dir)));
+
+        let removed = cmd
+            .get_envs()
+            .filter(|(_, value)| value.is_none())
+            .map(|(key, _)| key.to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(removed.len(), SCRUBBED_GIT_ENV.len());
+        assert!(removed.iter().all(|var| SCRUBBED_GIT_ENV.contains(&var.as_str())));
+    }
+
+    #[rstest]
+    #[report]
+    fn git_for_repo_command_resolve_relative_paths() -> Result<(), Whatever> {
+        let cwd = std::env::current_dir().with_whatever_context(|_| "Failed to get cwd")?;
+        let cmd = Git::for_repo(&RepoPaths::bare_alias("repos/dots", "home")).command();
+        let args = cmd.get_args().map(|arg| arg.to_string_lossy()).collect::<Vec<_>>();
+        let (git_dir, work_tree) = (cwd.join("repos/dots"), cwd.join("home"));
+        let expect = vec![
+            "--git-dir".into(),
+            git_dir.to_string_lossy(),
+            "--work-tree".into(),
+            work_tree.to_string_lossy(),
+        ];
+        assert_eq!(args, expect);
+        assert_eq!(cmd.get_current_dir(), Some(cwd.join("home").as_path()));
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[report]
+    fn git_for_repo_run_in_work_tree() -> Result<(), Whatever> {
+        let harness = FixtureHarness::open()?;
+        let path = harness.as_path().join("vim");
+        Git::new().init(&path, "main", false).with_whatever_context(|_| "Failed to init")?;
+        let mut git = Git::for_repo(&RepoPaths::normal(&path));
+        git.with_args(["rev-parse", "--show-prefix"]);
+        let result = git.run().with_whatever_context(|_| "Failed to run Git binary")?;
+        assert_eq!(result, "\n");
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[report]
+    fn git_status_hide_untracked_unless_requested() -> Result<(), Whatever> {
+        let harness = FixtureHarness::open()?;
+        let git_dir = harness.as_path().join("dots");
+        let home = harness.as_path().join("home");
+        std::fs::create_dir(&home).with_whatever_context(|_| "Failed to create home")?;
+        std::fs::write(home.join("bashrc"), "").with_whatever_context(|_| "Failed to write")?;
+        let git = Git::new();
+        git.init(&git_dir, "main", true).with_whatever_context(|_| "Failed to init")?;
+        git.set_config(&RepoPaths::bare(&git_dir), "status.showUntrackedFiles", "no")
+            .with_whatever_context(|_| "Failed to set config")?;
+
+        let repo = RepoPaths::bare_alias(&git_dir, &home);
+        let hidden = git.status(&repo, false).with_whatever_context(|_| "Failed to get status")?;
+        assert!(hidden.is_clean());
+        let shown = git.status(&repo, true).with_whatever_context(|_| "Failed to get status")?;
+        assert_eq!(shown.entries, vec![StatusEntry::Untracked { path: "bashrc".into() }]);
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[report]
+    fn git_status_return_parsed_report() -> Result<(), Whatever> {
+        let harness = FixtureHarness::open()?;
+        let path = harness.as_path().join("vim");
+        Git::new().init(&path, "main", false).with_whatever_context(|_| "Failed to init")?;
+        std::fs::write(path.join("a b.txt"), "")
+            .with_whatever_context(|_| "Failed to write file")?;
+        let result = Git::new()
+            .status(&RepoPaths::normal(&path), false)
+            .with_whatever_context(|_| "Failed to get status report")?;
+        let expect = StatusReport {
+            branch: BranchStatus { head: Some("main".into()), ..Default::default() },
+            entries: vec![StatusEntry::Untracked { path: "a b.txt".into() }],
+        };
+        assert_eq!(result, expect);
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[report]
+    fn git_ahead_behind_and_refs_return_typed_results() -> Result<(), Whatever> {
+        let harness = FixtureHarness::open()?;
+        let path = harness.as_path().join("vim");
+        let repo = RepoPaths::normal(&path);
+        let git = Git::new();
+        git.init(&path, "main", false).with_whatever_context(|_| "Failed to init")?;
+        for (key, value) in [("user.name", "ocd"), ("user.email", "ocd@localhost")] {
+            git.set_config(&repo, key, value).with_whatever_context(|_| "Failed to config")?;
+        }
+        for message in ["First", "Second"] {
+            git.run_on(&repo, ["commit", "--allow-empty", "-m", message])
+                .with_whatever_context(|_| "Failed to commit")?;
+        }
+        git.run_on(&repo, ["tag", "-a", "v1", "-m", "v1", "HEAD~1"])
+            .with_whatever_context(|_| "Failed to tag")?;
+
+        let counts = git
+            .ahead_behind(&repo, "HEAD", "v1")
+            .with_whatever_context(|_| "Failed to count commits")?;
+        assert_eq!(counts, AheadBehind { ahead: 1, behind: 0 });
+        let first =
+            git.run_on(&repo, ["rev-parse", "HEAD~1"]).with_whatever_context(|_| "No HEAD")?;
+        let refs = git.refs(&repo, &["refs/tags"]).with_whatever_context(|_| "Failed to list")?;
+        assert_eq!(refs.len(), 1);
+        assert_eq!(refs[0].name, "refs/tags/v1");
+        assert_eq!(refs[0].target(), first.trim());
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::plain("git version 2.39.5", (2, 39, 5))]
+    #[case::windows("git version 2.45.2.windows.1\n", (2, 45, 2))]
+    #[case::apple("git version 2.39.3 (Apple Git-146)", (2, 39, 3))]
+    #[case::no_patch("git version 2.25", (2, 25, 0))]
+    fn git_version_from_str_return_version(#[case] input: &str, #[case] expect: (u64, u64, u64)) {
+        let (major, minor, patch) = expect;
+        assert_eq!(input.parse(), Ok(GitVersion { major, minor, patch }));
+    }
+
+    #[rstest]
+    #[case::empty("")]
+    #[case::no_prefix("2.39.5")]
+    #[case::no_minor("git version 2")]
+    #[case::garbage("git version two")]
+    fn git_version_from_str_return_err(#[case] input: &str) {
+        assert!(input.parse::<GitVersion>().is_err());
+    }
+
+    #[rstest]
+    fn git_version_ord_compare_components() {
+        let old = GitVersion { major: 2, minor: 24, patch: 9 };
+        let new = GitVersion { major: 2, minor: 100, patch: 0 };
+        assert!(old < MIN_GIT_VERSION);
+        assert!(new > MIN_GIT_VERSION);
+        assert_eq!(MIN_GIT_VERSION.to_string(), "2.25.0");
+    }
+
+    #[rstest]
+    #[report]
+    fn cached_version_probe_once() -> Result<(), Whatever> {
+        let cache = OnceLock::new();
+        let probes = Cell::new(0);
+        let probe = |result: Result<GitVersion, VcsError>| {
+            probes.set(probes.get() + 1);
+            result
+        };
+
+        let result = cached_version(&cache, || probe(Err(VcsError::rejected("no Git"))));
+        assert!(result.is_err());
+        assert_eq!(cache.get(), None);
+
+        let version = GitVersion { major: 2, minor: 39, patch: 5 };
+        let result = cached_version(&cache, || probe(Ok(version)))
+            .with_whatever_context(|_| "Failed to probe version")?;
+        assert_eq!(result, version);
+        let other = GitVersion { major: 2, minor: 45, patch: 2 };
+        let result = cached_version(&cache, || probe(Ok(other)))
+            .with_whatever_context(|_| "Failed to take cached version")?;
+        assert_eq!(result, version);
+        assert_eq!(probes.get(), 2);
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[report]
+    fn git_version_check_return_cached_version() -> Result<(), Whatever> {
+        let version = Git::version_check().with_whatever_context(|_| "Git is too old")?;
+        assert_eq!(Git::version().with_whatever_context(|_| "Failed to probe Git")?, version);
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::minimum(MIN_GIT_VERSION, None)]
+    #[case::newer(GitVersion { major: 3, minor: 0, patch: 0 }, None)]
+    #[case::older_minor(
+        GitVersion { major: 2, minor: 24, patch: 9 },
+        Some("Git 2.24.9 is not supported, install Git 2.25.0 or newer"),
+    )]
+    #[case::older_major(
+        GitVersion { major: 1, minor: 99, patch: 0 },
+        Some("Git 1.99.0 is not supported, install Git 2.25.0 or newer"),
+    )]
+    fn supported_check_reject_old_versions(
+        #[case] version: GitVersion,
+        #[case] expect: Option<&str>,
+    ) {
+        let result = supported_check(version).map_err(|error| error.to_string());
+        match expect {
+            Some(msg) => assert_eq!(result, Err(msg.to_string())),
+            None => assert_eq!(result, Ok(version)),
+        }
+    }
+
+    #[rstest]
+    #[report]
+    fn fake_vcs_checkout_move_head() -> Result<(), Whatever> {
+        let vcs = FakeVcs::new().with_repo(
+            "/repos/sh",
+            FakeRepo::new("master").with_commit("1a2b3c4d").with_tag("v1").with_commit("5e6f7a8b"),
+        );
+        let repo = RepoPaths::normal("/repos/sh");
+        vcs.checkout(&repo, Some("1a2b")).with_whatever_context(|_| "Failed to check out")?;
+        let info = vcs.rev_info(&repo).with_whatever_context(|_| "Failed to get revision")?;
+        assert_eq!(info.commit, "1a2b3c4d");
+        assert_eq!(info.tags, vec!["v1"]);
+        assert!(vcs.checkout(&repo, Some("deadbeef")).is_err());
+        assert_eq!(vcs.calls(), vec!["checkout /repos/sh 1a2b", "checkout /repos/sh deadbeef"]);
+
+        Ok(())
     }
 }
@@ -0,0 +1,963 @@
+// SPDX-FileCopyrightText: 2024 Jason Pena <jasonpena@awkless.com>
+// SPDX-License-Identifier: MIT
+
+use crate::{
+    config::RevInfo,
+    repo::vcs::{
+        AheadBehind, BranchStatus, FileStatus, InnerVcsError, RefRecord, RepoPaths, StatusEntry,
+        StatusReport, Vcs, VcsError,
+    },
+};
+
+use gix::{
+    bstr::{BStr, BString, ByteSlice},
+    commit::describe::SelectRef,
+    config::Source::Local,
+    glob::pattern::Case,
+    index::entry::Mode,
+    progress::Discard,
+    refs::{
+        transaction::{Change, LogChange, PreviousValue, RefEdit},
+        FullName, Target,
+    },
+    Repository,
+};
+use log::debug;
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    error::Error as StdError,
+    fs::{copy, create_dir_all, read, read_dir, read_link, remove_file, write},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::atomic::AtomicBool,
+};
+
+/// In-process Git backend through gitoxide.
+///
+/// Initializes, clones, checks out, and inspects repositories without
+/// spawning a Git binary. Only repositories at local paths can be cloned.
+/// Committing, pushing, and pulling are not supported, and fail with an error
+/// that points to the Git binary backend instead.
+#[derive(Debug, Default, Clone)]
+pub struct Gix;
+
+impl Gix {
+    /// Construct new gitoxide backend.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Vcs for Gix {
+    fn init(&self, path: &Path, branch: &str, bare: bool) -> Result<(), VcsError> {
+        debug!("Initialize '{}' through gitoxide", path.display());
+        let repo = match bare {
+            true => gix::init_bare(path).map_err(native)?,
+            false => gix::init(path).map_err(native)?,
+        };
+        let repo = with_fallback_committer(repo)?;
+        set_head(&repo, Target::Symbolic(branch_ref(branch)?))
+    }
+
+    fn clone_repo(&self, url: &str, path: &Path, branch: &str, bare: bool) -> Result<(), VcsError> {
+        let origin = Path::new(url.strip_prefix("file://").unwrap_or(url));
+        if !origin.is_dir() {
+            return Err(VcsError::rejected(format!(
+                "gitoxide backend can only clone from local paths, not '{url}'"
+            )));
+        }
+
+        debug!("Clone '{url}' into '{}' through gitoxide", path.display());
+        let source = gix::open(origin).map_err(native)?;
+        self.init(path, branch, bare)?;
+        let git_dir = if bare { path.to_path_buf() } else { path.join(".git") };
+        copy_dir(&source.common_dir().join("objects"), &git_dir.join("objects")).map_err(native)?;
+
+        let repo = with_fallback_committer(gix::open(path).map_err(native)?)?;
+        let mut edits = Vec::new();
+        let mut head = None;
+        for reference in source.references().map_err(native)?.all().map_err(native)? {
+            let mut reference = reference.map_err(native)?;
+            let id = reference.peel_to_id_in_place().map_err(native)?.detach();
+            let name = reference.name().as_bstr().to_str_lossy().into_owned();
+            let name = if let Some(short) = name.strip_prefix("refs/heads/") {
+                if short == branch {
+                    head = Some(id);
+                }
+                match bare {
+                    true => name.clone(),
+                    false => format!("refs/remotes/origin/{short}"),
+                }
+            } else if name.starts_with("refs/tags/") {
+                name
+            } else {
+                continue;
+            };
+            edits.push(update_ref(full_name(&name)?, Target::Peeled(id)));
+        }
+
+        let head =
+            head.ok_or_else(|| VcsError::rejected(format!("Remote branch '{branch}' not found")))?;
+        if !bare {
+            edits.push(update_ref(branch_ref(branch)?, Target::Peeled(head)));
+        }
+        repo.edit_references(edits).map_err(native)?;
+
+        let origin = origin.canonicalize().map_err(native)?.to_string_lossy().into_owned();
+        let mut config = gix::config::File::from_path_no_includes(git_dir.join("config"), Local)
+            .map_err(native)?;
+        let set = |config: &mut gix::config::File<'_>, section, sub: &str, key, value: &str| {
+            config
+                .set_raw_value(section, Some(BStr::new(sub)), key, BStr::new(value))
+                .map(drop)
+                .map_err(native)
+        };
+        set(&mut config, "remote", "origin", "url", &origin)?;
+        if !bare {
+            set(&mut config, "remote", "origin", "fetch", "+refs/heads/*:refs/remotes/origin/*")?;
+            set(&mut config, "branch", branch, "remote", "origin")?;
+            set(&mut config, "branch", branch, "merge", &format!("refs/heads/{branch}"))?;
+        }
+        write(git_dir.join("config"), config.to_bstring()).map_err(native)?;
+
+        if !bare {
+            self.checkout(&RepoPaths::normal(path), None)?;
+        }
+
+        Ok(())
+    }
+
+    fn checkout(&self, repo: &RepoPaths, rev: Option<&str>) -> Result<(), VcsError> {
+        let handle = open(repo)?;
+        let work_tree = work_tree(repo, &handle)?;
+
+        // Resolve everything up front, so bad revisions leave work tree alone.
+        let commit = handle
+            .rev_parse_single(rev.unwrap_or("HEAD"))
+            .map_err(native)?
+            .object()
+            .map_err(native)?
+            .peel_to_kind(gix::object::Kind::Commit)
+            .map_err(native)?
+            .into_commit();
+        let head = match rev {
+            Some(rev) => match FullName::try_from(format!("refs/heads/{rev}")) {
+                Ok(branch)
+                    if handle.try_find_reference(branch.as_ref()).map_err(native)?.is_some() =>
+                {
+                    Some(Target::Symbolic(branch))
+                }
+                _ => Some(Target::Peeled(commit.id)),
+            },
+            None => None,
+        };
+        let tree = commit.tree_id().map_err(native)?;
+        let mut index = handle.index_from_tree(&tree).map_err(native)?;
+
+        // Refuse to clobber local changes or files that are not tracked yet, just like Git does.
+        let mut tracked = HashSet::new();
+        if let Some(previous) = handle.try_index().map_err(native)? {
+            if let Some(entry) = tracked_changes(&handle, &work_tree, &previous)?.first() {
+                return Err(VcsError::rejected(format!(
+                    "Local changes to '{}' would be overwritten by checkout, commit or stash them \
+                     first",
+                    work_tree.join(entry.path()).display()
+                )));
+            }
+            tracked.extend(previous.entries().iter().map(|entry| entry.path(&previous).to_owned()));
+        }
+        let wanted = index
+            .entries()
+            .iter()
+            .map(|entry| entry.path(&index).to_owned())
+            .collect::<HashSet<_>>();
+        for path in &wanted {
+            let file = work_tree.join(gix::path::from_bstr(path.as_bstr()));
+            if !tracked.contains(path) && file.symlink_metadata().is_ok() {
+                return Err(VcsError::rejected(format!(
+                    "Untracked file '{}' would be overwritten by checkout",
+                    file.display()
+                )));
+            }
+        }
+
+        // Tracked files were verified to match the index, so overwriting them loses nothing.
+        let options = gix::worktree::state::checkout::Options {
+            fs: gix::fs::Capabilities::probe(handle.git_dir()),
+            overwrite_existing: true,
+            ..Default::default()
+        };
+        let objects = handle.objects.clone().into_arc().map_err(native)?;
+        let outcome = gix::worktree::state::checkout(
+            &mut index,
+            &work_tree,
+            objects,
+            &Discard,
+            &Discard,
+            &AtomicBool::new(false),
+            options,
+        )
+        .map_err(native)?;
+        if let Some(record) = outcome.errors.first() {
+            return Err(VcsError::rejected(format!(
+                "Failed to check out '{}': {}",
+                record.path, record.error
+            )));
+        }
+
+        for path in tracked.difference(&wanted) {
+            remove_file(work_tree.join(gix::path::from_bstr(path.as_bstr()))).map_err(native)?;
+        }
+        index.write(Default::default()).map_err(native)?;
+
+        if let Some(head) = head {
+            set_head(&handle, head)?;
+        }
+
+        Ok(())
+    }
+
+    fn status(&self, repo: &RepoPaths, untracked: bool) -> Result<StatusReport, VcsError> {
+        let handle = open(repo)?;
+        let work_tree = work_tree(repo, &handle)?;
+        let index = handle.index_or_empty().map_err(native)?;
+        let mut entries = tracked_changes(&handle, &work_tree, &index)?;
+
+        let hidden = handle
+            .config_snapshot()
+            .string("status.showUntrackedFiles")
+            .is_some_and(|value| value.as_ref() == "no" || value.as_ref() == "false");
+        if untracked || !hidden {
+            entries.extend(untracked_files(&handle, &work_tree, &index)?);
+        }
+        entries.sort_by(|lhs, rhs| lhs.path().cmp(rhs.path()));
+
+        let head = handle.head().map_err(native)?;
+        let oid = head.id().map(|id| id.to_string());
+        let name = head.referent_name().map(ToOwned::to_owned);
+        let mut branch = BranchStatus {
+            head: name.as_ref().map(|name| name.shorten().to_string()),
+            ..Default::default()
+        };
+        let tracking = name
+            .and_then(|name| {
+                handle.branch_remote_tracking_ref_name(name.as_ref(), gix::remote::Direction::Fetch)
+            })
+            .and_then(Result::ok)
+            .map(|tracking| tracking.into_owned());
+        if let Some(tracking) = tracking {
+            branch.upstream = Some(tracking.shorten().to_string());
+            let exists = handle.try_find_reference(tracking.as_ref()).map_err(native)?.is_some();
+            if exists && oid.is_some() {
+                let tracking = tracking.as_bstr().to_str_lossy();
+                branch.ahead_behind = Some(self.ahead_behind(repo, "HEAD", &tracking)?);
+            }
+        }
+        branch.oid = oid;
+
+        Ok(StatusReport { branch, entries })
+    }
+
+    fn ahead_behind(
+        &self,
+        repo: &RepoPaths,
+        left: &str,
+        right: &str,
+    ) -> Result<AheadBehind, VcsError> {
+        let handle = open(repo)?;
+        let ancestors = |rev: &str| -> Result<HashSet<gix::ObjectId>, VcsError> {
+            let tip = handle.rev_parse_single(rev).map_err(native)?.detach();
+            let mut ancestors = HashSet::new();
+            for info in handle.rev_walk([tip]).all().map_err(native)? {
+                ancestors.insert(info.map_err(native)?.id);
+            }
+            Ok(ancestors)
+        };
+        let (left, right) = (ancestors(left)?, ancestors(right)?);
+
+        Ok(AheadBehind {
+            ahead: left.difference(&right).count() as u64,
+            behind: right.difference(&left).count() as u64,
+        })
+    }
+
+    fn refs(&self, repo: &RepoPaths, patterns: &[&str]) -> Result<Vec<RefRecord>, VcsError> {
+        let handle = open(repo)?;
+        let mut records = Vec::new();
+        for reference in handle.references().map_err(native)?.all().map_err(native)? {
+            let mut reference = reference.map_err(native)?;
+            let name = reference.name().as_bstr().to_str_lossy().into_owned();
+            let matches = patterns.is_empty()
+                || patterns
+                    .iter()
+                    .any(|pattern| name == *pattern || name.starts_with(&format!("{pattern}/")));
+            if !matches {
+                continue;
+            }
+
+            let Some(oid) = reference.target().try_id().map(ToOwned::to_owned) else {
+                continue;
+            };
+            let target = reference.peel_to_id_in_place().map_err(native)?.detach();
+            let upstream = handle
+                .branch_remote_tracking_ref_name(reference.name(), gix::remote::Direction::Fetch)
+                .and_then(Result::ok)
+                .map(|upstream| upstream.as_bstr().to_str_lossy().into_owned());
+            records.push(RefRecord {
+                name,
+                oid: oid.to_string(),
+                peeled: (target != oid).then(|| target.to_string()),
+                upstream,
+            });
+        }
+
+        Ok(records)
+    }
+
+    fn commit(&self, _: &RepoPaths, _: Option<&str>) -> Result<(), VcsError> {
+        Err(VcsError(InnerVcsError::Unsupported { op: "commit" }))
+    }
+
+    fn push(&self, _: &RepoPaths, _: &str, _: &str) -> Result<(), VcsError> {
+        Err(VcsError(InnerVcsError::Unsupported { op: "push" }))
+    }
+
+    fn pull(&self, _: &RepoPaths, _: &str, _: &str) -> Result<(), VcsError> {
+        Err(VcsError(InnerVcsError::Unsupported { op: "pull" }))
+    }
+
+    fn ls_files(&self, repo: &RepoPaths) -> Result<Vec<PathBuf>, VcsError> {
+        let handle = open(repo)?;
+        let index = handle.index_or_empty().map_err(native)?;
+        let files = index
+            .entries()
+            .iter()
+            .map(|entry| gix::path::from_bstr(entry.path(&index)).into_owned())
+            .collect();
+
+        Ok(files)
+    }
+
+    fn rev_info(&self, repo: &RepoPaths) -> Result<RevInfo, VcsError> {
+        let handle = open(repo)?;
+        let head = handle.head_id().map_err(native)?;
+
+        let mut tags = Vec::new();
+        for reference in handle.references().map_err(native)?.tags().map_err(native)? {
+            let mut reference = reference.map_err(native)?;
+            if reference.peel_to_id_in_place().map_err(native)? == head {
+                tags.push(reference.name().shorten().to_string());
+            }
+        }
+        tags.sort();
+
+        let nearest_tag = head
+            .object()
+            .map_err(native)?
+            .try_into_commit()
+            .map_err(native)?
+            .describe()
+            .names(SelectRef::AllTags)
+            .try_resolve()
+            .map_err(native)?
+            .and_then(|resolution| resolution.outcome.name)
+            .map(|name| name.to_string());
+
+        Ok(RevInfo { commit: head.to_string(), tags, nearest_tag })
+    }
+
+    fn has_commit(&self, repo: &RepoPaths, commit: &str) -> Result<bool, VcsError> {
+        let Ok(id) = gix::ObjectId::from_hex(commit.as_bytes()) else {
+            return Ok(false);
+        };
+        let handle = open(repo)?;
+        let header = handle.try_find_header(id).map_err(native)?;
+
+        Ok(header.is_some_and(|header| header.kind() == gix::object::Kind::Commit))
+    }
+
+    fn remote_url(&self, repo: &RepoPaths, remote: &str) -> Result<String, VcsError> {
+        let handle = open(repo)?;
+        let remote = handle.find_remote(remote).map_err(native)?;
+        let url = remote
+            .url(gix::remote::Direction::Fetch)
+            .ok_or_else(|| VcsError::rejected("Remote has no URL"))?;
+
+        Ok(url.to_bstring().to_string())
+    }
+
+    fn set_config(&self, repo: &RepoPaths, key: &str, value: &str) -> Result<(), VcsError> {
+        let (section, name) = key
+            .split_once('.')
+            .ok_or_else(|| VcsError::rejected(format!("Invalid key '{key}'")))?;
+        let (subsection, name) = match name.rsplit_once('.') {
+            Some((subsection, name)) => (Some(BStr::new(subsection)), name),
+            None => (None, name),
+        };
+
+        let path = repo.git_dir.join("config");
+        let mut config =
+            gix::config::File::from_path_no_includes(path.clone(), Local).map_err(native)?;
+        config
+            .set_raw_value(section, subsection, name.to_owned(), BStr::new(value))
+            .map_err(native)?;
+        write(path, config.to_bstring()).map_err(native)?;
+
+        Ok(())
+    }
+}
+
+/// Open repository.
+///
+/// Bare-alias repositories are opened as plain bare repositories, because
+/// gitoxide offers no way to attach a work tree to them. Callers must use the
+/// alias of such repositories explicitly.
+fn open(repo: &RepoPaths) -> Result<Repository, VcsError> {
+    let options = gix::open::Options::default().open_path_as_is(true);
+    with_fallback_committer(gix::open_opts(&repo.git_dir, options).map_err(native)?)
+}
+
+/// Work tree of repository, which bare-alias repositories must name explicitly.
+fn work_tree(repo: &RepoPaths, handle: &Repository) -> Result<PathBuf, VcsError> {
+    repo.work_tree.clone().or_else(|| handle.work_dir().map(Path::to_path_buf)).ok_or_else(|| {
+        VcsError::rejected(format!("Repository '{}' has no work tree", repo.label()))
+    })
+}
+
+/// Compare tracked files of HEAD, index, and work tree.
+///
+/// Files of work tree are hashed in full rather than compared by their stat
+/// data, which is fine for the small repositories dotfiles tend to be.
+fn tracked_changes(
+    handle: &Repository,
+    work_tree: &Path,
+    index: &gix::index::State,
+) -> Result<Vec<StatusEntry>, VcsError> {
+    let mut head = HashMap::new();
+    if let Some(id) = handle.head().map_err(native)?.id() {
+        let tree = id
+            .object()
+            .map_err(native)?
+            .peel_to_kind(gix::object::Kind::Commit)
+            .map_err(native)?
+            .into_commit()
+            .tree_id()
+            .map_err(native)?;
+        let state = handle.index_from_tree(&tree).map_err(native)?;
+        for entry in state.entries() {
+            head.insert(entry.path(&state).to_owned(), (entry.id, entry.mode));
+        }
+    }
+
+    let mut entries = Vec::new();
+    let mut unmerged = BTreeSet::new();
+    for entry in index.entries() {
+        let rela_path = entry.path(index);
+        let path = gix::path::from_bstr(rela_path).into_owned();
+        if entry.stage_raw() != 0 {
+            head.remove(rela_path);
+            unmerged.insert(path);
+            continue;
+        }
+
+        let staged = match head.remove(rela_path) {
+            None => FileStatus::Added,
+            Some(tracked) if tracked != (entry.id, entry.mode) => FileStatus::Modified,
+            Some(_) => FileStatus::Unmodified,
+        };
+        let unstaged = worktree_status(handle, &work_tree.join(&path), entry)?;
+        if staged != FileStatus::Unmodified || unstaged != FileStatus::Unmodified {
+            entries.push(StatusEntry::Changed { index: staged, worktree: unstaged, path });
+        }
+    }
+    entries.extend(unmerged.into_iter().map(|path| StatusEntry::Unmerged {
+        index: FileStatus::Unmerged,
+        worktree: FileStatus::Unmerged,
+        path,
+    }));
+    entries.extend(head.into_keys().map(|path| StatusEntry::Changed {
+        index: FileStatus::Deleted,
+        worktree: FileStatus::Unmodified,
+        path: gix::path::from_bstr(path.as_bstr()).into_owned(),
+    }));
+
+    Ok(entries)
+}
+
+fn worktree_status(
+    handle: &Repository,
+    file: &Path,
+    entry: &gix::index::Entry,
+) -> Result<FileStatus, VcsError> {
+    let metadata = match file.symlink_metadata() {
+        Ok(metadata) => metadata,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(FileStatus::Deleted),
+        Err(error) => return Err(native(error)),
+    };
+    if entry.mode == Mode::COMMIT {
+        return Ok(FileStatus::Unmodified);
+    }
+    if metadata.is_dir() {
+        return Ok(FileStatus::Deleted);
+    }
+    if metadata.file_type().is_symlink() != (entry.mode == Mode::SYMLINK) {
+        return Ok(FileStatus::TypeChanged);
+    }
+
+    let data = match metadata.file_type().is_symlink() {
+        true => gix::path::into_bstr(read_link(file).map_err(native)?).into_owned().into(),
+        false => read(file).map_err(native)?,
+    };
+    let id = gix::objs::compute_hash(handle.object_hash(), gix::object::Kind::Blob, &data);
+
+    Ok(if id == entry.id { FileStatus::Unmodified } else { FileStatus::Modified })
+}
+
+/// List untracked files of work tree.
+///
+/// Like `git status --untracked-files=normal`, directories without tracked
+/// files are listed as a whole instead of descending into them. Ignore rules
+/// of `.gitignore` files, `info/exclude`, and `core.excludesFile` apply.
+fn untracked_files(
+    handle: &Repository,
+    work_tree: &Path,
+    index: &gix::index::State,
+) -> Result<Vec<StatusEntry>, VcsError> {
+    let mut tracked = HashSet::new();
+    let mut tracked_dirs = HashSet::new();
+    for entry in index.entries() {
+        let path = entry.path(index);
+        tracked.insert(path.to_owned());
+        for idx in path.find_iter("/") {
+            tracked_dirs.insert(path[..idx].to_owned());
+        }
+    }
+
+    let excludes_file = handle
+        .config_snapshot()
+        .trusted_path("core.excludesFile")
+        .and_then(Result::ok)
+        .map(|path| path.into_owned());
+    let mut buf = Vec::new();
+    let excludes = gix::ignore::Search::from_git_dir(handle.git_dir(), excludes_file, &mut buf)
+        .map_err(native)?;
+    let mut walk = UntrackedWalk {
+        work_tree,
+        git_dir: handle.git_dir().canonicalize().map_err(native)?,
+        tracked,
+        tracked_dirs,
+        excludes,
+        entries: Vec::new(),
+    };
+    walk.scan(work_tree, BString::default())?;
+
+    Ok(walk.entries)
+}
+
+struct UntrackedWalk<'repo> {
+    work_tree: &'repo Path,
+    git_dir: PathBuf,
+    tracked: HashSet<BString>,
+    tracked_dirs: HashSet<BString>,
+    excludes: gix::ignore::Search,
+    entries: Vec<StatusEntry>,
+}
+
+impl UntrackedWalk<'_> {
+    fn scan(&mut self, dir: &Path, prefix: BString) -> Result<(), VcsError> {
+        for (path, rela_path, is_dir) in self.children(dir, &prefix)? {
+            if self.tracked.contains(&rela_path) {
+                continue;
+            }
+
+            if !is_dir {
+                let path = gix::path::from_bstr(rela_path.as_bstr()).into_owned();
+                self.entries.push(StatusEntry::Untracked { path });
+            } else if self.tracked_dirs.contains(&rela_path) {
+                self.scan(&path, rela_path)?;
+            } else if self.has_untracked(&path, &rela_path)? {
+                let path = gix::path::from_bstr(rela_path.as_bstr()).join("");
+                self.entries.push(StatusEntry::Untracked { path });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn has_untracked(&mut self, dir: &Path, prefix: &BString) -> Result<bool, VcsError> {
+        for (path, rela_path, is_dir) in self.children(dir, prefix)? {
+            if !is_dir || self.has_untracked(&path, &rela_path)? {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Entries of `dir` that are neither ignored nor part of Git itself.
+    fn children(
+        &mut self,
+        dir: &Path,
+        prefix: &BString,
+    ) -> Result<Vec<(PathBuf, BString, bool)>, VcsError> {
+        if let Ok(patterns) = read(dir.join(".gitignore")) {
+            self.excludes.add_patterns_buffer(
+                &patterns,
+                dir.join(".gitignore"),
+                Some(self.work_tree),
+            );
+        }
+
+        let mut children = Vec::new();
+        for entry in read_dir(dir).map_err(native)? {
+            let entry = entry.map_err(native)?;
+            let path = entry.path();
+            let is_dir = entry.file_type().map_err(native)?.is_dir();
+            if entry.file_name() == ".git"
+                || (is_dir && path.canonicalize().ok() == Some(self.git_dir.clone()))
+            {
+                continue;
+            }
+
+            let mut rela_path = prefix.clone();
+            if !rela_path.is_empty() {
+                rela_path.push(b'/');
+            }
+            rela_path.extend_from_slice(&gix::path::into_bstr(PathBuf::from(entry.file_name())));
+            let ignored = self
+                .excludes
+                .pattern_matching_relative_path(rela_path.as_bstr(), Some(is_dir), Case::Sensitive)
+                .is_some_and(|found| !found.pattern.is_negative());
+            if !ignored {
+                children.push((path, rela_path, is_dir));
+            }
+        }
+        children.sort_by(|lhs, rhs| lhs.1.cmp(&rhs.1));
+
+        Ok(children)
+    }
+}
+
+/// Give repository a committer for its reflogs if user has not configured one.
+///
+/// Git binary falls back to an identity derived from the system in this case,
+/// while gitoxide refuses to update references at all.
+fn with_fallback_committer(mut repo: Repository) -> Result<Repository, VcsError> {
+    if repo.committer().is_none() {
+        let mut config = repo.config_snapshot_mut();
+        config
+            .append_config(["committer.name=ocd", "committer.email=ocd@localhost"], Local)
+            .map_err(native)?;
+        config.commit().map_err(native)?;
+    }
+
+    Ok(repo)
+}
+
+fn set_head(repo: &Repository, target: Target) -> Result<(), VcsError> {
+    let mut edit = update_ref(full_name("HEAD")?, target);
+    edit.deref = false;
+    repo.edit_reference(edit).map(drop).map_err(native)
+}
+
+fn update_ref(name: FullName, new: Target) -> RefEdit {
+    RefEdit {
+        change: Change::Update { log: LogChange::default(), expected: PreviousValue::Any, new },
+        name,
+        deref: false,
+    }
+}
+
+fn branch_ref(branch: &str) -> Result<FullName, VcsError> {
+    full_name(&format!("refs/heads/{branch}"))
+}
+
+fn full_name(name: &str) -> Result<FullName, VcsError> {
+    FullName::try_from(name).map_err(native)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    create_dir_all(to)?;
+    for entry in read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            copy(entry.path(), target)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn native(error: impl Into<Box<dyn StdError + Send + Sync>>) -> VcsError {
+    VcsError(InnerVcsError::Native { source: error.into() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{repo::vcs::Git, testenv::FixtureHarness};
+
+    use pretty_assertions::assert_eq;
+    use rstest::{fixture, rstest};
+    use snafu::{report, ResultExt, Whatever};
+    use std::fs::read_to_string;
+
+    fn git_in(path: &Path, args: &[&str]) -> Result<String, Whatever> {
+        let mut git = Git::new();
+        git.with_arg("-C");
+        git.with_arg(path);
+        git.with_args(["-c", "user.name=ocd", "-c", "user.email=ocd@localhost"]);
+        git.with_args(args);
+        git.run().with_whatever_context(|_| format!("Failed to run git {args:?}"))
+    }
+
+    /// Harness with `origin` repository holding two commits, where first one
+    /// is tagged `v1.0.0` and tracks `vimrc`, and second one adds `bashrc`.
+    #[fixture]
+    fn origin() -> Result<FixtureHarness, Whatever> {
+        let harness = FixtureHarness::open()?;
+        let origin = harness.as_path().join("origin");
+        Git::new().init(&origin, "main", false).with_whatever_context(|_| "Failed to init")?;
+        write(origin.join("vimrc"), "set nu\n").with_whatever_context(|_| "Failed to write")?;
+        git_in(&origin, &["add", "vimrc"])?;
+        git_in(&origin, &["commit", "-qm", "init"])?;
+        git_in(&origin, &["tag", "v1.0.0"])?;
+        write(origin.join("bashrc"), "set -o vi\n").with_whatever_context(|_| "Failed to write")?;
+        git_in(&origin, &["add", "bashrc"])?;
+        git_in(&origin, &["commit", "-qm", "bash"])?;
+
+        Ok(harness)
+    }
+
+    fn clone_normal(harness: &FixtureHarness) -> Result<RepoPaths, Whatever> {
+        let origin = harness.as_path().join("origin");
+        let clone = harness.as_path().join("clone");
+        Gix::new()
+            .clone_repo(&origin.to_string_lossy(), &clone, "main", false)
+            .with_whatever_context(|_| "Failed to clone")?;
+
+        Ok(RepoPaths::normal(clone))
+    }
+
+    fn clone_bare_alias(harness: &FixtureHarness, name: &str) -> Result<RepoPaths, Whatever> {
+        let origin = harness.as_path().join("origin");
+        let bare = harness.as_path().join(format!("{name}.git"));
+        let home = harness.as_path().join(name);
+        create_dir_all(&home).with_whatever_context(|_| "Failed to create home")?;
+        Gix::new()
+            .clone_repo(&origin.to_string_lossy(), &bare, "main", true)
+            .with_whatever_context(|_| "Failed to clone bare")?;
+
+        Ok(RepoPaths::bare_alias(bare, home))
+    }
+
+    #[rstest]
+    #[report]
+    fn gix_clone_check_out_branch(
+        origin: Result<FixtureHarness, Whatever>,
+    ) -> Result<(), Whatever> {
+        let harness = origin?;
+        let repo = clone_normal(&harness)?;
+        let vcs = Gix::new();
+        let vimrc = read_to_string(repo.root().join("vimrc"))
+            .with_whatever_context(|_| "Failed to read vimrc")?;
+        assert_eq!(vimrc, "set nu\n");
+        let files = vcs.ls_files(&repo).with_whatever_context(|_| "Failed to list files")?;
+        assert_eq!(files, vec![PathBuf::from("bashrc"), PathBuf::from("vimrc")]);
+        let url = harness.as_path().join("origin").canonicalize().whatever_context("No origin")?;
+        let result = vcs.remote_url(&repo, "origin").with_whatever_context(|_| "No remote")?;
+        assert_eq!(result, url.to_string_lossy());
+        let info = vcs.rev_info(&repo).with_whatever_context(|_| "Failed to get revision")?;
+        assert!(info.tags.is_empty());
+        assert_eq!(info.nearest_tag.as_deref(), Some("v1.0.0"));
+        assert!(vcs.has_commit(&repo, &info.commit).whatever_context("Failed to find commit")?);
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[report]
+    fn gix_status_report_staged_and_unstaged_changes(
+        origin: Result<FixtureHarness, Whatever>,
+    ) -> Result<(), Whatever> {
+        let harness = origin?;
+        let repo = clone_normal(&harness)?;
+        let vcs = Gix::new();
+        let status = vcs.status(&repo, false).with_whatever_context(|_| "Failed to get status")?;
+        assert!(status.is_clean());
+        assert_eq!(status.branch.head.as_deref(), Some("main"));
+        assert_eq!(status.branch.upstream.as_deref(), Some("origin/main"));
+        assert_eq!(status.branch.ahead_behind, Some(AheadBehind { ahead: 0, behind: 0 }));
+
+        let root = repo.root().to_path_buf();
+        write(root.join("vimrc"), "set rnu\n").with_whatever_context(|_| "Failed to write")?;
+        git_in(&root, &["add", "vimrc"])?;
+        write(root.join("bashrc"), "").with_whatever_context(|_| "Failed to write")?;
+        create_dir_all(root.join("nvim")).with_whatever_context(|_| "Failed to create dir")?;
+        write(root.join("nvim/init.lua"), "").with_whatever_context(|_| "Failed to write")?;
+        write(root.join("zshrc"), "").with_whatever_context(|_| "Failed to write")?;
+        write(root.join(".gitignore"), "zshrc\n").with_whatever_context(|_| "Failed to write")?;
+        let status = vcs.status(&repo, false).with_whatever_context(|_| "Failed to get status")?;
+        let expect = vec![
+            StatusEntry::Untracked { path: ".gitignore".into() },
+            StatusEntry::Changed {
+                index: FileStatus::Unmodified,
+                worktree: FileStatus::Modified,
+                path: "bashrc".into(),
+            },
+            StatusEntry::Untracked { path: "nvim/".into() },
+            StatusEntry::Changed {
+                index: FileStatus::Modified,
+                worktree: FileStatus::Unmodified,
+                path: "vimrc".into(),
+            },
+        ];
+        assert_eq!(status.entries, expect);
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[report]
+    fn gix_status_bare_alias_hide_untracked_unless_requested(
+        origin: Result<FixtureHarness, Whatever>,
+    ) -> Result<(), Whatever> {
+        let harness = origin?;
+        let repo = clone_bare_alias(&harness, "home")?;
+        let vcs = Gix::new();
+        vcs.checkout(&repo, None).with_whatever_context(|_| "Failed to check out")?;
+        vcs.set_config(&repo, "status.showUntrackedFiles", "no")
+            .with_whatever_context(|_| "Failed to set config")?;
+        let home = repo.work_tree.clone().unwrap_or_default();
+        write(home.join("zshrc"), "").with_whatever_context(|_| "Failed to write")?;
+        remove_file(home.join("bashrc")).with_whatever_context(|_| "Failed to remove")?;
+
+        let bashrc = StatusEntry::Changed {
+            index: FileStatus::Unmodified,
+            worktree: FileStatus::Deleted,
+            path: "bashrc".into(),
+        };
+        let hidden = vcs.status(&repo, false).with_whatever_context(|_| "Failed to get status")?;
+        assert_eq!(hidden.entries, vec![bashrc.clone()]);
+        let shown = vcs.status(&repo, true).with_whatever_context(|_| "Failed to get status")?;
+        assert_eq!(shown.entries, vec![bashrc, StatusEntry::Untracked { path: "zshrc".into() }]);
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::tag("v1.0.0")]
+    #[case::relative("HEAD~1")]
+    #[report]
+    fn gix_checkout_detach_at_rev(
+        origin: Result<FixtureHarness, Whatever>,
+        #[case] rev: &str,
+    ) -> Result<(), Whatever> {
+        let harness = origin?;
+        let repo = clone_bare_alias(&harness, "home")?;
+        let vcs = Gix::new();
+        vcs.checkout(&repo, None).with_whatever_context(|_| "Failed to check out")?;
+        let home = repo.work_tree.clone().unwrap_or_default();
+        vcs.checkout(&repo, Some(rev)).with_whatever_context(|_| "Failed to check out rev")?;
+        assert!(!home.join("bashrc").exists());
+        let status = vcs.status(&repo, false).with_whatever_context(|_| "Failed to get status")?;
+        assert!(status.is_clean());
+        assert_eq!(status.branch.head, None);
+        let info = vcs.rev_info(&repo).with_whatever_context(|_| "Failed to get revision")?;
+        assert_eq!(info.tags, vec!["v1.0.0"]);
+
+        vcs.checkout(&repo, Some("main")).with_whatever_context(|_| "Failed to check out")?;
+        assert!(home.join("bashrc").exists());
+        let status = vcs.status(&repo, false).with_whatever_context(|_| "Failed to get status")?;
+        assert_eq!(status.branch.head.as_deref(), Some("main"));
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[report]
+    fn gix_checkout_bad_rev_leave_work_tree_alone(
+        origin: Result<FixtureHarness, Whatever>,
+    ) -> Result<(), Whatever> {
+        let harness = origin?;
+        let repo = clone_normal(&harness)?;
+        let vcs = Gix::new();
+        let result = vcs.checkout(&repo, Some("HEAD~5"));
+        assert!(matches!(result.map_err(|err| err.0), Err(InnerVcsError::Native { .. })));
+        assert!(repo.root().join("bashrc").exists());
+        let status = vcs.status(&repo, false).with_whatever_context(|_| "Failed to get status")?;
+        assert!(status.is_clean());
+        assert_eq!(status.branch.head.as_deref(), Some("main"));
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[report]
+    fn gix_checkout_refuse_to_overwrite_local_changes(
+        origin: Result<FixtureHarness, Whatever>,
+    ) -> Result<(), Whatever> {
+        let harness = origin?;
+        let repo = clone_normal(&harness)?;
+        let vcs = Gix::new();
+        let bashrc = repo.root().join("bashrc");
+        write(&bashrc, "set -o emacs\n").with_whatever_context(|_| "Failed to write")?;
+        let result = vcs.checkout(&repo, Some("v1.0.0"));
+        assert!(matches!(result.map_err(|err| err.0), Err(InnerVcsError::Rejected { .. })));
+        let result = read_to_string(&bashrc).with_whatever_context(|_| "Failed to read")?;
+        assert_eq!(result, "set -o emacs\n");
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[report]
+    fn gix_checkout_refuse_to_overwrite_untracked_files(
+        origin: Result<FixtureHarness, Whatever>,
+    ) -> Result<(), Whatever> {
+        let harness = origin?;
+        let repo = clone_bare_alias(&harness, "home")?;
+        let vimrc = repo.work_tree.clone().unwrap_or_default().join("vimrc");
+        write(&vimrc, "").with_whatever_context(|_| "Failed to write")?;
+        let result = Gix::new().checkout(&repo, None);
+        assert!(matches!(result.map_err(|err| err.0), Err(InnerVcsError::Rejected { .. })));
+        assert_eq!(read_to_string(&vimrc).with_whatever_context(|_| "Failed to read")?, "");
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[report]
+    fn gix_commit_push_pull_return_err_unsupported(
+        origin: Result<FixtureHarness, Whatever>,
+    ) -> Result<(), Whatever> {
+        let harness = origin?;
+        let repo = clone_normal(&harness)?;
+        let vcs = Gix::new();
+        let results = [
+            vcs.commit(&repo, Some("message")),
+            vcs.push(&repo, "origin", "main"),
+            vcs.pull(&repo, "origin", "main"),
+        ];
+        for result in results {
+            assert!(matches!(result.map_err(|err| err.0), Err(InnerVcsError::Unsupported { .. })));
+        }
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[report]
+    fn gix_clone_remote_url_return_err() -> Result<(), Whatever> {
+        let harness = FixtureHarness::open()?;
+        let path = harness.as_path().join("clone");
+        let result = Gix::new().clone_repo("https://some/url.git", &path, "main", false);
+        assert!(matches!(result.map_err(|err| err.0), Err(InnerVcsError::Rejected { .. })));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,750 @@
+// SPDX-FileCopyrightText: 2024 Jason Pena <jasonpena@awkless.com>
+// SPDX-License-Identifier: MIT
+
+use crate::repo::vcs::{InnerVcsError, VcsError};
+
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult, Write},
+    path::PathBuf,
+};
+
+/// Format to pass to `git for-each-ref --format` for [`parse_refs`].
+pub const REF_FORMAT: &str = "%(refname)%00%(objectname)%00%(*objectname)%00%(upstream)";
+
+/// Parsed output of `git status --porcelain=v2 --branch -z`.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct StatusReport {
+    pub branch: BranchStatus,
+    pub entries: Vec<StatusEntry>,
+}
+
+impl StatusReport {
+    /// Check if work tree and index have no changes besides ignored files.
+    pub fn is_clean(&self) -> bool {
+        self.entries.iter().all(|entry| matches!(entry, StatusEntry::Ignored { .. }))
+    }
+
+    /// Check if any tracked file has changes, ignoring untracked files.
+    pub fn has_tracked_changes(&self) -> bool {
+        self.entries.iter().any(|entry| {
+            !matches!(entry, StatusEntry::Untracked { .. } | StatusEntry::Ignored { .. })
+        })
+    }
+
+    /// Render report like `git status --short --branch`.
+    pub fn to_short(&self) -> String {
+        let mut output = format!("## {}\n", self.branch);
+        for entry in &self.entries {
+            let (index, worktree) = entry.codes();
+            let _ = match entry {
+                StatusEntry::Renamed { path, orig_path, .. } => writeln!(
+                    output,
+                    "{index}{worktree} {} -> {}",
+                    orig_path.display(),
+                    path.display()
+                ),
+                _ => writeln!(output, "{index}{worktree} {}", entry.path().display()),
+            };
+        }
+
+        output
+    }
+
+    /// Render report like `git status`, with changes grouped by their state.
+    pub fn to_long(&self) -> String {
+        let mut output = match &self.branch.head {
+            Some(head) => format!("On branch {head}\n"),
+            None => "HEAD detached\n".into(),
+        };
+        if let (Some(upstream), Some(counts)) = (&self.branch.upstream, self.branch.ahead_behind) {
+            let _ = writeln!(
+                output,
+                "Ahead {} and behind {} of '{upstream}'",
+                counts.ahead, counts.behind
+            );
+        }
+        // Unmerged entries have both index and work tree states, so they get
+        // their own section instead of showing up as staged and unstaged.
+        let merged = self.entries.iter().filter(|entry| !entry.is_unmerged());
+        let staged = merged
+            .clone()
+            .filter_map(|entry| entry.index().map(|status| (status, entry)))
+            .filter(|(status, _)| *status != FileStatus::Unmodified);
+        write_section(&mut output, "Changes to be committed", staged);
+        let unmerged = self.entries.iter().filter(|entry| entry.is_unmerged()).collect::<Vec<_>>();
+        if !unmerged.is_empty() {
+            output.push_str("Unmerged paths:\n");
+            for entry in unmerged {
+                let (index, worktree) = entry.codes();
+                let _ = writeln!(
+                    output,
+                    "    {:<17}{}",
+                    format!("{}:", describe_unmerged(index, worktree)),
+                    entry.path().display()
+                );
+            }
+        }
+        let unstaged = merged
+            .filter_map(|entry| entry.worktree().map(|status| (status, entry)))
+            .filter(|(status, _)| *status != FileStatus::Unmodified);
+        write_section(&mut output, "Changes not staged for commit", unstaged);
+
+        let untracked = self
+            .entries
+            .iter()
+            .filter(|entry| matches!(entry, StatusEntry::Untracked { .. }))
+            .collect::<Vec<_>>();
+        if !untracked.is_empty() {
+            output.push_str("Untracked files:\n");
+            for entry in untracked {
+                let _ = writeln!(output, "    {}", entry.path().display());
+            }
+        }
+
+        if self.is_clean() {
+            output.push_str("Nothing to commit, working tree clean\n");
+        }
+
+        output
+    }
+}
+
+fn write_section<'report>(
+    output: &mut String,
+    title: &str,
+    entries: impl Iterator<Item = (FileStatus, &'report StatusEntry)>,
+) {
+    let mut entries = entries.peekable();
+    if entries.peek().is_none() {
+        return;
+    }
+
+    let _ = writeln!(output, "{title}:");
+    for (status, entry) in entries {
+        let _ = match entry {
+            StatusEntry::Renamed { path, orig_path, .. } => writeln!(
+                output,
+                "    {:<12}{} -> {}",
+                format!("{}:", status.describe()),
+                orig_path.display(),
+                path.display()
+            ),
+            _ => writeln!(
+                output,
+                "    {:<12}{}",
+                format!("{}:", status.describe()),
+                entry.path().display()
+            ),
+        };
+    }
+}
+
+/// Describe unmerged entry by status codes of both sides like `git status`.
+fn describe_unmerged(index: char, worktree: char) -> &'static str {
+    match (index, worktree) {
+        ('D', 'D') => "both deleted",
+        ('A', 'U') => "added by us",
+        ('U', 'D') => "deleted by them",
+        ('U', 'A') => "added by them",
+        ('D', 'U') => "deleted by us",
+        ('A', 'A') => "both added",
+        _ => "both modified",
+    }
+}
+
+/// Branch headers of status report.
+///
+/// Displayed like the branch line of `git status --short --branch`, e.g.,
+/// `main...origin/main [ahead 2, behind 1]`.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct BranchStatus {
+    /// Checked out commit, or `None` if no commits exist yet.
+    pub oid: Option<String>,
+
+    /// Checked out branch, or `None` if HEAD is detached.
+    pub head: Option<String>,
+
+    /// Upstream of checked out branch if any.
+    pub upstream: Option<String>,
+
+    /// Commits ahead and behind of upstream if upstream exists.
+    pub ahead_behind: Option<AheadBehind>,
+}
+
+impl Display for BranchStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match (&self.head, &self.oid) {
+            (Some(head), None) => return write!(f, "No commits yet on {head}"),
+            (Some(head), Some(_)) => write!(f, "{head}")?,
+            (None, _) => write!(f, "HEAD (no branch)")?,
+        }
+
+        if let Some(upstream) = &self.upstream {
+            write!(f, "...{upstream}")?;
+        }
+
+        match self.ahead_behind {
+            Some(AheadBehind { ahead: 0, behind: 0 }) | None => Ok(()),
+            Some(AheadBehind { ahead, behind: 0 }) => write!(f, " [ahead {ahead}]"),
+            Some(AheadBehind { ahead: 0, behind }) => write!(f, " [behind {behind}]"),
+            Some(AheadBehind { ahead, behind }) => write!(f, " [ahead {ahead}, behind {behind}]"),
+        }
+    }
+}
+
+/// Count of commits ahead and behind of another revision.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct AheadBehind {
+    pub ahead: u64,
+    pub behind: u64,
+}
+
+/// Single entry of status report.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum StatusEntry {
+    Changed { index: FileStatus, worktree: FileStatus, path: PathBuf },
+    Renamed { index: FileStatus, worktree: FileStatus, path: PathBuf, orig_path: PathBuf },
+    Unmerged { index: FileStatus, worktree: FileStatus, path: PathBuf },
+    Untracked { path: PathBuf },
+    Ignored { path: PathBuf },
+}
+
+impl StatusEntry {
+    /// State of file in index, or [`None`] if file is not tracked.
+    pub fn index(&self) -> Option<FileStatus> {
+        match self {
+            Self::Changed { index, .. }
+            | Self::Renamed { index, .. }
+            | Self::Unmerged { index, .. } => Some(*index),
+            Self::Untracked { .. } | Self::Ignored { .. } => None,
+        }
+    }
+
+    /// State of file in work tree, or [`None`] if file is not tracked.
+    pub fn worktree(&self) -> Option<FileStatus> {
+        match self {
+            Self::Changed { worktree, .. }
+            | Self::Renamed { worktree, .. }
+            | Self::Unmerged { worktree, .. } => Some(*worktree),
+            Self::Untracked { .. } | Self::Ignored { .. } => None,
+        }
+    }
+
+    /// Check if entry has merge conflicts.
+    pub fn is_unmerged(&self) -> bool {
+        matches!(self, Self::Unmerged { .. })
+    }
+
+    /// Status codes of `git status --short`.
+    fn codes(&self) -> (char, char) {
+        match (self, self.index(), self.worktree()) {
+            (Self::Untracked { .. }, ..) => ('?', '?'),
+            (_, Some(index), Some(worktree)) => (index.code(), worktree.code()),
+            _ => ('!', '!'),
+        }
+    }
+
+    /// Path of file entry refers to.
+    pub fn path(&self) -> &PathBuf {
+        match self {
+            Self::Changed { path, .. }
+            | Self::Renamed { path, .. }
+            | Self::Unmerged { path, .. }
+            | Self::Untracked { path }
+            | Self::Ignored { path } => path,
+        }
+    }
+}
+
+/// State of file in index or work tree.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum FileStatus {
+    Unmodified,
+    Modified,
+    TypeChanged,
+    Added,
+    Deleted,
+    Renamed,
+    Copied,
+    Unmerged,
+}
+
+impl FileStatus {
+    fn code(self) -> char {
+        match self {
+            Self::Unmodified => ' ',
+            Self::Modified => 'M',
+            Self::TypeChanged => 'T',
+            Self::Added => 'A',
+            Self::Deleted => 'D',
+            Self::Renamed => 'R',
+            Self::Copied => 'C',
+            Self::Unmerged => 'U',
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Self::Unmodified => "unmodified",
+            Self::Modified => "modified",
+            Self::TypeChanged => "typechange",
+            Self::Added => "new file",
+            Self::Deleted => "deleted",
+            Self::Renamed => "renamed",
+            Self::Copied => "copied",
+            Self::Unmerged => "unmerged",
+        }
+    }
+
+    fn from_code(code: char) -> Option<Self> {
+        let status = match code {
+            '.' => Self::Unmodified,
+            'M' => Self::Modified,
+            'T' => Self::TypeChanged,
+            'A' => Self::Added,
+            'D' => Self::Deleted,
+            'R' => Self::Renamed,
+            'C' => Self::Copied,
+            'U' => Self::Unmerged,
+            _ => return None,
+        };
+
+        Some(status)
+    }
+}
+
+/// Reference listed by `git for-each-ref --format` with [`REF_FORMAT`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RefRecord {
+    /// Full name of reference, e.g., `refs/heads/main`.
+    pub name: String,
+
+    /// Object reference points at.
+    pub oid: String,
+
+    /// Commit that annotated tag peels to.
+    pub peeled: Option<String>,
+
+    /// Upstream of branch if any.
+    pub upstream: Option<String>,
+}
+
+impl RefRecord {
+    /// Commit that reference ultimately points at.
+    pub fn target(&self) -> &str {
+        self.peeled.as_deref().unwrap_or(&self.oid)
+    }
+}
+
+/// Parse output of `git status --porcelain=v2 --branch -z`.
+///
+/// # Errors
+///
+/// Will fail if any record is malformed.
+pub fn parse_status(output: &str) -> Result<StatusReport, VcsError> {
+    let mut report = StatusReport::default();
+    let mut records = output.split('\0').filter(|record| !record.is_empty());
+    while let Some(record) = records.next() {
+        let malformed = || malformed("status", record);
+        let (kind, rest) = record.split_once(' ').ok_or_else(malformed)?;
+        match kind {
+            "#" => parse_branch_header(&mut report.branch, rest).ok_or_else(malformed)?,
+            "1" => {
+                let fields = rest.splitn(8, ' ').collect::<Vec<_>>();
+                let [xy, _, _, _, _, _, _, path] = fields[..] else {
+                    return Err(malformed());
+                };
+                let (index, worktree) = parse_xy(xy).ok_or_else(malformed)?;
+                report.entries.push(StatusEntry::Changed { index, worktree, path: path.into() });
+            }
+            "2" => {
+                let fields = rest.splitn(9, ' ').collect::<Vec<_>>();
+                let [xy, _, _, _, _, _, _, _, path] = fields[..] else {
+                    return Err(malformed());
+                };
+                let (index, worktree) = parse_xy(xy).ok_or_else(malformed)?;
+                let orig_path = records.next().ok_or_else(malformed)?;
+                report.entries.push(StatusEntry::Renamed {
+                    index,
+                    worktree,
+                    path: path.into(),
+                    orig_path: orig_path.into(),
+                });
+            }
+            "u" => {
+                let fields = rest.splitn(10, ' ').collect::<Vec<_>>();
+                let [xy, _, _, _, _, _, _, _, _, path] = fields[..] else {
+                    return Err(malformed());
+                };
+                let (index, worktree) = parse_xy(xy).ok_or_else(malformed)?;
+                report.entries.push(StatusEntry::Unmerged { index, worktree, path: path.into() });
+            }
+            "?" => report.entries.push(StatusEntry::Untracked { path: rest.into() }),
+            "!" => report.entries.push(StatusEntry::Ignored { path: rest.into() }),
+            _ => return Err(malformed()),
+        }
+    }
+
+    Ok(report)
+}
+
+/// Parse output of `git ls-files -z`.
+pub fn parse_ls_files(output: &str) -> Vec<PathBuf> {
+    output.split('\0').filter(|path| !path.is_empty()).map(PathBuf::from).collect()
+}
+
+/// Parse output of `git rev-list --left-right --count <left>...<right>`.
+///
+/// Commits only reachable from left side count as ahead, and commits only
+/// reachable from right side count as behind.
+///
+/// # Errors
+///
+/// Will fail if output is not a pair of counts.
+pub fn parse_ahead_behind(output: &str) -> Result<AheadBehind, VcsError> {
+    let record = output.trim();
+    let (ahead, behind) = record.split_once('\t').ok_or_else(|| malformed("rev-list", record))?;
+    let ahead = ahead.parse().map_err(|_| malformed("rev-list", record))?;
+    let behind = behind.parse().map_err(|_| malformed("rev-list", record))?;
+
+    Ok(AheadBehind { ahead, behind })
+}
+
+/// Parse output of `git for-each-ref --format` with [`REF_FORMAT`].
+///
+/// # Errors
+///
+/// Will fail if any line does not have all fields of [`REF_FORMAT`].
+pub fn parse_refs(output: &str) -> Result<Vec<RefRecord>, VcsError> {
+    output
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let fields = line.split('\0').collect::<Vec<_>>();
+            let [name, oid, peeled, upstream] = fields[..] else {
+                return Err(malformed("for-each-ref", line));
+            };
+            let non_empty = |field: &str| (!field.is_empty()).then(|| field.to_string());
+
+            Ok(RefRecord {
+                name: name.into(),
+                oid: oid.into(),
+                peeled: non_empty(peeled),
+                upstream: non_empty(upstream),
+            })
+        })
+        .collect()
+}
+
+fn parse_branch_header(branch: &mut BranchStatus, header: &str) -> Option<()> {
+    let (key, value) = header.split_once(' ')?;
+    match key {
+        "branch.oid" => branch.oid = (value != "(initial)").then(|| value.into()),
+        "branch.head" => branch.head = (value != "(detached)").then(|| value.into()),
+        "branch.upstream" => branch.upstream = Some(value.into()),
+        "branch.ab" => {
+            let (ahead, behind) = value.split_once(' ')?;
+            let ahead = ahead.strip_prefix('+')?.parse().ok()?;
+            let behind = behind.strip_prefix('-')?.parse().ok()?;
+            branch.ahead_behind = Some(AheadBehind { ahead, behind });
+        }
+        // Skip headers that newer versions of Git may add.
+        _ => {}
+    }
+
+    Some(())
+}
+
+fn parse_xy(xy: &str) -> Option<(FileStatus, FileStatus)> {
+    let mut codes = xy.chars();
+    let index = FileStatus::from_code(codes.next()?)?;
+    let worktree = FileStatus::from_code(codes.next()?)?;
+    codes.next().is_none().then_some((index, worktree))
+}
+
+fn malformed(what: &'static str, record: &str) -> VcsError {
+    VcsError(InnerVcsError::Porcelain { what, record: record.into() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use snafu::{prelude::*, report, Whatever};
+
+    #[rstest]
+    #[report]
+    fn parse_status_return_report() -> Result<(), Whatever> {
+        let output = [
+            "# branch.oid 2a313db47ac928002693569122c2aef19a3f276d",
+            "# branch.head main",
+            "# branch.upstream origin/main",
+            "# branch.ab +2 -1",
+            "# stash 3",
+            "1 .M N... 100644 100644 100644 587be6b 587be6b .vimrc",
+            "1 A. N... 000000 100644 100644 0000000 587be6b dir/with space.txt",
+            "2 R. N... 100644 100644 100644 587be6b 587be6b R100 c d.txt",
+            "a b.txt",
+            "u UU N... 100644 100644 100644 100644 587be6b 587be6b 587be6b conflict.sh",
+            "? new file",
+            "! target",
+            "",
+        ]
+        .join("\0");
+        let result =
+            parse_status(&output).with_whatever_context(|_| "Failed to parse status output")?;
+        let expect = StatusReport {
+            branch: BranchStatus {
+                oid: Some("2a313db47ac928002693569122c2aef19a3f276d".into()),
+                head: Some("main".into()),
+                upstream: Some("origin/main".into()),
+                ahead_behind: Some(AheadBehind { ahead: 2, behind: 1 }),
+            },
+            entries: vec![
+                StatusEntry::Changed {
+                    index: FileStatus::Unmodified,
+                    worktree: FileStatus::Modified,
+                    path: ".vimrc".into(),
+                },
+                StatusEntry::Changed {
+                    index: FileStatus::Added,
+                    worktree: FileStatus::Unmodified,
+                    path: "dir/with space.txt".into(),
+                },
+                StatusEntry::Renamed {
+                    index: FileStatus::Renamed,
+                    worktree: FileStatus::Unmodified,
+                    path: "c d.txt".into(),
+                    orig_path: "a b.txt".into(),
+                },
+                StatusEntry::Unmerged {
+                    index: FileStatus::Unmerged,
+                    worktree: FileStatus::Unmerged,
+                    path: "conflict.sh".into(),
+                },
+                StatusEntry::Untracked { path: "new file".into() },
+                StatusEntry::Ignored { path: "target".into() },
+            ],
+        };
+        assert_eq!(result, expect);
+        assert!(!result.is_clean());
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[report]
+    fn parse_status_return_initial_detached() -> Result<(), Whatever> {
+        let output = "# branch.oid (initial)\0# branch.head (detached)\0! target\0";
+        let result =
+            parse_status(output).with_whatever_context(|_| "Failed to parse status output")?;
+        assert_eq!(result.branch, BranchStatus::default());
+        assert!(result.is_clean());
+
+        Ok(())
+    }
+
+    fn status_report() -> StatusReport {
+        StatusReport {
+            branch: BranchStatus {
+                oid: Some("2a313db".into()),
+                head: Some("main".into()),
+                upstream: Some("origin/main".into()),
+                ahead_behind: Some(AheadBehind { ahead: 2, behind: 1 }),
+            },
+            entries: vec![
+                StatusEntry::Changed {
+                    index: FileStatus::Added,
+                    worktree: FileStatus::Modified,
+                    path: ".vimrc".into(),
+                },
+                StatusEntry::Renamed {
+                    index: FileStatus::Renamed,
+                    worktree: FileStatus::Unmodified,
+                    path: "c.txt".into(),
+                    orig_path: "a.txt".into(),
+                },
+                StatusEntry::Untracked { path: "notes".into() },
+            ],
+        }
+    }
+
+    #[rstest]
+    fn status_report_to_short_return_short_format() {
+        let expect = indoc! {"
+            ## main...origin/main [ahead 2, behind 1]
+            AM .vimrc
+            R  a.txt -> c.txt
+            ?? notes
+        "};
+        assert_eq!(status_report().to_short(), expect);
+    }
+
+    #[rstest]
+    fn status_report_to_long_group_changes() {
+        let expect = indoc! {"
+            On branch main
+            Ahead 2 and behind 1 of 'origin/main'
+            Changes to be committed:
+                new file:   .vimrc
+                renamed:    a.txt -> c.txt
+            Changes not staged for commit:
+                modified:   .vimrc
+            Untracked files:
+                notes
+        "};
+        assert_eq!(status_report().to_long(), expect);
+        assert!(status_report().has_tracked_changes());
+    }
+
+    #[rstest]
+    fn status_report_to_long_list_unmerged_paths_once() {
+        let mut report = status_report();
+        report.entries.extend([
+            StatusEntry::Unmerged {
+                index: FileStatus::Unmerged,
+                worktree: FileStatus::Unmerged,
+                path: ".bashrc".into(),
+            },
+            StatusEntry::Unmerged {
+                index: FileStatus::Unmerged,
+                worktree: FileStatus::Deleted,
+                path: ".zshrc".into(),
+            },
+        ]);
+        let expect = indoc! {"
+            On branch main
+            Ahead 2 and behind 1 of 'origin/main'
+            Changes to be committed:
+                new file:   .vimrc
+                renamed:    a.txt -> c.txt
+            Unmerged paths:
+                both modified:   .bashrc
+                deleted by them: .zshrc
+            Changes not staged for commit:
+                modified:   .vimrc
+            Untracked files:
+                notes
+        "};
+        assert_eq!(report.to_long(), expect);
+    }
+
+    #[rstest]
+    #[case::initial(Some("main"), None, None, None, "No commits yet on main")]
+    #[case::detached(None, Some("2a313db"), None, None, "HEAD (no branch)")]
+    #[case::no_upstream(Some("main"), Some("2a313db"), None, None, "main")]
+    #[case::in_sync(Some("main"), Some("2a313db"), Some("up"), Some((0, 0)), "main...up")]
+    #[case::ahead(Some("main"), Some("2a313db"), Some("up"), Some((3, 0)), "main...up [ahead 3]")]
+    #[case::behind(Some("main"), Some("2a313db"), Some("up"), Some((0, 4)), "main...up [behind 4]")]
+    fn branch_status_display_header(
+        #[case] head: Option<&str>,
+        #[case] oid: Option<&str>,
+        #[case] upstream: Option<&str>,
+        #[case] counts: Option<(u64, u64)>,
+        #[case] expect: &str,
+    ) {
+        let branch = BranchStatus {
+            oid: oid.map(Into::into),
+            head: head.map(Into::into),
+            upstream: upstream.map(Into::into),
+            ahead_behind: counts.map(|(ahead, behind)| AheadBehind { ahead, behind }),
+        };
+        assert_eq!(branch.to_string(), expect);
+    }
+
+    #[rstest]
+    fn status_report_clean_to_long_say_so() {
+        let report = StatusReport {
+            branch: BranchStatus { head: Some("main".into()), ..Default::default() },
+            entries: vec![StatusEntry::Ignored { path: "target".into() }],
+        };
+        assert_eq!(report.to_long(), "On branch main\nNothing to commit, working tree clean\n");
+        assert!(!report.has_tracked_changes());
+    }
+
+    #[rstest]
+    #[case::unknown_kind("3 foo\0")]
+    #[case::short_changed("1 .M N... .vimrc\0")]
+    #[case::bad_xy("1 XM N... 100644 100644 100644 587be6b 587be6b .vimrc\0")]
+    #[case::missing_orig_path("2 R. N... 100644 100644 100644 587be6b 587be6b R100 c\0")]
+    #[case::bad_ab("# branch.ab 2 1\0")]
+    fn parse_status_return_err(#[case] output: &str) {
+        let result = parse_status(output);
+        assert!(matches!(result.unwrap_err().0, InnerVcsError::Porcelain { .. }));
+    }
+
+    #[rstest]
+    fn parse_ls_files_return_paths() {
+        let result = parse_ls_files(".vimrc\0dir/with space.txt\0new\nline\0");
+        let expect: Vec<PathBuf> =
+            vec![".vimrc".into(), "dir/with space.txt".into(), "new\nline".into()];
+        assert_eq!(result, expect);
+        assert!(parse_ls_files("").is_empty());
+    }
+
+    #[rstest]
+    #[report]
+    fn parse_ahead_behind_return_counts() -> Result<(), Whatever> {
+        let result = parse_ahead_behind("3\t5\n")
+            .with_whatever_context(|_| "Failed to parse rev-list output")?;
+        assert_eq!(result, AheadBehind { ahead: 3, behind: 5 });
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::empty("")]
+    #[case::single("3\n")]
+    #[case::not_number("a\tb\n")]
+    fn parse_ahead_behind_return_err(#[case] output: &str) {
+        let result = parse_ahead_behind(output);
+        assert!(matches!(result.unwrap_err().0, InnerVcsError::Porcelain { .. }));
+    }
+
+    #[rstest]
+    #[report]
+    fn parse_refs_return_records() -> Result<(), Whatever> {
+        let output = [
+            "refs/heads/main\x002a313db\x00\x00refs/remotes/origin/main",
+            "refs/tags/v1\0fb06a50\0f075ebb\0",
+            "refs/tags/v2\0f075ebb\0\0",
+            "",
+        ]
+        .join("\n");
+        let result = parse_refs(&output).with_whatever_context(|_| "Failed to parse refs")?;
+        let expect = vec![
+            RefRecord {
+                name: "refs/heads/main".into(),
+                oid: "2a313db".into(),
+                peeled: None,
+                upstream: Some("refs/remotes/origin/main".into()),
+            },
+            RefRecord {
+                name: "refs/tags/v1".into(),
+                oid: "fb06a50".into(),
+                peeled: Some("f075ebb".into()),
+                upstream: None,
+            },
+            RefRecord {
+                name: "refs/tags/v2".into(),
+                oid: "f075ebb".into(),
+                peeled: None,
+                upstream: None,
+            },
+        ];
+        assert_eq!(result, expect);
+        assert_eq!(result[1].target(), "f075ebb");
+        assert_eq!(result[2].target(), "f075ebb");
+
+        Ok(())
+    }
+
+    #[rstest]
+    fn parse_refs_return_err() {
+        let result = parse_refs("refs/heads/main\x002a313db\n");
+        assert!(matches!(result.unwrap_err().0, InnerVcsError::Porcelain { .. }));
+    }
+}